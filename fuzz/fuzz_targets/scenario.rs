@@ -0,0 +1,12 @@
+#![no_main]
+
+use falling_glyphs::scenario::Scenario;
+use libfuzzer_sys::fuzz_target;
+
+// Runs the game through arbitrary sizes, resizes and key presses, failing
+// on a panic, a change off the screen or a screen drawn unlike the view
+fuzz_target!(|data: &[u8]| {
+    if let Err(e) = Scenario::from_bytes(data).run() {
+        panic!("{}", e);
+    }
+});
@@ -0,0 +1,37 @@
+// Bounds on how far the balancer may scale the spawn rate
+const MIN_SCALE: f64 = 0.05;
+const MAX_SCALE: f64 = 20.0;
+
+// How strongly each update's error in coverage moves the spawn rate. Kept
+// low, since stacks take seconds to fill the screen after they spawn.
+const GAIN: f64 = 0.03;
+
+// A feedback loop steering the spawn rate toward a target fraction of lit
+// cells, whatever the screen size, speed or stack lengths
+#[derive(Clone, Debug)]
+pub struct CoverageBalancer {
+    target: f64,
+    scale: f64,
+}
+
+impl CoverageBalancer {
+    // Target is the fraction (0.0 - 1.0) of cells to keep lit
+    pub fn new(target: f64) -> Self {
+        Self {
+            target: target.clamp(0.0, 1.0),
+            scale: 1.0,
+        }
+    }
+
+    pub fn target(&self) -> f64 {
+        self.target
+    }
+
+    // Takes the latest coverage, returning the factor to scale the spawn rate by
+    pub fn update(&mut self, coverage: f64) -> f64 {
+        let error = (self.target - coverage) / self.target.max(0.01);
+        self.scale =
+            (self.scale * (GAIN * error.clamp(-1.0, 1.0)).exp()).clamp(MIN_SCALE, MAX_SCALE);
+        self.scale
+    }
+}
@@ -0,0 +1,93 @@
+use rand::Rng;
+use std::time::Duration;
+
+use crate::compositor::{Layer, LayerCell};
+use crate::font;
+use crate::charset::Charset;
+use crate::game::AnsiColor;
+
+// Delay between each glyph column the banner scrolls
+const SCROLL_INTERVAL: Duration = Duration::from_millis(80);
+
+// Glyph columns between letters
+const LETTER_SPACING: u16 = 1;
+
+// Opacity of the shade the banner lays over the rain, dimming it about one
+// brightness step
+const SHADE: f64 = 0.35;
+
+// Large block letters scrolling right to left across the middle of the
+// screen, drawn with flickering glyphs over dimmed rain. Positions are in
+// glyph columns (two cells wide).
+pub struct Banner {
+    letters: Vec<[u8; font::HEIGHT as usize]>,
+    elapsed: Duration,
+}
+
+impl Banner {
+    pub fn new(text: &str) -> Self {
+        Self {
+            letters: text.chars().map(font::letter).collect(),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    // Scrolls the banner along by dt
+    pub fn update(&mut self, dt: Duration) {
+        self.elapsed += dt;
+    }
+
+    fn text_width(&self) -> u16 {
+        self.letters.len() as u16 * (font::WIDTH + LETTER_SPACING)
+    }
+
+    // Whether the banner pixel at the glyph column (counted from the start
+    // of the text) and row is lit
+    fn is_lit(&self, x: u16, y: u16) -> bool {
+        let advance = font::WIDTH + LETTER_SPACING;
+        self.letters
+            .get((x / advance) as usize)
+            .is_some_and(|letter| font::is_lit(letter, x % advance, y))
+    }
+
+    // A layer shading the rain behind the banner, whose lit pixels show a
+    // fresh random glyph every frame
+    pub fn layer<R: Rng>(&self, width: u16, height: u16, rng: &mut R) -> Layer {
+        let mut layer = Layer::new(width, height);
+        layer.fill(LayerCell::Tint {
+            color: AnsiColor::Rgb(0, 0, 0),
+            alpha: SHADE,
+        });
+
+        // The text enters from the right edge, and starts over once it has left
+        let columns = width / 2;
+        let cycle = (columns + self.text_width()) as u128;
+        let scrolled = (self.elapsed.as_millis() / SCROLL_INTERVAL.as_millis() % cycle) as i32;
+        let left = columns as i32 - scrolled;
+
+        let top = (height.saturating_sub(font::HEIGHT)) / 2;
+        for row in 0..font::HEIGHT.min(height) {
+            for column in 0..columns {
+                let Ok(x) = u16::try_from(column as i32 - left) else {
+                    continue;
+                };
+                if self.is_lit(x, row) {
+                    let color = if rng.random_bool(0.3) {
+                        AnsiColor::Green
+                    } else {
+                        AnsiColor::White
+                    };
+                    // Laid out two columns a glyph, so always in full-width katakana
+                    let cell = LayerCell::Glyph {
+                        ch: Charset::Katakana.random_glyph(rng),
+                        color,
+                        alpha: 1.0,
+                    };
+                    layer.set(column * 2 + 1, top + row, LayerCell::Clear);
+                    layer.set(column * 2, top + row, cell);
+                }
+            }
+        }
+        layer
+    }
+}
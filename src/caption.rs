@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use crate::compositor::{Layer, LayerCell};
+use crate::game::AnsiColor;
+
+// How long a caption shows before it's removed
+const SHOW_TIME: Duration = Duration::from_secs(8);
+
+// Opacity of the caption's text, just enough to hide the glyphs beneath
+const ALPHA: f64 = 0.6;
+
+// A short line of text in the bottom right corner of the screen, drawn
+// half-faded over the rain for a few seconds, to label something in the
+// rain without drawing attention from it
+pub struct Caption {
+    text: Vec<char>,
+    color: AnsiColor,
+    elapsed: Duration,
+}
+
+impl Caption {
+    pub fn new(text: &str, color: AnsiColor) -> Self {
+        Self {
+            text: text.chars().collect(),
+            color,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    // Advances the caption by dt, returning false once its time is up
+    pub fn update(&mut self, dt: Duration) -> bool {
+        self.elapsed += dt;
+        self.elapsed < SHOW_TIME
+    }
+
+    // A layer with the text on the bottom row, a column in from the right
+    pub fn layer(&self, width: u16, height: u16) -> Layer {
+        let mut layer = Layer::new(width, height);
+        let Some(y) = height.checked_sub(1) else {
+            return layer;
+        };
+
+        let len = self.text.len().min(width.saturating_sub(1) as usize) as u16;
+        let start = width - 1 - len;
+
+        // Rain glyphs are two columns wide, so clear the column before the text
+        if let Some(x) = start.checked_sub(1) {
+            layer.set(x, y, LayerCell::Clear);
+        }
+        for (i, ch) in self.text.iter().take(len as usize).enumerate() {
+            let cell = LayerCell::Glyph {
+                ch: *ch,
+                color: self.color,
+                alpha: ALPHA,
+            };
+            layer.set(start + i as u16, y, cell);
+        }
+        layer
+    }
+}
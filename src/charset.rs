@@ -0,0 +1,515 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use clap::ValueEnum;
+use rand::Rng;
+
+// Code points that are never glyphs of their own, beyond the controls:
+// combining marks, invisible formatting, noncharacters, and the planes with
+// nothing assigned in them. Without the Unicode database, gaps left
+// unassigned inside assigned blocks can't be told apart, but terminals draw
+// those as placeholders rather than garbage.
+const NON_GLYPHS: [RangeInclusive<u32>; 21] = [
+    0x00AD..=0x00AD,   // soft hyphen
+    0x0300..=0x036F,   // combining diacritical marks
+    0x0483..=0x0489,   // combining cyrillic marks
+    0x0591..=0x05C7,   // hebrew points and accents
+    0x0610..=0x061A,   // arabic marks
+    0x064B..=0x065F,   // arabic vowel marks
+    0x1160..=0x11FF,   // conjoining hangul vowels and final consonants
+    0x1AB0..=0x1AFF,   // combining diacritical marks extended
+    0x1DC0..=0x1DFF,   // combining diacritical marks supplement
+    0x200B..=0x200F,   // zero width characters and direction marks
+    0x2028..=0x202E,   // separators and direction embeddings
+    0x2060..=0x206F,   // invisible operators and formatting
+    0x20D0..=0x20FF,   // combining marks for symbols
+    0x3099..=0x309A,   // combining kana voicing marks
+    0xFDD0..=0xFDEF,   // noncharacters
+    0xFE00..=0xFE0F,   // variation selectors
+    0xFE20..=0xFE2F,   // combining half marks
+    0xFEFF..=0xFEFF,   // byte order mark
+    0xFFF0..=0xFFFF,   // specials, the replacement character and noncharacters
+    0x40000..=0xDFFFF, // unassigned planes
+    0xE0000..=0xE0FFF, // tags and variation selectors supplement
+];
+
+// Code points left for fonts to draw anything in, like logos or icons
+const PRIVATE_USE: [RangeInclusive<u32>; 2] = [0xE000..=0xF8FF, 0xF0000..=0x10FFFF];
+
+// Whether a character takes up columns of its own, rather than none
+fn has_width(ch: char) -> bool {
+    !ch.is_control() && !NON_GLYPHS.iter().any(|range| range.contains(&(ch as u32)))
+}
+
+// Whether a code point draws as a glyph of its own in any font
+fn is_glyph(code: u32) -> bool {
+    let Some(ch) = char::from_u32(code) else {
+        return false;
+    };
+    has_width(ch) && !ch.is_whitespace() && !PRIVATE_USE.iter().any(|range| range.contains(&code))
+}
+
+// Glyphs and their mirror images, where Unicode has a mirrored form (like
+// brackets and box corners) or a glyph that looks like one (like `Я` for
+// `R`). Each pair takes up the same columns.
+const MIRROR_PAIRS: [(char, char); 47] = [
+    ('(', ')'),
+    ('/', '\\'),
+    ('3', 'Ɛ'),
+    (';', '⁏'),
+    ('<', '>'),
+    ('?', '⸮'),
+    ('C', 'Ɔ'),
+    ('E', 'Ǝ'),
+    ('F', 'ꟻ'),
+    ('L', '⅃'),
+    ('N', 'И'),
+    ('P', 'ꟼ'),
+    ('R', 'Я'),
+    ('S', 'Ƨ'),
+    ('[', ']'),
+    ('b', 'd'),
+    ('c', 'ɔ'),
+    ('e', 'ɘ'),
+    ('p', 'q'),
+    ('s', 'ƨ'),
+    ('{', '}'),
+    ('«', '»'),
+    ('‹', '›'),
+    ('⁅', '⁆'),
+    ('∈', '∋'),
+    ('≤', '≥'),
+    ('⊂', '⊃'),
+    ('⊆', '⊇'),
+    ('┌', '┐'),
+    ('└', '┘'),
+    ('├', '┤'),
+    ('┏', '┓'),
+    ('┗', '┛'),
+    ('┣', '┫'),
+    ('╒', '╕'),
+    ('╓', '╖'),
+    ('╔', '╗'),
+    ('╘', '╛'),
+    ('╙', '╜'),
+    ('╚', '╝'),
+    ('╞', '╡'),
+    ('╟', '╢'),
+    ('╠', '╣'),
+    ('╭', '╮'),
+    ('╰', '╯'),
+    ('╱', '╲'),
+    ('╴', '╶'),
+];
+
+// A glyph's mirror image, or the glyph itself if it has none
+pub fn mirror_image(ch: char) -> char {
+    for (glyph, image) in MIRROR_PAIRS {
+        if ch == glyph {
+            return image;
+        }
+        if ch == image {
+            return glyph;
+        }
+    }
+    ch
+}
+
+// A glyph picked at random from blocks of code points, each from its first
+// code point to one past its last, and each of a block's glyphs as likely as
+// its weight if the blocks are weighted
+fn pick<R: Rng>(blocks: &[(u32, u32)], weights: Option<&[u32]>, rng: &mut R) -> char {
+    let code = match (blocks, weights) {
+        // A single block is picked from directly, as the katakana always were
+        ([(start, end)], None) => rng.random_range(*start..*end),
+        (blocks, Some(weights)) => {
+            let weighted = || blocks.iter().zip(weights);
+            let total = weighted()
+                .map(|((start, end), weight)| (end - start) as u64 * *weight as u64)
+                .sum::<u64>();
+            let mut index = rng.random_range(0..total);
+            let mut code = 0;
+            for ((start, end), weight) in weighted() {
+                let size = (end - start) as u64 * *weight as u64;
+                if index < size {
+                    code = start + (index / *weight as u64) as u32;
+                    break;
+                }
+                index -= size;
+            }
+            code
+        }
+        (blocks, None) => {
+            let count = blocks.iter().map(|(start, end)| end - start).sum::<u32>();
+            let mut index = rng.random_range(0..count);
+            let mut code = 0;
+            for (start, end) in blocks {
+                if index < end - start {
+                    code = start + index;
+                    break;
+                }
+                index -= end - start;
+            }
+            code
+        }
+    };
+    char::from_u32(code).unwrap_or('?')
+}
+
+// A set of glyphs the rain can be drawn in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, ValueEnum)]
+pub enum Charset {
+    // Full-width katakana, two columns wide
+    #[default]
+    Katakana,
+    // Half-width katakana, as in the films
+    HalfWidthKatakana,
+    // Upper and lower case latin letters
+    Latin,
+    Digits,
+    // Hexadecimal digits, in upper case
+    Hex,
+    // Upper and lower case greek letters
+    Greek,
+    // Upper and lower case cyrillic letters
+    Cyrillic,
+    // Hangul syllables, two columns wide
+    Hangul,
+    // Runic letters
+    Runes,
+    // Lines and corners of box drawing characters
+    BoxDrawing,
+}
+
+impl Charset {
+    pub fn next(self) -> Self {
+        match self {
+            Charset::Katakana => Charset::HalfWidthKatakana,
+            Charset::HalfWidthKatakana => Charset::Latin,
+            Charset::Latin => Charset::Digits,
+            Charset::Digits => Charset::Hex,
+            Charset::Hex => Charset::Greek,
+            Charset::Greek => Charset::Cyrillic,
+            Charset::Cyrillic => Charset::Hangul,
+            Charset::Hangul => Charset::Runes,
+            Charset::Runes => Charset::BoxDrawing,
+            Charset::BoxDrawing => Charset::Katakana,
+        }
+    }
+
+    // The blocks of code points the set is made of, each from its first
+    // code point to one past its last
+    fn blocks(self) -> &'static [(u32, u32)] {
+        match self {
+            Charset::Katakana => &[(0x30A0, 0x30FF)],
+            Charset::HalfWidthKatakana => &[(0xFF66, 0xFF9E)],
+            Charset::Latin => &[(0x41, 0x5B), (0x61, 0x7B)],
+            Charset::Digits => &[(0x30, 0x3A)],
+            Charset::Hex => &[(0x30, 0x3A), (0x41, 0x47)],
+            // Skipping the reserved code point between rho and sigma
+            Charset::Greek => &[(0x391, 0x3A2), (0x3A3, 0x3AA), (0x3B1, 0x3CA)],
+            Charset::Cyrillic => &[(0x410, 0x450)],
+            Charset::Hangul => &[(0xAC00, 0xD7A4)],
+            Charset::Runes => &[(0x16A0, 0x16EB)],
+            Charset::BoxDrawing => &[(0x2500, 0x2580)],
+        }
+    }
+
+    // A glyph picked at random from the set
+    pub fn random_glyph<R: Rng>(self, rng: &mut R) -> char {
+        pick(self.blocks(), None, rng)
+    }
+}
+
+// The glyphs the rain is drawn from: a named charset, ranges of code points
+// given on the command line, the characters of a file, or glyphs weighted in
+// the config. Cheap to clone, so each stack keeps one.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GlyphSet {
+    blocks: Arc<[(u32, u32)]>,   // as in Charset::blocks
+    weights: Option<Arc<[u32]>>, // per block, unless every glyph is as likely
+    mirrored: bool,              // whether glyphs are drawn as their mirror images
+}
+
+impl GlyphSet {
+    // The glyphs in ranges of code points (overlapping or not), leaving out
+    // anything that isn't a glyph of its own. None if that leaves nothing.
+    pub fn from_ranges(ranges: &[RangeInclusive<u32>]) -> Option<Self> {
+        let mut ranges = ranges.to_vec();
+        ranges.sort_by_key(|range| *range.start());
+        let mut codes = Vec::new();
+        let mut next = 0; // past every code point looked at so far
+        for range in ranges {
+            codes.extend(((*range.start()).max(next)..=*range.end()).filter(|c| is_glyph(*c)));
+            next = next.max(range.end() + 1);
+        }
+        Self::from_codes(codes)
+    }
+
+    // The glyphs in a text, like a file of them: each character once,
+    // skipping whitespace. Characters from private use areas are kept, for
+    // fonts drawing logos or icons there. Anything else without columns of
+    // its own (a control, combining mark or invisible formatting) is an
+    // error naming where it is, as is a text with no glyphs at all.
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        let mut codes = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            for ch in line.chars().filter(|ch| !ch.is_whitespace()) {
+                if !has_width(ch) {
+                    return Err(format!(
+                        "U+{:04X} on line {} isn't a glyph of its own",
+                        ch as u32,
+                        number + 1
+                    ));
+                }
+                codes.push(ch as u32);
+            }
+        }
+        codes.sort_unstable();
+        codes.dedup();
+        Self::from_codes(codes).ok_or_else(|| "there are no glyphs in it".to_string())
+    }
+
+    // Glyphs each picked as often as its weight relative to the others, e.g.
+    // `0` and `1` weighted 20 to rain mostly binary with a kanji weighted 1
+    // now and then. Glyphs weighted 0 are left out. Like a text, each glyph
+    // has to draw on its own, and it's an error to weight one twice.
+    pub fn from_weights(weights: &[(char, u32)]) -> Result<Self, String> {
+        let mut weights = weights.to_vec();
+        weights.sort_by_key(|(ch, _)| *ch);
+        if let Some(pair) = weights.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(format!("`{}` is weighted twice", pair[0].0));
+        }
+        if let Some((ch, _)) = weights
+            .iter()
+            .find(|(ch, _)| ch.is_whitespace() || !has_width(*ch))
+        {
+            return Err(format!("U+{:04X} isn't a glyph of its own", *ch as u32));
+        }
+        weights.retain(|(_, weight)| *weight > 0);
+        let mut blocks: Vec<(u32, u32)> = Vec::new();
+        let mut block_weights: Vec<u32> = Vec::new();
+        for (ch, weight) in weights {
+            match (blocks.last_mut(), block_weights.last()) {
+                (Some((_, end)), Some(last)) if *end == ch as u32 && *last == weight => *end += 1,
+                _ => {
+                    blocks.push((ch as u32, ch as u32 + 1));
+                    block_weights.push(weight);
+                }
+            }
+        }
+        if blocks.is_empty() {
+            return Err("there are no glyphs weighted above 0".to_string());
+        }
+        // Glyphs all weighted the same are picked as in any other set
+        let even = block_weights
+            .iter()
+            .all(|weight| *weight == block_weights[0]);
+        Ok(Self {
+            blocks: blocks.into(),
+            weights: (!even).then(|| block_weights.into()),
+            mirrored: false,
+        })
+    }
+
+    // The glyphs with the given code points, in order and each only once
+    fn from_codes(codes: Vec<u32>) -> Option<Self> {
+        let mut blocks: Vec<(u32, u32)> = Vec::new();
+        for code in codes {
+            match blocks.last_mut() {
+                Some((_, end)) if *end == code => *end += 1,
+                _ => blocks.push((code, code + 1)),
+            }
+        }
+        (!blocks.is_empty()).then(|| Self {
+            blocks: blocks.into(),
+            weights: None,
+            mirrored: false,
+        })
+    }
+
+    pub fn mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    // Draws glyphs as their mirror images where they have one, like the
+    // rain in the films
+    pub fn set_mirrored(&mut self, mirrored: bool) {
+        self.mirrored = mirrored;
+    }
+
+    pub fn random_glyph<R: Rng>(&self, rng: &mut R) -> char {
+        let glyph = pick(&self.blocks, self.weights.as_deref(), rng);
+        match self.mirrored {
+            true => mirror_image(glyph),
+            false => glyph,
+        }
+    }
+}
+
+impl Default for GlyphSet {
+    fn default() -> Self {
+        Charset::default().into()
+    }
+}
+
+impl From<Charset> for GlyphSet {
+    fn from(charset: Charset) -> Self {
+        Self {
+            blocks: charset.blocks().into(),
+            weights: None,
+            mirrored: false,
+        }
+    }
+}
+
+// Parses a range of code points, like `0x2580-0x259F`, `U+2580-U+259F` or a
+// single `0x2588`
+pub fn parse_range(text: &str) -> Result<RangeInclusive<u32>, String> {
+    let code = |part: &str| {
+        let part = part.trim();
+        let hex = part
+            .strip_prefix("0x")
+            .or_else(|| part.strip_prefix("0X"))
+            .or_else(|| part.strip_prefix("U+"))
+            .or_else(|| part.strip_prefix("u+"))
+            .unwrap_or(part);
+        u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|code| *code <= char::MAX as u32)
+            .ok_or_else(|| format!("`{part}` isn't a code point, like 0x30A0 or U+30A0"))
+    };
+    let (start, end) = match text.split_once('-') {
+        Some((start, end)) => (code(start)?, code(end)?),
+        None => (code(text)?, code(text)?),
+    };
+    if start > end {
+        return Err(format!("`{text}` ends before it starts"));
+    }
+    Ok(start..=end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::display_width;
+    use crate::rng::RngStreams;
+    use std::collections::HashMap;
+
+    #[test]
+    fn every_charset_draws_glyphs_of_one_width() {
+        let mut rngs = RngStreams::new(1);
+        for charset in Charset::value_variants() {
+            let width = display_width(charset.random_glyph(&mut rngs.glyphs));
+            for _ in 0..500 {
+                let ch = charset.random_glyph(&mut rngs.glyphs);
+                assert!(
+                    ch != '?' && display_width(ch) == width,
+                    "{:?}: {:?}",
+                    charset,
+                    ch
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn glyph_ranges_leave_out_what_isnt_a_glyph() {
+        let ranges = [
+            0x0000..=0x0041,
+            0x0300..=0x036F,
+            0x3099..=0x309C,
+            0x0041..=0x0042,
+        ];
+        let glyph_set = GlyphSet::from_ranges(&ranges).unwrap();
+        let mut rngs = RngStreams::new(1);
+        let mut seen: Vec<char> = (0..2000)
+            .map(|_| glyph_set.random_glyph(&mut rngs.glyphs))
+            .collect();
+        seen.sort();
+        seen.dedup();
+        let mut expected: Vec<char> = ('!'..='B').chain(['゛', '゜']).collect();
+        expected.sort();
+        assert_eq!(seen, expected);
+        assert_eq!(GlyphSet::from_ranges(&[0x0300..=0x036F]), None);
+    }
+
+    #[test]
+    fn ranges_must_be_code_points_in_order() {
+        assert_eq!(parse_range("U+30A0-U+30FF"), Ok(0x30A0..=0x30FF));
+        assert_eq!(parse_range("0x41"), Ok(0x41..=0x41));
+        assert!(parse_range("U+30G0").is_err());
+        assert!(parse_range("0x110000").is_err());
+        assert_eq!(
+            parse_range("30FF-30A0"),
+            Err("`30FF-30A0` ends before it starts".to_string())
+        );
+    }
+
+    #[test]
+    fn glyph_files_keep_each_character_once_and_refuse_marks() {
+        let glyph_set = GlyphSet::from_text("\u{feff}ab a\n\tb\u{E000}\r\n").unwrap();
+        assert_eq!(glyph_set, GlyphSet::from_text("\u{E000}ba").unwrap());
+        let mut rngs = RngStreams::new(1);
+        let mut seen: Vec<char> = (0..200)
+            .map(|_| glyph_set.random_glyph(&mut rngs.glyphs))
+            .collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen, ['a', 'b', '\u{E000}']);
+        assert_eq!(
+            GlyphSet::from_text("ab\ne\u{301}"),
+            Err("U+0301 on line 2 isn't a glyph of its own".to_string())
+        );
+        assert!(GlyphSet::from_text(" \n").is_err());
+    }
+
+    #[test]
+    fn empty_glyph_files_are_refused() {
+        let refused = Err("there are no glyphs in it".to_string());
+        assert_eq!(GlyphSet::from_text(""), refused);
+        assert_eq!(GlyphSet::from_text("\u{feff}\n"), refused);
+    }
+
+    #[test]
+    fn glyphs_are_picked_as_often_as_their_weights() {
+        let glyph_set = GlyphSet::from_weights(&[('0', 6), ('1', 3), ('日', 1), ('x', 0)]).unwrap();
+        let mut rngs = RngStreams::new(1);
+        let mut counts = HashMap::new();
+        for _ in 0..10_000 {
+            let glyph = glyph_set.random_glyph(&mut rngs.glyphs);
+            *counts.entry(glyph).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 3);
+        for (glyph, share) in [('0', 0.6), ('1', 0.3), ('日', 0.1)] {
+            let picked = counts[&glyph] as f64 / 10_000.0;
+            assert!((picked - share).abs() < 0.02, "{:?}", counts);
+        }
+        let even = GlyphSet::from_weights(&[('a', 2), ('b', 2)]).unwrap();
+        assert_eq!(even, GlyphSet::from_text("ab").unwrap());
+        assert!(GlyphSet::from_weights(&[('a', 1), ('a', 2)]).is_err());
+    }
+
+    #[test]
+    fn glyphs_weighted_zero_are_never_picked() {
+        let glyph_set = GlyphSet::from_weights(&[('a', 0), ('b', 5)]).unwrap();
+        assert_eq!(glyph_set, GlyphSet::from_text("b").unwrap());
+        assert_eq!(
+            GlyphSet::from_weights(&[('a', 0), ('b', 0)]),
+            Err("there are no glyphs weighted above 0".to_string())
+        );
+    }
+
+    #[test]
+    fn mirrored_glyphs_keep_their_width_and_mirror_back() {
+        for code in (0x20..0x250).chain(0x2500..0x2580) {
+            let Some(ch) = char::from_u32(code) else {
+                continue;
+            };
+            let image = mirror_image(ch);
+            assert_eq!(display_width(image), display_width(ch), "{:?}", ch);
+            assert_eq!(mirror_image(image), ch, "{:?}", ch);
+        }
+    }
+}
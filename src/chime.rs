@@ -0,0 +1,104 @@
+use rand::Rng;
+use std::time::Duration;
+
+use crate::charset::Charset;
+use crate::compositor::{Layer, LayerCell};
+use crate::font;
+use crate::game::AnsiColor;
+
+// How long the time shows for
+const SHOW_TIME: Duration = Duration::from_secs(5);
+
+// Glyph columns between digits
+const DIGIT_SPACING: u16 = 1;
+
+// Opacity of the shade laid over the rain behind the time
+const SHADE: f64 = 0.35;
+
+// The time in large block digits, held in the middle of the screen for a few
+// seconds like a clock chiming, drawn with flickering glyphs over dimmed
+// rain. Positions are in glyph columns (two cells wide).
+pub struct Chime {
+    digits: Vec<[u8; font::HEIGHT as usize]>,
+    elapsed: Duration,
+}
+
+impl Chime {
+    pub fn new(hour: u8, minute: u8) -> Self {
+        let time = format!("{:02}:{:02}", hour, minute);
+        Self {
+            digits: time.chars().map(font::letter).collect(),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    // Advances the chime by dt, returning false once its time is up
+    pub fn update(&mut self, dt: Duration) -> bool {
+        self.elapsed += dt;
+        self.elapsed < SHOW_TIME
+    }
+
+    // A layer shading the rain, with the time centered on it in lit pixels
+    // that each show a fresh random glyph every frame
+    pub fn layer<R: Rng>(&self, width: u16, height: u16, rng: &mut R) -> Layer {
+        let mut layer = Layer::new(width, height);
+        layer.fill(LayerCell::Tint {
+            color: AnsiColor::Rgb(0, 0, 0),
+            alpha: SHADE,
+        });
+
+        let advance = font::WIDTH + DIGIT_SPACING;
+        let text_width = (self.digits.len() as u16 * advance).saturating_sub(DIGIT_SPACING);
+        let left = (width / 2).saturating_sub(text_width) / 2;
+        let top = height.saturating_sub(font::HEIGHT) / 2;
+        for row in 0..font::HEIGHT.min(height) {
+            for x in 0..text_width.min(width / 2 - left) {
+                let lit = self
+                    .digits
+                    .get((x / advance) as usize)
+                    .is_some_and(|digit| font::is_lit(digit, x % advance, row));
+                if !lit {
+                    continue;
+                }
+                let color = if rng.random_bool(0.3) {
+                    AnsiColor::Green
+                } else {
+                    AnsiColor::White
+                };
+                // Laid out two columns a glyph, so always in full-width katakana
+                let cell = LayerCell::Glyph {
+                    ch: Charset::Katakana.random_glyph(rng),
+                    color,
+                    alpha: 1.0,
+                };
+                let column = (left + x) * 2;
+                layer.set(column + 1, top + row, LayerCell::Clear);
+                layer.set(column, top + row, cell);
+            }
+        }
+        layer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Viewport;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn the_time_is_centered_and_goes_after_a_few_seconds() {
+        let mut chime = Chime::new(9, 0);
+        let mut view = Viewport::new(80, 24);
+        chime
+            .layer(80, 24, &mut StdRng::seed_from_u64(1))
+            .composite(&mut view);
+        let lit: Vec<u16> = (0..80).filter(|x| view.get(*x, 12).is_some()).collect();
+        let (first, last) = (lit[0], lit[lit.len() - 1]);
+        assert!(first.abs_diff(80 - 2 - last) <= 2, "{:?}", lit);
+
+        assert!(chime.update(Duration::from_secs(4)));
+        assert!(!chime.update(Duration::from_secs(1)));
+    }
+}
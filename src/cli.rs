@@ -0,0 +1,753 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use falling_glyphs::game::{
+    AnsiColor, Attributes, BRIGHTNESS_RANGE, CELL_ASPECT_RANGE, DENSITY_RANGE,
+    MAX_STACK_HEIGHT_RANGE,
+};
+use falling_glyphs::gradient::{self, ColorStops};
+use falling_glyphs::lightning::{FlashRegion, FlashStyle};
+use falling_glyphs::mask::MaskPattern;
+use falling_glyphs::charset::{self, Charset};
+use falling_glyphs::effects::EffectKind;
+use falling_glyphs::embers::EmberStyle;
+use falling_glyphs::mirror::Mirror;
+use falling_glyphs::origin::Origin;
+use falling_glyphs::pattern::{self, SpawnPattern};
+use falling_glyphs::reserved::Reserved;
+use falling_glyphs::theme::{ColorMode, Theme};
+use falling_glyphs::weather::Weather;
+use falling_glyphs::zoom::Zoom;
+
+use crate::palette::{ColorDepth, Dither};
+use crate::schedule::Schedule;
+
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "A falling matrix of green glyphs in your terminal",
+    args_override_self = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub mode: Option<Mode>,
+
+    /// Stack spawn rate (0.1 - 1.0) [default: 0.5]
+    #[arg(long, value_parser = parse_density)]
+    pub density: Option<f64>,
+
+    /// Speed level (1 - 50, where 10 is normal speed) [default: 10]
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=50))]
+    pub speed: Option<u8>,
+
+    /// Tallest a glyph stack grows, as a fraction of the screen height (0.1 - 1.0)
+    /// [default: 0.5]
+    #[arg(long, value_parser = parse_stack_height)]
+    pub max_stack_height: Option<f64>,
+
+    /// Start with the debug overlay shown (toggled with `?`)
+    #[arg(long)]
+    pub debug: bool,
+
+    /// Weather preset bundling density, speed, stack length, mutation rate and lightning
+    #[arg(long, value_enum)]
+    pub weather: Option<Weather>,
+
+    /// Chance of a lightning flash every 75ms (0.0 - 1.0)
+    #[arg(long, value_parser = parse_chance)]
+    pub lightning: Option<f64>,
+
+    /// How lightning flashes render
+    #[arg(long, value_enum, default_value_t)]
+    pub lightning_style: FlashStyle,
+
+    /// Whether lightning flashes the whole screen or a random region of it
+    #[arg(long, value_enum, default_value_t)]
+    pub lightning_region: FlashRegion,
+
+    /// Start with the spotlight on this column (toggled with `l`)
+    #[arg(long, value_name = "COLUMN")]
+    pub spotlight: Option<u16>,
+
+    /// Reflect the left half of the screen onto the right, or the top half onto the bottom
+    #[arg(long, value_enum, default_value_t)]
+    pub mirror: Mirror,
+
+    /// Hide the rain in a repeating pattern of cells
+    #[arg(long, value_enum, conflicts_with = "stencil")]
+    pub mask: Option<MaskPattern>,
+
+    /// Size (in cells) of the stripes or checkerboard squares
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    pub mask_size: u16,
+
+    /// Text file whose non-whitespace characters hide the rain, centered on the screen
+    #[arg(long, value_name = "FILE")]
+    pub stencil: Option<PathBuf>,
+
+    /// Line of text typed out in the middle of the screen after the rain runs briefly
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Scroll this text across the middle of the screen in large block letters
+    #[arg(long, value_name = "TEXT")]
+    pub banner: Option<String>,
+
+    /// Chime at these minutes past the hour (the top of each hour if none are given; e.g.
+    /// `0,30` or `*/15`), with a wave of rain across the screen and the time in large block
+    /// digits
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0",
+        value_parser = parse_schedule
+    )]
+    pub chime: Option<Schedule>,
+
+    /// Spawn stacks on a schedule, in place of the density's random ones, as rules like
+    /// `every 0.5s at random; every 5s wave; at 30s message "HELLO"`: each is `every` or
+    /// `at` a time, then `at random`, `at column N`, `wave` or `message "TEXT"`
+    #[arg(long, value_name = "PATTERN", value_parser = parse_spawn_pattern)]
+    pub spawn_pattern: Option<SpawnPattern>,
+
+    /// Exit after running this long (e.g. `30s`, `2m`, `1500ms`; bare numbers are seconds)
+    #[arg(long, value_parser = parse_duration)]
+    pub duration: Option<Duration>,
+
+    /// Wait in the background for this terminal (e.g. `$(tty)`) to sit idle at the shell's
+    /// prompt, then take it over with the rain until a key is pressed, like a screensaver
+    #[arg(long, value_name = "TTY")]
+    pub watch_idle: Option<PathBuf>,
+
+    /// How long the terminal has to be idle before `--watch-idle` takes it over
+    #[arg(long, value_name = "TIME", default_value = "5m", value_parser = parse_duration)]
+    pub idle_after: Duration,
+
+    /// Exit once this terminal has input read from it, without reading the keyboard
+    /// itself, so it can run from the background without taking the keys
+    #[arg(long, value_name = "TTY")]
+    pub until_activity: Option<PathBuf>,
+
+    /// Keep the frames drawn over the last this long (e.g. `10s`), for `R` to save as an
+    /// asciinema recording in the current directory
+    #[arg(long, value_name = "TIME", value_parser = parse_duration)]
+    pub replay_buffer: Option<Duration>,
+
+    /// Exit after drawing this many frames (e.g. 1800 is 30s at `--fps 60`), for recordings
+    #[arg(long, value_name = "COUNT")]
+    pub frames: Option<u64>,
+
+    /// Exit, restoring the terminal, if writing to it blocks this long (e.g. a dead SSH
+    /// connection); 0 disables the watchdog
+    #[arg(long, value_name = "TIME", default_value = "5s", value_parser = parse_duration)]
+    pub watchdog: Duration,
+
+    /// Limit color churn, only recoloring cells once their color has changed visibly (by
+    /// --color-threshold), for smaller recordings and less scrollback. On by itself while
+    /// recording with asciinema
+    #[arg(long)]
+    pub reduce_colors: bool,
+
+    /// Don't limit color churn, even while recording
+    #[arg(long, conflicts_with = "reduce_colors")]
+    pub no_reduce_colors: bool,
+
+    /// Smallest recolor drawn while limiting color churn, as a perceptual color difference
+    /// (CIE76 delta E, from 1 to 100; about 2.3 is just noticeable)
+    #[arg(
+        long,
+        value_name = "DELTA_E",
+        default_value_t = 10.0,
+        value_parser = parse_color_threshold
+    )]
+    pub color_threshold: f64,
+
+    /// Draw the changes to odd rows one frame and even rows the next, halving what's written
+    /// each frame while keeping the motion, for slow terminals such as serial consoles
+    #[arg(long)]
+    pub interlace: bool,
+
+    /// Repaint the whole screen this often (e.g. `5m`), in case another program draws over it
+    #[arg(long, value_name = "TIME", value_parser = parse_duration)]
+    pub repaint_every: Option<Duration>,
+
+    /// Debugging: hash the expected screen each frame, repainting it in full if the changes
+    /// drawn have drifted from it, and also every this often regardless (e.g. `10s`)
+    #[arg(long, value_name = "TIME", value_parser = parse_duration)]
+    pub verify_frames: Option<Duration>,
+
+    /// Read percentages (one per line) from stdin, filling the screen with rain from
+    /// left to right as they grow; exits when stdin closes
+    #[arg(long)]
+    pub progress: bool,
+
+    /// Height of the canvas the rain falls through, in screens; pan over it with the arrow keys
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..=100))]
+    pub pages: u16,
+
+    /// Starting zoom level: spaced out, normal, or summarizing the rain with quadrant or braille blocks
+    #[arg(long, value_enum, default_value_t)]
+    pub zoom: Zoom,
+
+    /// Vary each stack's step interval randomly by up to this percentage around its base
+    #[arg(long, value_name = "PERCENT", default_value_t = 0.0, value_parser = parse_percent)]
+    pub jitter: f64,
+
+    /// Gusts of wind speeding up and slowing down neighbouring columns together, varying
+    /// their speed by up to this percentage as the gusts drift across the screen
+    #[arg(long, value_name = "PERCENT", default_value_t = 0.0, value_parser = parse_percent)]
+    pub wind: f64,
+
+    /// Render frames between steps, blending each head's brightness into the row below it
+    /// (best on fast terminals with truecolor support)
+    #[arg(long)]
+    pub smooth: bool,
+
+    /// Height of the font's cells over their width (1.0 - 4.0), so the vortex and helix
+    /// keep their shape [default: 2.0]
+    #[arg(long, value_name = "RATIO", value_parser = parse_cell_aspect)]
+    pub cell_aspect: Option<f64>,
+
+    /// Make stacks fall the same distance on screen per second whatever the cell aspect,
+    /// and as fast as stacks cross sideways
+    #[arg(long)]
+    pub normalize_speed: bool,
+
+    /// Once a stack's head leaves the screen, dissolve what's left of its tail, flickering
+    /// and fading out, rather than letting it drain off
+    #[arg(long)]
+    pub dissolve: bool,
+
+    /// Glow behind the head of each stack, drawing it over a background of this color
+    /// (e.g. `--glow=#003300`; white, green, dark-green or #rrggbb)
+    #[arg(
+        long,
+        value_name = "COLOR",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "#004400",
+        value_parser = gradient::parse_color
+    )]
+    pub glow: Option<AnsiColor>,
+
+    /// Flash each new head glyph bold and at full brightness for this many frames (1 by
+    /// default) before it settles
+    #[arg(
+        long,
+        value_name = "FRAMES",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1",
+        value_parser = clap::value_parser!(u8).range(1..=10)
+    )]
+    pub head_flash: Option<u8>,
+
+    /// Background the flashing head is drawn over, instead of the glow (e.g.
+    /// `--flash-background=white`; white, green, dark-green or #rrggbb)
+    #[arg(
+        long,
+        value_name = "COLOR",
+        requires = "head_flash",
+        value_parser = gradient::parse_color
+    )]
+    pub flash_background: Option<AnsiColor>,
+
+    /// What cells the rain has faded out of become: `off` clears them, `glyph` keeps a dim
+    /// ember of the glyph last drawn there, and `tint` leaves a dim background, until the
+    /// rain passes over them again
+    #[arg(long, value_enum, default_value_t)]
+    pub embers: EmberStyle,
+
+    /// Text attributes the head of each stack is drawn with: any of `bold`, `dim` and
+    /// `italic`, comma separated, or `none`
+    #[arg(long, value_name = "ATTRIBUTES", default_value = "none", value_parser = parse_attributes)]
+    pub head_attributes: Attributes,
+
+    /// Text attributes the back half of each stack's trail is drawn with (e.g. `dim`)
+    #[arg(long, value_name = "ATTRIBUTES", default_value = "none", value_parser = parse_attributes)]
+    pub tail_attributes: Attributes,
+
+    /// Colors the terminal can show, overriding the detected depth: `truecolor`, `256`
+    /// or `16` (blended colors are drawn in the nearest it has)
+    #[arg(long, value_name = "DEPTH")]
+    pub colors: Option<ColorDepth>,
+
+    /// At 16 colors, draw the colors of fades between the basic ones as patterns of the two
+    /// they lie between, in 2x2 (low) or 4x4 (high) blocks, so trails still fade
+    #[arg(long, value_enum, default_value_t)]
+    pub dither: Dither,
+
+    /// How frames are drawn: `diff` redraws the cells that changed; `scroll` also scrolls
+    /// the screen for effects that move down as a whole (the sheet), drawing only the new
+    /// rows
+    #[arg(long, value_enum, default_value_t)]
+    pub backend: Backend,
+
+    /// Frames drawn per second (1 - 240), adjusted with `<` and `>` [default: 13, or 60
+    /// with --smooth]
+    #[arg(long, value_parser = parse_fps)]
+    pub fps: Option<f64>,
+
+    /// Highest frame rate (1 - 240) to draw at, however the rate is set or adjusted, for
+    /// terminals that fall behind (set by `tune`)
+    #[arg(long, value_name = "FPS", value_parser = parse_fps)]
+    pub max_fps: Option<f64>,
+
+    /// Draw frames without wrapping them in synchronized updates, for terminals that
+    /// mishandle them (set by `tune`)
+    #[arg(long)]
+    pub no_sync: bool,
+
+    /// Keep roughly this percentage of the screen's cells lit, adjusting the spawn rate
+    /// continuously (the density setting only sets where it starts)
+    #[arg(long, value_name = "PERCENT", value_parser = parse_percent)]
+    pub coverage: Option<f64>,
+
+    /// Write a CSV row of metrics (rates, density, coverage and its trend) to this file
+    /// once a second (see `--metrics-interval`)
+    #[arg(long, value_name = "FILE")]
+    pub metrics: Option<PathBuf>,
+
+    /// Send metrics as gauges to the statsd server at this address (e.g. `localhost:8125`)
+    #[cfg(feature = "stats")]
+    #[arg(long, value_name = "HOST:PORT")]
+    pub statsd: Option<String>,
+
+    /// Write metrics in the Prometheus text format to this file, for node_exporter's
+    /// textfile collector
+    #[cfg(feature = "stats")]
+    #[arg(long, value_name = "FILE")]
+    pub prometheus_textfile: Option<PathBuf>,
+
+    /// How often metrics are written (or sent)
+    #[arg(long, value_name = "TIME", default_value = "1s", value_parser = parse_duration)]
+    pub metrics_interval: Duration,
+
+    /// Rain below the cursor in the terminal's own screen rather than taking it over, scrolling
+    /// what's above up to leave at least half the screen, and clear the rain away on exit
+    /// (for the end of scripts)
+    #[arg(long, conflicts_with = "until_activity")]
+    pub inline: bool,
+
+    /// Rows and columns along the edges the rain never draws in, left for widgets drawn by
+    /// another program (e.g. `top=1,right=2`)
+    #[arg(long, value_name = "EDGES", value_parser = parse_reserved)]
+    pub reserve: Option<Reserved>,
+
+    /// Color the rain by position along each stack, from head (0%) to tail (100%), blending
+    /// between stops (e.g. `0=white,30=green,70=dark-green,100=#003300`)
+    #[arg(long, value_name = "STOPS", value_parser = parse_color_stops)]
+    pub trail_colors: Option<ColorStops>,
+
+    /// Colors to draw the rain in: the classic greens (or `matrix`), amber, ice, blood, rainbow
+    /// or mono, or a theme safe for color blindness (blues for deuteranopia, yellows for
+    /// protanopia, reds for tritanopia); `t` cycles through them
+    #[arg(long, value_enum, default_value_t, conflicts_with = "trail_colors")]
+    pub theme: Theme,
+
+    /// Where the rain's colors come from: the theme, or a random hue for each stack
+    /// (`per-stack`), fading along its trail
+    #[arg(long, value_enum, default_value_t, conflicts_with = "trail_colors")]
+    pub color_mode: ColorMode,
+
+    /// Draw the rain for a light background, in dark glyphs paling down their trails. On by
+    /// itself when the terminal says its background is light (in COLORFGBG)
+    #[arg(long)]
+    pub light: bool,
+
+    /// Draw the rain for a dark background, even if the terminal says it's light
+    #[arg(long, conflicts_with = "light")]
+    pub no_light: bool,
+
+    /// Brightness of the rain's colors, from 0.2 to 2.0 (e.g. lower for an OLED screen at night,
+    /// or higher for a washed-out projector); `b` and `B` raise and lower it
+    #[arg(long, default_value_t = 1.0, value_parser = parse_brightness)]
+    pub brightness: f64,
+
+    /// Turn the rain's colors slowly round the spectrum, once every this long (e.g. `1m`)
+    #[arg(long, value_name = "TIME", value_parser = parse_duration)]
+    pub hue_cycle: Option<Duration>,
+
+    /// Separate the heads, the trails and the background as far as possible: bold white heads
+    /// over trails that keep one color of the theme, halfway between them in lightness
+    #[arg(long)]
+    pub high_contrast: bool,
+
+    /// Edge new stacks spawn from (`all` converges on the center from every edge)
+    #[arg(long, value_enum, default_value_t)]
+    pub origin: Origin,
+
+    /// Watch a git repository, raining the subject of each new commit in its own color
+    /// with its short hash in the corner
+    #[arg(long, value_name = "REPO")]
+    pub git_watch: Option<PathBuf>,
+
+    /// Follow the systemd journal, spawning a stack for each new entry colored by its
+    /// severity: red for errors, yellow for warnings and green for the rest
+    #[cfg(feature = "journal")]
+    #[arg(long)]
+    pub journal: bool,
+
+    /// Seed for every random choice, so a run can be replayed (shown in the debug overlay)
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Step the simulation one tick per frame, however long frames take to draw, so runs
+    /// with the same seed draw the same frames (as `compare` runs them)
+    #[arg(long)]
+    pub lockstep: bool,
+
+    /// Start paused, with nothing moving until `p` unpauses it or `.` steps it on
+    #[arg(long)]
+    pub paused: bool,
+
+    /// Config file of key bindings and profiles [default: <config dir>/falling_glyphs/config.toml]
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Profile from the config file whose settings replace the defaults (`default` if it
+    /// exists); options given on the command line still take precedence
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Check the config file, reporting every problem in it with its line, and exit (with an
+    /// error if there are any)
+    #[arg(long)]
+    pub validate_config: bool,
+
+    /// Start from the defaults rather than the density, speed and stack height the last run
+    /// left off with
+    #[arg(long)]
+    pub fresh: bool,
+
+    /// Run the setup wizard (also offered on first run), saving its choices as the config
+    /// file's default profile
+    #[arg(long)]
+    pub setup: bool,
+
+    /// Language of the text on screen, like `es` or `fr_FR.UTF-8`, falling back on English
+    /// where there's no translation [default: the locale's, from LC_ALL, LC_MESSAGES or LANG]
+    #[arg(long, value_name = "LANG")]
+    pub language: Option<String>,
+
+    /// Effect to run in place of the rain
+    #[arg(long, value_enum, default_value_t)]
+    pub effect: EffectKind,
+
+    /// Glyphs to draw the rain in
+    #[arg(long, value_enum, default_value_t)]
+    pub charset: Charset,
+
+    /// Draw the rain from a range of code points instead of a charset (e.g. `0x2580-0x259F` or
+    /// `U+30A0-U+30FF`), leaving out controls, combining marks and other non-glyphs; repeat
+    /// it to draw from several
+    #[arg(
+        long,
+        value_name = "RANGE",
+        value_parser = charset::parse_range,
+        conflicts_with = "charset"
+    )]
+    pub glyph_range: Vec<RangeInclusive<u32>>,
+
+    /// UTF-8 text file whose characters the rain is drawn in instead of a charset, each once,
+    /// whitespace aside (private use characters are kept, for fonts with logos in them)
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["charset", "glyph_range"])]
+    pub glyph_file: Option<PathBuf>,
+
+    /// Glyph weights from the config file to draw the rain in instead of a charset, each glyph
+    /// picked as often as its weight relative to the others
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = ["charset", "glyph_range", "glyph_file"]
+    )]
+    pub glyph_weights: Option<String>,
+
+    /// Draw glyphs as their mirror images, as in the films, where Unicode has a mirrored form
+    /// (like brackets and box corners) or a look-alike (like `Я` for `R`)
+    #[arg(long)]
+    pub mirrored_glyphs: bool,
+
+    /// Pipeline from the config file of effects to run at once, composited in order
+    #[arg(long, value_name = "NAME", conflicts_with = "effect")]
+    pub pipeline: Option<String>,
+}
+
+// Ways of drawing frames on the terminal
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    #[default]
+    Diff,
+    // Sets a scroll region and scrolls it with reverse index, where the terminal can
+    Scroll,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Mode {
+    /// Run the rain in the terminal, as it runs without a subcommand
+    Run,
+    /// Max out the rain to benchmark the simulation, or with --terminal the terminal
+    /// emulator, reporting where the time of each frame goes
+    #[command(visible_alias = "bench")]
+    Stress(StressArgs),
+    /// Draw frames of the rain in a pseudo terminal and save them as an asciinema recording,
+    /// without a terminal to watch them in (options before the subcommand apply to the run)
+    #[cfg(feature = "vt")]
+    Export(ExportArgs),
+    /// List the names the options take: charsets, effects, themes and weathers
+    List(ListArgs),
+    /// Print a script completing the options and subcommands in a shell, e.g.
+    /// `source <(falling_glyphs completions bash)`
+    Completions(CompletionsArgs),
+    /// Run the rain in a pseudo terminal for a few seconds and check what it writes, as
+    /// an end to end test of rendering (options before the subcommand apply to the run)
+    #[cfg(feature = "vt")]
+    Selftest(SelftestArgs),
+    /// Run the rain twice in pseudo terminals with different options (e.g. backends) and
+    /// check every frame leaves the same screen, for changing the renderer safely
+    #[cfg(feature = "vt")]
+    Compare(CompareArgs),
+    /// Measure what the terminal can do (its throughput, truecolor and synchronized
+    /// updates) and save settings for it, used whenever the rain runs in it again
+    Tune(TuneArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct StressArgs {
+    /// Draw to the terminal as fast as it takes frames, showing the frame time budget
+    /// live in the debug overlay
+    #[arg(long)]
+    pub terminal: bool,
+
+    /// How long to run [default: 10s, or until `q` with --terminal]
+    #[arg(long, value_parser = parse_duration)]
+    pub duration: Option<Duration>,
+}
+
+#[cfg(feature = "vt")]
+#[derive(Args, Debug, Clone)]
+pub struct SelftestArgs {
+    /// How long to run
+    #[arg(long, default_value = "3s", value_parser = parse_duration)]
+    pub duration: Duration,
+}
+
+#[cfg(feature = "vt")]
+#[derive(Args, Debug, Clone)]
+pub struct CompareArgs {
+    /// Options for the first run
+    #[arg(
+        long,
+        value_name = "OPTIONS",
+        default_value = "--backend diff",
+        allow_hyphen_values = true
+    )]
+    pub a: String,
+
+    /// Options for the second run
+    #[arg(
+        long,
+        value_name = "OPTIONS",
+        default_value = "--backend scroll",
+        allow_hyphen_values = true
+    )]
+    pub b: String,
+
+    /// Run the second with another build of falling_glyphs, e.g. one from before a change
+    #[arg(long, value_name = "FILE")]
+    pub b_exe: Option<PathBuf>,
+
+    /// How many frames to draw and compare
+    #[arg(long, value_name = "COUNT", default_value_t = 100)]
+    pub frames: u64,
+}
+
+#[cfg(feature = "vt")]
+#[derive(Args, Debug, Clone)]
+pub struct ExportArgs {
+    /// File to save the recording in
+    #[arg(long, short, value_name = "FILE", default_value = "falling_glyphs.cast")]
+    pub output: PathBuf,
+
+    /// How many frames to draw
+    #[arg(long, value_name = "COUNT", default_value_t = 400)]
+    pub frames: u64,
+
+    /// Size of the screen to draw on, in columns and rows
+    #[arg(long, value_name = "COLUMNSxROWS", default_value = "80x24", value_parser = parse_size)]
+    pub size: (u16, u16),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ListArgs {
+    /// Only list these
+    #[arg(value_enum)]
+    pub names: Option<Listing>,
+}
+
+// What `list` can list
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Listing {
+    Charsets,
+    Effects,
+    Themes,
+    Weathers,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CompletionsArgs {
+    /// Shell to complete in
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct TuneArgs {
+    /// Show the settings measured without saving them
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+fn parse_in_range(value: &str, range: RangeInclusive<f64>) -> Result<f64, String> {
+    let number: f64 = value
+        .parse()
+        .map_err(|_| format!("`{value}` isn't a number"))?;
+    if range.contains(&number) {
+        Ok(number)
+    } else {
+        Err(format!("`{value}` isn't between {:.1} and {:.1}", range.start(), range.end()))
+    }
+}
+
+#[cfg(feature = "vt")]
+fn parse_size(value: &str) -> Result<(u16, u16), String> {
+    let invalid = || format!("`{value}` isn't a size in columns and rows, like `80x24`");
+    let (columns, rows) = value.split_once('x').ok_or_else(invalid)?;
+    match (columns.parse(), rows.parse()) {
+        (Ok(columns), Ok(rows)) if columns > 0 && rows > 0 => Ok((columns, rows)),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_density(value: &str) -> Result<f64, String> {
+    parse_in_range(value, DENSITY_RANGE)
+}
+
+fn parse_stack_height(value: &str) -> Result<f64, String> {
+    parse_in_range(value, MAX_STACK_HEIGHT_RANGE)
+}
+
+fn parse_cell_aspect(value: &str) -> Result<f64, String> {
+    parse_in_range(value, CELL_ASPECT_RANGE)
+}
+
+fn parse_brightness(value: &str) -> Result<f64, String> {
+    parse_in_range(value, BRIGHTNESS_RANGE)
+}
+
+fn parse_fps(value: &str) -> Result<f64, String> {
+    parse_in_range(value, 1.0..=240.0)
+}
+
+fn parse_percent(value: &str) -> Result<f64, String> {
+    let percent: f64 = value
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("`{value}` isn't a number"))?;
+    if (0.0..=100.0).contains(&percent) {
+        Ok(percent)
+    } else {
+        Err(format!("`{value}` isn't between 0 and 100"))
+    }
+}
+
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    pattern::parse_duration(value)
+}
+
+fn parse_attributes(value: &str) -> Result<Attributes, String> {
+    value.parse()
+}
+
+fn parse_reserved(value: &str) -> Result<Reserved, String> {
+    value.parse()
+}
+
+fn parse_schedule(value: &str) -> Result<Schedule, String> {
+    value.parse()
+}
+
+fn parse_spawn_pattern(value: &str) -> Result<SpawnPattern, String> {
+    value.parse()
+}
+
+fn parse_color_stops(value: &str) -> Result<ColorStops, String> {
+    value.parse()
+}
+
+fn parse_color_threshold(value: &str) -> Result<f64, String> {
+    parse_in_range(value, 1.0..=100.0)
+}
+
+fn parse_chance(value: &str) -> Result<f64, String> {
+    let chance: f64 = value
+        .parse()
+        .map_err(|_| format!("`{value}` isn't a number"))?;
+    if (0.0..=1.0).contains(&chance) {
+        Ok(chance)
+    } else {
+        Err(format!("`{value}` isn't between 0.0 and 1.0"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn durations_take_a_unit_and_refuse_to_overflow() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1.5m"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert!(parse_duration("5d").is_err());
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("99999999999999999999h").is_err());
+    }
+
+    #[cfg(feature = "vt")]
+    #[test]
+    fn export_sizes_are_columns_by_rows() {
+        assert_eq!(parse_size("80x24"), Ok((80, 24)));
+        assert!(parse_size("80").is_err());
+        assert!(parse_size("0x24").is_err());
+        assert!(parse_size("80x-1").is_err());
+    }
+
+    #[test]
+    fn run_is_the_mode_without_a_subcommand() {
+        let cli = Cli::try_parse_from(["falling_glyphs", "--density", "0.3", "run"]).unwrap();
+        assert!(matches!(cli.mode, Some(Mode::Run)));
+        let cli = Cli::try_parse_from(["falling_glyphs", "bench", "--duration", "1s"]).unwrap();
+        assert!(matches!(cli.mode, Some(Mode::Stress(_))));
+    }
+
+    #[test]
+    fn completions_are_generated_from_the_options() {
+        let cli = Cli::try_parse_from(["falling_glyphs", "completions", "fish"]).unwrap();
+        let Some(Mode::Completions(args)) = cli.mode else {
+            panic!("not the completions subcommand");
+        };
+        let mut script = Vec::new();
+        clap_complete::generate(args.shell, &mut Cli::command(), "falling_glyphs", &mut script);
+        let script = String::from_utf8(script).unwrap();
+        assert!(script.contains("-l max-stack-height"));
+        assert!(script.contains("completions"));
+    }
+}
@@ -0,0 +1,39 @@
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+// Commands read line-by-line from stdin while the animation runs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    // Percentage complete (e.g. `42`, `42%` or `42.5`)
+    Progress(f64),
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let percent: f64 = line.strip_suffix('%').unwrap_or(line).trim().parse().ok()?;
+        percent
+            .is_finite()
+            .then(|| Command::Progress(percent.clamp(0.0, 100.0)))
+    }
+}
+
+// Reads commands from stdin on a background thread. The channel disconnects
+// once stdin is closed. Lines that aren't commands are ignored.
+pub fn spawn_stdin_reader() -> Receiver<Command> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if let Some(command) = Command::parse(&line) {
+                if sender.send(command).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    receiver
+}
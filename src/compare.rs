@@ -0,0 +1,140 @@
+use std::env;
+use std::ffi::OsString;
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use falling_glyphs::vt::{self, Cell, Screen};
+
+use crate::cli::CompareArgs;
+use crate::selftest::{self, COLUMNS, ROWS};
+
+// Marks the end of each frame, as the renderer draws them in synchronized
+// updates inside a pty
+pub const FRAME_END: &str = "\x1b[?2026l";
+
+// How many differing cells of a frame are listed
+const MAX_REPORTED: usize = 10;
+
+// How long past its frames (at a frame a second or faster) a run may take
+// to exit before it's killed
+const EXIT_GRACE: Duration = Duration::from_secs(10);
+
+fn describe(cell: &Cell) -> String {
+    let ch = match cell.ch {
+        '\0' => "the right half of a wide glyph".to_string(),
+        ch => format!("{:?}", ch),
+    };
+    let description = match (cell.foreground.as_str(), cell.background.as_str()) {
+        ("", "") => ch,
+        (foreground, "") => format!("{} (colored {})", ch, foreground),
+        ("", background) => format!("{} (over {})", ch, background),
+        (foreground, background) => format!("{} (colored {} over {})", ch, foreground, background),
+    };
+    match cell.attributes.as_str() {
+        "" => description,
+        attributes => format!("{} with attributes {}", description, attributes),
+    }
+}
+
+// The cells that differ between two screens
+fn differences(a: &Screen, b: &Screen) -> Vec<(u16, u16)> {
+    (0..ROWS)
+        .flat_map(|y| (0..COLUMNS).map(move |x| (x, y)))
+        .filter(|(x, y)| a.cell(*x, *y) != b.cell(*x, *y))
+        .collect()
+}
+
+// Runs the rain twice in pseudo terminals with the same seed, stepping it a
+// tick per frame so both draw the same frames, and checks every frame leaves
+// the two screens alike cell for cell, however differently each got there.
+// Options given before the subcommand apply to both runs.
+pub fn run(args: &CompareArgs, seed: Option<u64>) -> io::Result<()> {
+    let failed = |message: String| io::Error::other(format!("compare failed: {}", message));
+
+    let mut common = selftest::run_args("compare");
+    let seed = seed.unwrap_or_else(|| {
+        let seed: u64 = rand::random();
+        common.push("--seed".into());
+        common.push(seed.to_string().into());
+        seed
+    });
+    common.push("--lockstep".into());
+    common.push("--frames".into());
+    common.push(args.frames.to_string().into());
+    let run_args = |options: &str| {
+        let mut run_args = common.clone();
+        run_args.extend(options.split_whitespace().map(OsString::from));
+        run_args
+    };
+    let (a_args, b_args) = (run_args(&args.a), run_args(&args.b));
+    let limit = Duration::from_secs(args.frames) + EXIT_GRACE;
+
+    let a_exe = env::current_exe()?;
+    let b_exe = args.b_exe.clone().unwrap_or_else(|| a_exe.clone());
+
+    println!(
+        "Comparing {} frames of `{}` (A) and `{}` (B) with seed {} in {}x{} ptys...",
+        args.frames, args.a, args.b, seed, COLUMNS, ROWS
+    );
+    let (a, b) = thread::scope(|scope| {
+        let a = scope.spawn(|| vt::capture(&a_exe, a_args, (COLUMNS, ROWS), limit));
+        let b = scope.spawn(|| vt::capture(&b_exe, b_args, (COLUMNS, ROWS), limit));
+        let panicked = |_| Err(io::Error::other("the run panicked"));
+        (
+            a.join().unwrap_or_else(panicked),
+            b.join().unwrap_or_else(panicked),
+        )
+    });
+    let a = a.map_err(|e| failed(format!("A: {}", e)))?;
+    let b = b.map_err(|e| failed(format!("B: {}", e)))?;
+
+    let a_frames: Vec<&str> = a.split_inclusive(FRAME_END).collect();
+    let b_frames: Vec<&str> = b.split_inclusive(FRAME_END).collect();
+    let frames = a.matches(FRAME_END).count();
+    if frames != b.matches(FRAME_END).count() {
+        return Err(failed(format!(
+            "A drew {} frames but B drew {}",
+            frames,
+            b.matches(FRAME_END).count()
+        )));
+    }
+
+    let (mut a_screen, mut b_screen) = (Screen::new(COLUMNS, ROWS), Screen::new(COLUMNS, ROWS));
+    for (frame, (a_frame, b_frame)) in a_frames.iter().zip(&b_frames).enumerate() {
+        a_screen
+            .feed(a_frame)
+            .map_err(|e| failed(format!("A: {}", e)))?;
+        b_screen
+            .feed(b_frame)
+            .map_err(|e| failed(format!("B: {}", e)))?;
+        let cells = differences(&a_screen, &b_screen);
+        if cells.is_empty() {
+            continue;
+        }
+
+        println!("Frame {} differs in {} cells:", frame + 1, cells.len());
+        for (x, y) in cells.iter().take(MAX_REPORTED) {
+            println!(
+                "  {},{}: {} in A, {} in B",
+                x,
+                y,
+                describe(a_screen.cell(*x, *y)),
+                describe(b_screen.cell(*x, *y))
+            );
+        }
+        return Err(failed(format!(
+            "the screens diverged at frame {} (replay with --seed {} --lockstep)",
+            frame + 1,
+            seed
+        )));
+    }
+
+    println!(
+        "Identical: {} frames, A wrote {} bytes and B {} bytes",
+        frames,
+        a.len(),
+        b.len()
+    );
+    Ok(())
+}
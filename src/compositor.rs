@@ -0,0 +1,115 @@
+use crate::game::{AnsiColor, Attributes, Cell, Viewport};
+
+// What a layer contributes to a cell of the view beneath it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LayerCell {
+    // A glyph of its own, blended over the cell beneath with an opacity
+    // (0.0 - 1.0). Below half opacity, a glyph beneath shows through.
+    Glyph {
+        ch: char,
+        color: AnsiColor,
+        alpha: f64,
+    },
+    // A color laid over the glyph beneath (if any) with an opacity
+    Tint {
+        color: AnsiColor,
+        alpha: f64,
+    },
+    // Hides whatever is beneath
+    Clear,
+}
+
+// Blends a color over another, keeping the basic colors intact when either
+// is fully opaque
+fn blend(base: AnsiColor, over: AnsiColor, alpha: f64) -> AnsiColor {
+    if alpha >= 1.0 {
+        over
+    } else if alpha <= 0.0 {
+        base
+    } else {
+        base.blend(over, alpha)
+    }
+}
+
+// A screen-sized layer of cells, composited down onto the view beneath it.
+// Cells a layer leaves empty show the view through unchanged.
+#[derive(Clone, Default)]
+pub struct Layer {
+    width: u16,
+    height: u16,
+    cells: Vec<Option<LayerCell>>,
+}
+
+impl Layer {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![None; width as usize * height as usize],
+        }
+    }
+
+    // A layer of a view's glyphs at an opacity, for compositing one effect
+    // over another
+    pub fn from_view(view: &Viewport, alpha: f64) -> Self {
+        let mut layer = Self::new(view.width(), view.height());
+        for y in 0..view.height() {
+            for x in 0..view.width() {
+                if let Some(cell) = view.get(x, y) {
+                    let (ch, color) = (cell.ch, cell.color);
+                    layer.set(x, y, LayerCell::Glyph { ch, color, alpha });
+                }
+            }
+        }
+        layer
+    }
+
+    pub fn set(&mut self, x: u16, y: u16, cell: LayerCell) {
+        if x < self.width && y < self.height {
+            self.cells[y as usize * self.width as usize + x as usize] = Some(cell);
+        }
+    }
+
+    // Lays the same cell over every cell of the layer
+    pub fn fill(&mut self, cell: LayerCell) {
+        self.cells.fill(Some(cell));
+    }
+
+    // Blends the layer down onto the view
+    pub fn composite(&self, view: &mut Viewport) {
+        let width = self.width.min(view.width());
+        for y in 0..self.height.min(view.height()) {
+            for x in 0..width {
+                let Some(layer_cell) = self.cells[y as usize * self.width as usize + x as usize]
+                else {
+                    continue;
+                };
+                let base = view.get(x, y);
+                let cell = match layer_cell {
+                    LayerCell::Glyph { ch, color, alpha } => {
+                        let beneath = base.map_or(AnsiColor::Rgb(0, 0, 0), |cell| cell.color);
+                        let ch = match base {
+                            Some(cell) if alpha < 0.5 => cell.ch,
+                            _ => ch,
+                        };
+                        Some(Cell {
+                            ch,
+                            color: blend(beneath, color, alpha),
+                            background: None,
+                            attributes: Attributes::default(),
+                        })
+                    }
+                    LayerCell::Tint { color, alpha } => base.map(|cell| Cell {
+                        color: blend(cell.color, color, alpha),
+                        ..cell
+                    }),
+                    LayerCell::Clear => None,
+                };
+                match cell {
+                    Some(cell) => view.set(x, y, cell),
+                    None => view.clear(x, y),
+                }
+            }
+        }
+    }
+}
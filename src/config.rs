@@ -0,0 +1,724 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use clap::parser::ValueSource;
+use clap::{CommandFactory, ValueEnum};
+use falling_glyphs::charset::GlyphSet;
+use falling_glyphs::effects::{EffectKind, PipelineStage};
+use falling_glyphs::events::SimEvent;
+use falling_glyphs::game::Game;
+use falling_glyphs::theme::Theme;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use toml::de::{DeTable, DeValue, ValueDeserializer};
+use toml::Spanned;
+
+use crate::cli::Cli;
+use crate::keymap::{Action, KeyBinding, Keymap};
+use crate::notify::{Notification, Notifier};
+
+// Profile used when none is given with `--profile`
+pub const DEFAULT_PROFILE: &str = "default";
+
+// Options that pick the config itself, so can't be set by a profile
+const UNPROFILED: [&str; 2] = ["config", "profile"];
+
+// Prefix of the environment variables that set options, e.g.
+// FALLING_GLYPHS_DENSITY for `--density`
+const ENV_PREFIX: &str = "FALLING_GLYPHS_";
+
+// The tables of the config file
+const SECTIONS: [&str; 8] = [
+    "keys",
+    "effect-keys",
+    "profile",
+    "terminal",
+    "notify",
+    "pipeline",
+    "glyph-weights",
+    "preset",
+];
+
+// Slots presets are saved in, recalled with the number keys
+pub const PRESET_SLOTS: RangeInclusive<u8> = 1..=9;
+
+// The config file, e.g.
+//
+//   [keys]
+//   x = "increase-density"
+//
+//   [effect-keys.vortex]
+//   v = "cycle-mirror"
+//
+//   [notify]
+//   timer-expired = "desktop"
+//
+//   [pipeline]
+//   haunted = [{ effect = "helix", opacity = 0.3 }, { effect = "rain" }]
+//
+//   [glyph-weights]
+//   binary = { "01" = 20, "日月火水木金土" = 1 }
+//
+//   [profile.ambient]
+//   weather = "drizzle"
+//   smooth = true
+//
+//   [terminal."xterm-256color/WezTerm"]
+//   max-fps = 120
+//   colors = "truecolor"
+//
+//   [preset.1]
+//   name = "calm"
+//   density = 0.2
+//   speed = 6
+//   max-stack-height = 0.3
+//   effect = "rain"
+//   theme = "ice"
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    keys: HashMap<String, Action>,
+    effect_keys: HashMap<String, HashMap<String, Action>>,
+    profile: HashMap<String, toml::Table>,
+    terminal: HashMap<String, toml::Table>,
+    notify: HashMap<String, Notification>,
+    pipeline: HashMap<String, Vec<StageConfig>>,
+    glyph_weights: HashMap<String, HashMap<String, u32>>,
+    preset: HashMap<String, Preset>,
+}
+
+// An effect in a pipeline, composited over the ones listed before it
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct StageConfig {
+    effect: String,
+    #[serde(default = "full_opacity")]
+    opacity: f64,
+}
+
+fn full_opacity() -> f64 {
+    1.0
+}
+
+// Settings saved in a numbered slot, to switch between at runtime
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Preset {
+    // Only ever written by hand, to label the slot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub density: f64,
+    pub speed: u8,
+    pub max_stack_height: f64,
+    pub effect: EffectKind,
+    // Presets saved before themes were kept in them recall the default
+    #[serde(default)]
+    pub theme: Theme,
+}
+
+impl Preset {
+    pub fn capture(game: &Game, name: Option<String>) -> Self {
+        Self {
+            name,
+            density: game.density(),
+            speed: game.speed(),
+            max_stack_height: game.max_stack_height(),
+            effect: game.effect(),
+            theme: game.theme(),
+        }
+    }
+
+    // Restarts the effect only if it's another one, so the rain carries on
+    pub fn apply(&self, game: &mut Game) {
+        game.set_density(self.density);
+        game.set_speed(self.speed);
+        game.set_max_stack_height(self.max_stack_height);
+        if game.effect() != self.effect {
+            game.set_effect(self.effect);
+        }
+        game.set_theme(self.theme);
+    }
+
+    // The slot and its name, if it has one, e.g. `3 (storm)`
+    pub fn label(&self, slot: u8) -> String {
+        match &self.name {
+            Some(name) => format!("{} ({})", slot, name),
+            None => slot.to_string(),
+        }
+    }
+}
+
+// The options set by environment variables, as command line arguments, for
+// the real command line to be parsed over (and over a profile's settings).
+// Flags are set with `1`, `true` or `yes`, and left unset with `0`, `false`,
+// `no` or nothing.
+pub fn env_args() -> Result<Vec<String>, String> {
+    env_args_from(|name| std::env::var(name).ok())
+}
+
+// The options set by the variables the lookup finds
+fn env_args_from(lookup: impl Fn(&str) -> Option<String>) -> Result<Vec<String>, String> {
+    let command = Cli::command();
+    let mut args = Vec::new();
+    for arg in command.get_arguments() {
+        let Some(long) = arg.get_long() else {
+            continue;
+        };
+        let name = format!("{}{}", ENV_PREFIX, long.to_uppercase().replace('-', "_"));
+        let Some(value) = lookup(&name) else {
+            continue;
+        };
+        let flag = format!("--{}", long);
+        if arg.get_action().takes_values() {
+            args.push(format!("{}={}", flag, value));
+            continue;
+        }
+        match value.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" => args.push(flag),
+            "" | "0" | "false" | "no" => {}
+            _ => return Err(format!("{} isn't true or false: `{}`", name, value)),
+        }
+    }
+    Ok(args)
+}
+
+// Saves a preset into the config file at its slot, keeping anything else in
+// the file, like the setup wizard does with the default profile
+pub fn save_preset(path: &Path, slot: u8, preset: &Preset) -> io::Result<()> {
+    update_config(path, |config| {
+        let preset = toml::Table::try_from(preset).map_err(|e| e.to_string())?;
+        section(config, "preset")?.insert(slot.to_string(), preset.into());
+        Ok(())
+    })
+}
+
+// Saves the settings tuned for a terminal into the config file, replacing
+// any tuned before and keeping anything else in the file
+pub fn save_terminal(path: &Path, key: &str, settings: toml::Table) -> io::Result<()> {
+    update_config(path, |config| {
+        section(config, "terminal")?.insert(key.to_string(), settings.into());
+        Ok(())
+    })
+}
+
+// Reads the config file as a table (an empty one if there's no file yet),
+// changes it and writes it back, keeping what the change leaves alone
+fn update_config(
+    path: &Path,
+    update: impl FnOnce(&mut toml::Table) -> Result<(), String>,
+) -> io::Result<()> {
+    let invalid = |e: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid config {}: {}", path.display(), e),
+        )
+    };
+    let mut config = match fs::read_to_string(path) {
+        Ok(text) => text.parse::<toml::Table>().map_err(|e| invalid(e.to_string()))?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => toml::Table::new(),
+        Err(e) => return Err(e),
+    };
+    update(&mut config).map_err(invalid)?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text = toml::to_string(&config).map_err(|e| invalid(e.to_string()))?;
+    fs::write(path, text)
+}
+
+// A table of the config, added if it isn't there yet
+fn section<'a>(config: &'a mut toml::Table, name: &str) -> Result<&'a mut toml::Table, String> {
+    config
+        .entry(name)
+        .or_insert_with(|| toml::Table::new().into())
+        .as_table_mut()
+        .ok_or_else(|| format!("`{}` isn't a table", name))
+}
+
+impl FromStr for Config {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        toml::from_str(text).map_err(|e| e.message().to_string())
+    }
+}
+
+impl Config {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("falling_glyphs").join("config.toml"))
+    }
+
+    // Reads the config at the given path, or the default path if it exists
+    pub fn load(path: Option<&Path>) -> io::Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let invalid = |e: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid config {}: {}", path.display(), e),
+            )
+        };
+        std::fs::read_to_string(&path)?.parse().map_err(invalid)
+    }
+
+    pub fn has_profile(&self, name: &str) -> bool {
+        self.profile.contains_key(name)
+    }
+
+    // The named profile's settings as command line arguments, for the real
+    // command line to be parsed over
+    pub fn profile_args(&self, name: &str) -> Result<Vec<String>, String> {
+        let profile = self
+            .profile
+            .get(name)
+            .ok_or_else(|| format!("no profile named `{}`", name))?;
+        settings_args(profile, &format!("profile `{}`", name))
+    }
+
+    // The settings tuned for a terminal (see `tune`) as command line
+    // arguments, for a profile and the real command line to be parsed over
+    pub fn terminal_args(&self, key: &str) -> Result<Vec<String>, String> {
+        match self.terminal.get(key) {
+            Some(settings) => settings_args(settings, &format!("terminal `{}`", key)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn pipeline(&self, name: &str) -> Result<Vec<PipelineStage>, String> {
+        let stages = self
+            .pipeline
+            .get(name)
+            .ok_or_else(|| format!("no pipeline named `{}`", name))?;
+        stages
+            .iter()
+            .map(|stage| {
+                let effect = EffectKind::from_str(&stage.effect, true)
+                    .map_err(|_| format!("unknown effect `{}`", stage.effect))?;
+                if !(0.0..=1.0).contains(&stage.opacity) {
+                    return Err(format!("opacity {} isn't between 0.0 and 1.0", stage.opacity));
+                }
+                Ok(PipelineStage {
+                    effect,
+                    opacity: stage.opacity,
+                })
+            })
+            .collect()
+    }
+
+    // The glyphs of a named set of weights, each glyph of a key weighted as
+    // the key is
+    pub fn glyph_weights(&self, name: &str) -> Result<GlyphSet, String> {
+        let weights = self
+            .glyph_weights
+            .get(name)
+            .ok_or_else(|| format!("no glyph weights named `{}`", name))?;
+        let weights: Vec<(char, u32)> = weights
+            .iter()
+            .flat_map(|(glyphs, weight)| glyphs.chars().map(|ch| (ch, *weight)))
+            .collect();
+        GlyphSet::from_weights(&weights)
+    }
+
+    // The saved presets, by slot
+    pub fn presets(&self) -> Result<HashMap<u8, Preset>, String> {
+        self.preset
+            .iter()
+            .map(|(slot, preset)| match slot.parse() {
+                Ok(number) if PRESET_SLOTS.contains(&number) => Ok((number, preset.clone())),
+                _ => Err(format!("preset slot `{}` isn't a number from 1 to 9", slot)),
+            })
+            .collect()
+    }
+
+    pub fn notifier(&self) -> Result<Notifier, String> {
+        let mut notifications = HashMap::new();
+        for (name, notification) in &self.notify {
+            let event = SimEvent::ALL
+                .into_iter()
+                .find(|event| event.name() == name)
+                .ok_or_else(|| format!("unknown event `{}`", name))?;
+            notifications.insert(event, *notification);
+        }
+        Ok(Notifier::new(notifications))
+    }
+
+    // The default bindings, with the config's bindings on top
+    pub fn keymap(&self) -> Result<Keymap, String> {
+        let mut keymap = Keymap::default();
+        for (key, action) in &self.keys {
+            keymap.bind(None, KeyBinding::parse(key)?, check_slot(*action)?);
+        }
+        for (effect, keys) in &self.effect_keys {
+            let effect = EffectKind::from_str(effect, true)
+                .map_err(|_| format!("unknown effect `{}`", effect))?;
+            for (key, action) in keys {
+                keymap.bind(Some(effect), KeyBinding::parse(key)?, check_slot(*action)?);
+            }
+        }
+        Ok(keymap)
+    }
+}
+
+// Settings as command line arguments. Settings are named after the long
+// options, and `true` stands for a flag.
+fn settings_args(settings: &toml::Table, owner: &str) -> Result<Vec<String>, String> {
+    let command = Cli::command();
+    let mut args = Vec::new();
+    for (setting, value) in settings {
+        let known = command.get_arguments().any(|arg| arg.get_long() == Some(setting));
+        if !known || UNPROFILED.contains(&setting.as_str()) {
+            return Err(format!("unknown setting `{}` in {}", setting, owner));
+        }
+        let flag = format!("--{}", setting);
+        match value {
+            toml::Value::Boolean(true) => args.push(flag),
+            toml::Value::Boolean(false) => {}
+            toml::Value::String(value) => args.push(format!("{}={}", flag, value)),
+            toml::Value::Integer(value) => args.push(format!("{}={}", flag, value)),
+            toml::Value::Float(value) => args.push(format!("{}={}", flag, value)),
+            _ => {
+                return Err(format!(
+                    "setting `{}` in {} isn't a string, number or boolean",
+                    setting, owner
+                ))
+            }
+        }
+    }
+    Ok(args)
+}
+
+// Drops the arguments (each a single `--long` or `--long=value`) that the
+// arguments parsed over them replace: the same option, or one that can't
+// be given with it, like a profile's `charset` under `--glyph-range`.
+pub fn drop_overridden(args: Vec<String>, over: &[String]) -> Vec<String> {
+    let command = Cli::command();
+    let argv = std::iter::once("falling_glyphs").chain(over.iter().map(String::as_str));
+    let Ok(matches) = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(argv)
+    else {
+        return args;
+    };
+    let given: Vec<&clap::Arg> = command
+        .get_arguments()
+        .filter(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine))
+        .collect();
+    let overridden = |arg: &clap::Arg| {
+        given.iter().any(|given| {
+            given.get_id() == arg.get_id()
+                || command.get_arg_conflicts_with(given).contains(&arg)
+                || command.get_arg_conflicts_with(arg).contains(given)
+        })
+    };
+    args.into_iter()
+        .filter(|flag| {
+            let (long, _) = flag.split_once('=').unwrap_or((flag, ""));
+            let long = long.strip_prefix("--");
+            !command
+                .get_arguments()
+                .any(|arg| arg.get_long() == long && overridden(arg))
+        })
+        .collect()
+}
+
+// A problem found checking the config file, and the line it's on
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    pub line: usize,
+    pub message: String,
+}
+
+// Collects the problems of a config file with the lines they're on
+struct Checker<'t> {
+    text: &'t str,
+    problems: Vec<Problem>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, span: Range<usize>, message: String) {
+        let start = span.start.min(self.text.len());
+        let line = self.text[..start].matches('\n').count() + 1;
+        self.problems.push(Problem { line, message });
+    }
+
+    // A value as the type it should be, reporting where it isn't
+    fn typed<T: DeserializeOwned>(&mut self, value: &Spanned<DeValue>) -> Option<T> {
+        T::deserialize(ValueDeserializer::from(value.clone()))
+            .map_err(|e| self.report(e.span().unwrap_or(value.span()), e.message().to_string()))
+            .ok()
+    }
+
+    // The entries of a table, reporting a value that isn't one
+    fn table<'a, 'i>(
+        &mut self,
+        name: &str,
+        value: &'a Spanned<DeValue<'i>>,
+    ) -> Option<&'a DeTable<'i>> {
+        match value.get_ref() {
+            DeValue::Table(table) => Some(table),
+            _ => {
+                self.report(value.span(), format!("`{}` isn't a table", name));
+                None
+            }
+        }
+    }
+
+    fn keys(&mut self, keys: &DeTable) {
+        for (key, action) in keys {
+            if let Err(e) = KeyBinding::parse(key.get_ref()) {
+                self.report(key.span(), e);
+            }
+            if let Some(action) = self.typed::<Action>(action) {
+                if let Err(e) = check_slot(action) {
+                    self.report(key.span(), e);
+                }
+            }
+        }
+    }
+
+    // Settings of a profile or a terminal, each checked as the option it sets
+    fn settings(&mut self, settings: &DeTable, owner: &str) {
+        for (setting, value) in settings {
+            let Some(value) = self.typed::<toml::Value>(value) else {
+                continue;
+            };
+            let table = toml::Table::from_iter([(setting.get_ref().to_string(), value)]);
+            let args = match settings_args(&table, owner) {
+                Ok(args) => args,
+                Err(e) => {
+                    self.report(setting.span(), e);
+                    continue;
+                }
+            };
+            let argv = std::iter::once("falling_glyphs".to_string()).chain(args);
+            if let Err(e) = Cli::command().try_get_matches_from(argv) {
+                let message = e.to_string();
+                let line = message.lines().next().unwrap_or_default();
+                let message = format!("{} in {}", line.trim_start_matches("error: "), owner);
+                self.report(setting.span(), message);
+            }
+        }
+    }
+
+    fn stages(&mut self, stages: &Spanned<DeValue>) {
+        let DeValue::Array(stages) = stages.get_ref() else {
+            self.report(
+                stages.span(),
+                "a pipeline isn't a list of stages".to_string(),
+            );
+            return;
+        };
+        for stage in stages {
+            let Some(config) = self.typed::<StageConfig>(stage) else {
+                continue;
+            };
+            if EffectKind::from_str(&config.effect, true).is_err() {
+                self.report(stage.span(), format!("unknown effect `{}`", config.effect));
+            }
+            if !(0.0..=1.0).contains(&config.opacity) {
+                let message = format!("opacity {} isn't between 0.0 and 1.0", config.opacity);
+                self.report(stage.span(), message);
+            }
+        }
+    }
+}
+
+// Checks a config file through, finding every problem in it (unknown
+// sections and settings, values out of range, names of nothing) rather than
+// stopping at the first as loading it does
+pub fn validate(text: &str) -> Vec<Problem> {
+    let (document, errors) = DeTable::parse_recoverable(text);
+    let mut checker = Checker {
+        text,
+        problems: Vec::new(),
+    };
+    for error in errors {
+        checker.report(error.span().unwrap_or(0..0), error.message().to_string());
+    }
+    for (section, entries) in document.get_ref() {
+        let section_name = section.get_ref().as_ref();
+        if !SECTIONS.contains(&section_name) {
+            checker.report(
+                section.span(),
+                format!("unknown section `{}`", section_name),
+            );
+            continue;
+        }
+        let Some(entries) = checker.table(section_name, entries) else {
+            continue;
+        };
+        if section_name == "keys" {
+            checker.keys(entries);
+            continue;
+        }
+        for (key, value) in entries {
+            let name = key.get_ref().as_ref();
+            match section_name {
+                "effect-keys" => {
+                    if EffectKind::from_str(name, true).is_err() {
+                        checker.report(key.span(), format!("unknown effect `{}`", name));
+                    }
+                    if let Some(keys) = checker.table(name, value) {
+                        checker.keys(keys);
+                    }
+                }
+                "profile" | "terminal" => {
+                    if let Some(settings) = checker.table(name, value) {
+                        checker.settings(settings, &format!("{} `{}`", section_name, name));
+                    }
+                }
+                "notify" => {
+                    if !SimEvent::ALL.iter().any(|event| event.name() == name) {
+                        checker.report(key.span(), format!("unknown event `{}`", name));
+                    }
+                    checker.typed::<Notification>(value);
+                }
+                "pipeline" => checker.stages(value),
+                "glyph-weights" => {
+                    let Some(weights) = checker.typed::<HashMap<String, u32>>(value) else {
+                        continue;
+                    };
+                    let weights: Vec<(char, u32)> = weights
+                        .iter()
+                        .flat_map(|(glyphs, weight)| glyphs.chars().map(|ch| (ch, *weight)))
+                        .collect();
+                    if let Err(e) = GlyphSet::from_weights(&weights) {
+                        checker.report(key.span(), e);
+                    }
+                }
+                _ => {
+                    if !name.parse().is_ok_and(|slot| PRESET_SLOTS.contains(&slot)) {
+                        let message = format!("preset slot `{}` isn't a number from 1 to 9", name);
+                        checker.report(key.span(), message);
+                    }
+                    checker.typed::<Preset>(value);
+                }
+            }
+        }
+    }
+    checker.problems.sort_by_key(|problem| problem.line);
+    checker.problems
+}
+
+fn check_slot(action: Action) -> Result<Action, String> {
+    match action {
+        Action::SavePreset(slot) | Action::RecallPreset(slot) if !PRESET_SLOTS.contains(&slot) => {
+            Err(format!("preset slot {} isn't from 1 to 9", slot))
+        }
+        _ => Ok(action),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn environment_variables_set_options_with_their_values_attached() {
+        let env = env_args_from(|name| match name {
+            "FALLING_GLYPHS_SPOTLIGHT" => Some("3".to_string()),
+            "FALLING_GLYPHS_SMOOTH" => Some("yes".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert!(env.contains(&"--spotlight=3".to_string()));
+        assert!(env.contains(&"--smooth".to_string()));
+        let argv = std::iter::once("falling_glyphs".to_string()).chain(env);
+        assert!(Cli::try_parse_from(argv).is_ok());
+    }
+
+    #[test]
+    fn profile_settings_become_options_with_their_values_attached() {
+        let text = "[profile.calm]\nspeed = 5\ntheme = \"protanopia\"\nmask = \"stripes\"\n";
+        let config: Config = toml::from_str(text).unwrap();
+        let mut settings = config.profile_args("calm").unwrap();
+        settings.sort();
+        assert_eq!(
+            settings,
+            args(&["--mask=stripes", "--speed=5", "--theme=protanopia"])
+        );
+        let config: Config = toml::from_str("[profile.odd]\njiter = 5\n").unwrap();
+        assert!(config.profile_args("odd").is_err());
+        let given = args(&["--trail-colors", "0=white,100=green", "--stencil=logo.txt"]);
+        assert_eq!(drop_overridden(settings, &given), args(&["--speed=5"]));
+    }
+
+    #[test]
+    fn saving_presets_and_tuned_settings_keeps_the_rest_of_the_config() {
+        let dir = std::env::temp_dir().join(format!("falling_glyphs-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "[keys]\nx = \"increase-density\"\n").unwrap();
+        let preset = Preset {
+            name: None,
+            density: 0.3,
+            speed: 5,
+            max_stack_height: 0.5,
+            effect: EffectKind::default(),
+            theme: Theme::Amber,
+        };
+        save_preset(&path, 2, &preset).unwrap();
+        save_terminal(&path, "xterm", toml::Table::new()).unwrap();
+        let config: Config = fs::read_to_string(&path).unwrap().parse().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(config.keys.len(), 1);
+        assert_eq!(config.preset["2"].speed, 5);
+        assert_eq!(config.preset["2"].theme, Theme::Amber);
+        assert!(config.terminal.contains_key("xterm"));
+    }
+
+    #[test]
+    fn options_give_way_to_the_same_or_conflicting_options_given_over_them() {
+        let env = args(&[
+            "--charset=latin",
+            "--effect=helix",
+            "--density=0.3",
+            "--speed=5",
+        ]);
+        let given = args(&[
+            "--glyph-range",
+            "U+30A0-U+30FF",
+            "--pipeline",
+            "mine",
+            "--speed=20",
+        ]);
+        assert_eq!(drop_overridden(env, &given), args(&["--density=0.3"]));
+    }
+
+    #[test]
+    fn validating_reports_every_problem_with_its_line() {
+        let text = "\
+[keys]
+q = \"quit\"
+z = \"explode\"
+
+[profile.calm]
+speed = 500
+charset = \"wingdings\"
+
+[pipeline]
+mine = [{ effect = \"rain\", opacity = 2.0 }]
+
+[colours]
+";
+        let lines: Vec<usize> = validate(text).iter().map(|problem| problem.line).collect();
+        assert_eq!(lines, [3, 6, 7, 10, 12]);
+        assert!(validate("[keys]\nq = \"quit\"\n").is_empty());
+    }
+}
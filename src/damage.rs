@@ -0,0 +1,57 @@
+use falling_glyphs::game::{Change, Rect};
+
+// Tracks the screen regions covered by overlays (like the debug view), so
+// rain changes underneath them can be held back and the underlying cells
+// re-emitted once an overlay shrinks or is removed.
+#[derive(Default)]
+pub struct DamageTracker {
+    covered: Vec<Rect>,
+    previous: Vec<Rect>,
+}
+
+impl DamageTracker {
+    // Starts a new frame, in which overlays register the cells they cover again
+    pub fn begin_frame(&mut self) {
+        self.previous = std::mem::take(&mut self.covered);
+    }
+
+    // Forgets every region, for when the screen has been cleared
+    pub fn reset(&mut self) {
+        self.covered.clear();
+        self.previous.clear();
+    }
+
+    pub fn cover(&mut self, region: Rect) {
+        if region.width > 0 && region.height > 0 {
+            self.covered.push(region);
+        }
+    }
+
+    pub fn is_covered(&self, x: u16, y: u16) -> bool {
+        self.covered.iter().any(|r| r.contains(x, y))
+    }
+
+    // Regions covered last frame that may now be (partially) exposed. Cells
+    // still covered should be skipped when repainting them.
+    pub fn exposed(&self) -> Vec<Rect> {
+        self.previous
+            .iter()
+            .filter(|r| !self.covered.contains(r))
+            .copied()
+            .collect()
+    }
+
+    // Drops changes to cells hidden under an overlay
+    pub fn filter(&self, changes: Vec<Change>) -> Vec<Change> {
+        if self.covered.is_empty() {
+            return changes;
+        }
+        changes
+            .into_iter()
+            .filter(|change| {
+                let (x, y) = change.position();
+                !self.is_covered(x, y)
+            })
+            .collect()
+    }
+}
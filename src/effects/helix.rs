@@ -0,0 +1,113 @@
+use std::f64::consts::{PI, TAU};
+
+use rand::Rng;
+
+use super::stream::PathStream;
+use super::{Effect, EffectParams, EffectStats};
+use crate::game::{AnsiColor, Attributes, Cell, Viewport};
+use crate::rng::RngStreams;
+
+// Height of a full twist of the helix, in glyph widths
+const TWIST_HEIGHT: f64 = 16.0;
+
+// Rows between the rungs joining the strands
+const RUNG_SPACING: u16 = 3;
+
+// The widest the helix gets, in glyph columns either side of its center
+const MAX_AMPLITUDE: f64 = 10.0;
+
+// Each strand is a single line of streams, so they spawn less often than rain
+const SPAWN_SCALE: f64 = 0.5;
+
+// Two intertwined strands of glyphs rising up the middle of the screen, with
+// rungs drawn between them
+#[derive(Default)]
+pub struct Helix {
+    streams: Vec<PathStream>,
+    rungs: Viewport, // the glyphs rungs are drawn with, so they don't flicker
+    cell_aspect: f64,
+}
+
+// Column of a strand (0 or 1) at the given row, kept on even columns so
+// double-width glyphs line up. Rows are scaled to the cells' aspect, so
+// the twists keep their proportions.
+fn strand_x(strand: u8, y: u16, width: u16, cell_aspect: f64) -> i32 {
+    let center = (width / 2) as f64 / 2.0;
+    let amplitude = (center / 2.0).min(MAX_AMPLITUDE);
+    let twist_rows = TWIST_HEIGHT / (cell_aspect / 2.0);
+    let angle = y as f64 * TAU / twist_rows + strand as f64 * PI;
+    (center + amplitude * angle.sin()).round() as i32 * 2
+}
+
+// Cells of a strand from the bottom of the screen to the top
+fn strand_path(strand: u8, width: u16, height: u16, cell_aspect: f64) -> Vec<(i32, i32)> {
+    (0..height)
+        .rev()
+        .map(|y| (strand_x(strand, y, width, cell_aspect), y as i32))
+        .collect()
+}
+
+impl Effect for Helix {
+    fn update(&mut self, params: &EffectParams, rngs: &mut RngStreams) -> EffectStats {
+        let mut stats = EffectStats::default();
+        self.cell_aspect = params.cell_aspect;
+
+        if (self.rungs.width(), self.rungs.height()) != (params.width, params.height) {
+            self.rungs = Viewport::new(params.width, params.height);
+            for y in 0..params.height {
+                for x in (0..params.width).step_by(2) {
+                    let ch = params.glyph_set.random_glyph(&mut rngs.glyphs);
+                    let color = AnsiColor::DarkGreen;
+                    let cell = Cell {
+                        ch,
+                        color,
+                        background: None,
+                        attributes: Attributes::default(),
+                    };
+                    self.rungs.set(x, y, cell);
+                }
+            }
+        }
+
+        let max_length = (params.height as f64 * params.max_stack_height) as usize;
+        for _ in 0..params.spawn_count(&mut rngs.spawn, SPAWN_SCALE) {
+            let strand = rngs.spawn.random_range(0..2);
+            let path = strand_path(strand, params.width, params.height, params.cell_aspect);
+            let interval_range = params.update_interval_ms.clone();
+            let stream = PathStream::new(path, max_length, interval_range, &params.glyph_set, rngs);
+            self.streams.push(stream);
+            stats.spawned += 1;
+        }
+
+        for stream in &mut self.streams {
+            let (dt, speed) = (params.dt, params.speed);
+            if stream.update(dt, speed, params.mutation_rate, &params.glyph_set, rngs) {
+                stats.glyphs += 1;
+            }
+        }
+        self.streams.retain(|s| !s.is_done());
+
+        stats
+    }
+
+    fn render(&self, view: &mut Viewport) {
+        for stream in &self.streams {
+            stream.render(view);
+        }
+
+        // Rungs join the strands on rows where both have glyphs
+        for y in (0..view.height()).step_by(RUNG_SPACING as usize) {
+            let a = strand_x(0, y, view.width(), self.cell_aspect).max(0) as u16;
+            let b = strand_x(1, y, view.width(), self.cell_aspect).max(0) as u16;
+            let (left, right) = (a.min(b), a.max(b));
+            if view.get(left, y).is_none() || view.get(right, y).is_none() {
+                continue;
+            }
+            for x in (left + 2..right).step_by(2) {
+                if let Some(cell) = self.rungs.get(x, y) {
+                    view.set(x, y, cell);
+                }
+            }
+        }
+    }
+}
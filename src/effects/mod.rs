@@ -0,0 +1,112 @@
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::charset::GlyphSet;
+use crate::game::{base_ticks, Viewport};
+use crate::rng::RngStreams;
+
+pub mod helix;
+pub mod sheet;
+pub mod stream;
+pub mod vortex;
+pub mod waterfall;
+
+use helix::Helix;
+use sheet::Sheet;
+use vortex::Vortex;
+use waterfall::Waterfall;
+
+// The settings an effect draws on each update, taken from the game
+#[derive(Clone, Debug)]
+pub struct EffectParams {
+    pub width: u16,
+    pub height: u16,
+    pub density: f64,
+    pub speed: u8,
+    pub max_stack_height: f64,
+    pub update_interval_ms: RangeInclusive<u64>,
+    pub mutation_rate: f64,
+    pub cell_aspect: f64, // height of a cell over its width
+    pub glyph_set: GlyphSet, // the glyphs drawn from
+    pub dt: Duration,     // time since the last update
+}
+
+impl EffectParams {
+    // Number of streams to spawn this update, scaling with speed and the
+    // time step like the rain
+    pub fn spawn_count<R: rand::Rng>(&self, rng: &mut R, scale: f64) -> usize {
+        let rate = self.density * self.speed as f64 / 10.0 * scale * base_ticks(self.dt);
+        rate.floor() as usize + rng.random_bool(rate.fract()) as usize
+    }
+}
+
+// What an effect did during an update, for the debug overlay
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EffectStats {
+    pub spawned: usize,
+    pub glyphs: usize,
+}
+
+/// An alternative to the falling rain, drawn into the simulated view before
+/// zoom, mirroring, lightning and the other overlays are applied.
+pub trait Effect {
+    /// Advances the effect by `params.dt`, drawing randomness from the game's streams.
+    fn update(&mut self, params: &EffectParams, rngs: &mut RngStreams) -> EffectStats;
+
+    /// Draws the effect into a view of the size it was last updated with.
+    fn render(&self, view: &mut Viewport);
+
+    /// Rows every glyph of the effect moved down together in its last update,
+    /// for renderers that can scroll the screen rather than redraw it. Effects
+    /// whose glyphs move independently report none.
+    fn scrolled(&self) -> u16 {
+        0
+    }
+}
+
+// One of the effects of a pipeline, composited over the ones before it at
+// an opacity (0.0 - 1.0)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PipelineStage {
+    pub effect: EffectKind,
+    pub opacity: f64,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, ValueEnum, Deserialize, Serialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum EffectKind {
+    #[default]
+    Rain,
+    Vortex,
+    Helix,
+    Waterfall,
+    Sheet,
+}
+
+impl EffectKind {
+    pub fn next(self) -> Self {
+        match self {
+            EffectKind::Rain => EffectKind::Vortex,
+            EffectKind::Vortex => EffectKind::Helix,
+            EffectKind::Helix => EffectKind::Waterfall,
+            EffectKind::Waterfall => EffectKind::Sheet,
+            EffectKind::Sheet => EffectKind::Rain,
+        }
+    }
+
+    // The rain is run by the game itself, so it has no effect to build
+    pub fn build(self) -> Option<Box<dyn Effect>> {
+        match self {
+            EffectKind::Rain => None,
+            EffectKind::Vortex => Some(Box::new(Vortex::default())),
+            EffectKind::Helix => Some(Box::new(Helix::default())),
+            EffectKind::Waterfall => Some(Box::new(Waterfall::default())),
+            EffectKind::Sheet => Some(Box::new(Sheet::default())),
+        }
+    }
+}
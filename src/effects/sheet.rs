@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use rand::Rng;
+
+use super::{Effect, EffectParams, EffectStats};
+use crate::game::{AnsiColor, Attributes, Cell, Viewport};
+use crate::rng::RngStreams;
+
+// Fraction of the screen streams cover at top density
+const MAX_COVERAGE: f64 = 0.5;
+
+// A stream entering the screen at the top, a glyph a step
+#[derive(Clone, Copy)]
+struct Emitter {
+    entered: u16,
+    length: u16,
+}
+
+impl Emitter {
+    // Colors are fixed as glyphs enter, so they scroll down unchanged: a
+    // white head leading a green trail that darkens over its last third
+    fn color(&self) -> AnsiColor {
+        if self.entered == 0 {
+            AnsiColor::White
+        } else if self.entered < self.length - self.length / 3 {
+            AnsiColor::Green
+        } else {
+            AnsiColor::DarkGreen
+        }
+    }
+}
+
+// Rain that falls as one sheet: every glyph moves down a row at the same
+// time, so a terminal can scroll the screen rather than redraw it. The
+// simulation runs on glyph columns (two cells wide).
+#[derive(Default)]
+pub struct Sheet {
+    columns: u16,
+    rows: VecDeque<Vec<Option<Cell>>>, // the top row first
+    emitters: Vec<Option<Emitter>>,
+    since_step: Duration,
+    scrolled: u16, // rows moved in the last update
+}
+
+impl Sheet {
+    // Moves every row down one, entering the next glyph of each stream in
+    // the top row, and returns the streams started and glyphs entered
+    fn step(&mut self, params: &EffectParams, rngs: &mut RngStreams) -> (usize, usize) {
+        let (mut spawned, mut glyphs) = (0, 0);
+
+        // Streams are half the longest length on average
+        let max_length = ((self.rows.len() as f64 * params.max_stack_height) as u16).max(1);
+        let chance = params.density * MAX_COVERAGE / ((max_length as f64 + 1.0) / 2.0);
+
+        let mut row = vec![None; self.columns as usize];
+        for (x, slot) in self.emitters.iter_mut().enumerate() {
+            if slot.is_none() && rngs.spawn.random_bool(chance.min(1.0)) {
+                let length = rngs.spawn.random_range(1..=max_length);
+                *slot = Some(Emitter { entered: 0, length });
+                spawned += 1;
+            }
+            let Some(emitter) = slot else {
+                continue;
+            };
+            row[x] = Some(Cell {
+                ch: params.glyph_set.random_glyph(&mut rngs.glyphs),
+                color: emitter.color(),
+                background: None,
+                attributes: Attributes::default(),
+            });
+            glyphs += 1;
+            emitter.entered += 1;
+            if emitter.entered == emitter.length {
+                *slot = None;
+            }
+        }
+        self.rows.pop_back();
+        self.rows.push_front(row);
+        (spawned, glyphs)
+    }
+}
+
+impl Effect for Sheet {
+    fn update(&mut self, params: &EffectParams, rngs: &mut RngStreams) -> EffectStats {
+        let mut stats = EffectStats::default();
+        self.scrolled = 0;
+
+        if (self.columns, self.rows.len()) != (params.width / 2, params.height as usize) {
+            self.columns = params.width / 2;
+            self.rows = (0..params.height)
+                .map(|_| vec![None; self.columns as usize])
+                .collect();
+            self.emitters = vec![None; self.columns as usize];
+        }
+        if self.columns == 0 || self.rows.is_empty() {
+            return stats;
+        }
+
+        // Every glyph steps together, at the middle of the update interval
+        // range, catching up on no more than a screenful at once
+        let range = &params.update_interval_ms;
+        let interval = Duration::from_millis((range.start() + range.end()) / 2)
+            .div_f64(params.speed as f64 / 10.0);
+        self.since_step += params.dt;
+        while self.since_step >= interval {
+            if self.scrolled as usize == self.rows.len() {
+                self.since_step = Duration::ZERO;
+                break;
+            }
+            self.since_step -= interval;
+            let (spawned, glyphs) = self.step(params, rngs);
+            stats.spawned += spawned;
+            stats.glyphs += glyphs;
+            self.scrolled += 1;
+        }
+
+        stats
+    }
+
+    fn render(&self, view: &mut Viewport) {
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if let Some(cell) = cell {
+                    view.set(x as u16 * 2, y as u16, *cell);
+                }
+            }
+        }
+    }
+
+    fn scrolled(&self) -> u16 {
+        self.scrolled
+    }
+}
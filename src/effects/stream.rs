@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::charset::GlyphSet;
+use crate::game::{advance_glyphs, AnsiColor, Attributes, Cell, Glyph, Viewport};
+use crate::rng::RngStreams;
+
+// A stream of glyphs following a path of cells, rather than a single column.
+// Its head advances one cell along the path per step, and its tail follows
+// it off the end of the path.
+pub struct PathStream {
+    path: Vec<(i32, i32)>,
+    glyphs: VecDeque<Glyph>,
+    head: usize, // index into the path of the head glyph
+    length: usize,
+    update_interval: Duration,
+    since_update: Duration,
+}
+
+impl PathStream {
+    pub fn new(
+        path: Vec<(i32, i32)>,
+        max_length: usize,
+        interval_range: RangeInclusive<u64>,
+        glyph_set: &GlyphSet,
+        rngs: &mut RngStreams,
+    ) -> Self {
+        let mut glyphs = VecDeque::new();
+        glyphs.push_front(Glyph {
+            value: glyph_set.random_glyph(&mut rngs.glyphs),
+            color: AnsiColor::White,
+            age: 0,
+        });
+        Self {
+            path,
+            glyphs,
+            head: 0,
+            length: rngs.spawn.random_range(1..=max_length.max(1)),
+            update_interval: Duration::from_millis(rngs.spawn.random_range(interval_range)),
+            since_update: Duration::ZERO,
+        }
+    }
+
+    // Advances the stream by dt, returning whether it took a step
+    pub fn update(
+        &mut self,
+        dt: Duration,
+        speed: u8,
+        mutation_rate: f64,
+        glyph_set: &GlyphSet,
+        rngs: &mut RngStreams,
+    ) -> bool {
+        let interval = self.update_interval.div_f64(speed as f64 / 10.0);
+        self.since_update += dt;
+        if self.since_update < interval {
+            return false;
+        }
+        self.since_update = Duration::ZERO;
+        let value = glyph_set.random_glyph(&mut rngs.glyphs);
+        advance_glyphs(&mut self.glyphs, value, self.length, mutation_rate, glyph_set, rngs);
+        self.head += 1;
+        true
+    }
+
+    // The whole stream has run off the end of its path
+    pub fn is_done(&self) -> bool {
+        self.head + 1 >= self.path.len() + self.glyphs.len()
+    }
+
+    pub fn render(&self, view: &mut Viewport) {
+        for (i, glyph) in self.glyphs.iter().enumerate() {
+            let Some(index) = self.head.checked_sub(i) else {
+                continue;
+            };
+            let Some(&(x, y)) = self.path.get(index) else {
+                continue;
+            };
+            if x >= 0 && y >= 0 && x < view.width() as i32 && y < view.height() as i32 {
+                let cell = Cell {
+                    ch: glyph.value,
+                    color: glyph.color,
+                    background: None,
+                    attributes: Attributes::default(),
+                };
+                view.set(x as u16, y as u16, cell);
+            }
+        }
+    }
+}
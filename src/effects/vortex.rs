@@ -0,0 +1,88 @@
+use std::f64::consts::TAU;
+
+use rand::Rng;
+
+use super::stream::PathStream;
+use super::{Effect, EffectParams, EffectStats};
+use crate::game::Viewport;
+use crate::rng::RngStreams;
+
+// Turns each stream makes on its way from the rim to the center
+const TURNS: f64 = 1.5;
+
+// Streams are far longer lived than columns of rain, so fewer are spawned
+const SPAWN_SCALE: f64 = 0.3;
+
+// Glyph streams spiralling in from the edges of the screen toward its center
+#[derive(Default)]
+pub struct Vortex {
+    streams: Vec<PathStream>,
+}
+
+// Cells along a spiral starting at the given angle on a circle touching the
+// screen's longer sides, and winding clockwise into its center. The spiral is
+// traced in glyph columns (two cells wide), with rows scaled to the cells'
+// aspect, so it looks round.
+fn spiral_path(width: u16, height: u16, cell_aspect: f64, start_angle: f64) -> Vec<(i32, i32)> {
+    let row_height = cell_aspect / 2.0; // in glyph widths
+    let center_x = (width / 2) as f64 / 2.0;
+    let center_y = height as f64 / 2.0 * row_height;
+    let start_radius = center_x.max(center_y);
+    let shrink = start_radius / (TURNS * TAU); // radius lost per radian
+
+    let mut path: Vec<(i32, i32)> = Vec::new();
+    let (mut radius, mut angle) = (start_radius, start_angle);
+    while radius > 0.5 {
+        let x = (center_x + radius * angle.cos()).round() as i32;
+        let y = ((center_y + radius * angle.sin()) / row_height).round() as i32;
+        if path.last() != Some(&(x * 2, y)) {
+            path.push((x * 2, y));
+        }
+
+        // Half-cell steps along the arc, so no cell is skipped
+        let step = 0.5 * row_height.min(1.0) / radius.max(1.0);
+        angle += step;
+        radius -= shrink * step;
+    }
+
+    // Streams start at the edge of the screen, not out on the circle
+    let on_screen =
+        |&(x, y): &(i32, i32)| x >= 0 && y >= 0 && x < width as i32 && y < height as i32;
+    let start = path.iter().position(on_screen).unwrap_or(path.len());
+    path.split_off(start)
+}
+
+impl Effect for Vortex {
+    fn update(&mut self, params: &EffectParams, rngs: &mut RngStreams) -> EffectStats {
+        let mut stats = EffectStats::default();
+
+        let max_length = (params.height as f64 * params.max_stack_height) as usize;
+        for _ in 0..params.spawn_count(&mut rngs.spawn, SPAWN_SCALE) {
+            let angle = rngs.spawn.random_range(0.0..TAU);
+            let path = spiral_path(params.width, params.height, params.cell_aspect, angle);
+            if !path.is_empty() {
+                let interval_range = params.update_interval_ms.clone();
+                let glyph_set = &params.glyph_set;
+                let stream = PathStream::new(path, max_length, interval_range, glyph_set, rngs);
+                self.streams.push(stream);
+                stats.spawned += 1;
+            }
+        }
+
+        for stream in &mut self.streams {
+            let (dt, speed) = (params.dt, params.speed);
+            if stream.update(dt, speed, params.mutation_rate, &params.glyph_set, rngs) {
+                stats.glyphs += 1;
+            }
+        }
+        self.streams.retain(|s| !s.is_done());
+
+        stats
+    }
+
+    fn render(&self, view: &mut Viewport) {
+        for stream in &self.streams {
+            stream.render(view);
+        }
+    }
+}
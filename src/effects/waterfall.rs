@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+use super::{Effect, EffectParams, EffectStats};
+use crate::charset::GlyphSet;
+use crate::game::{AnsiColor, Attributes, Cell, Glyph, Viewport};
+use crate::rng::RngStreams;
+
+// The tallest the terrain gets, as a fraction of the screen height
+const MAX_TERRAIN: f64 = 0.4;
+
+// Chance each step that a pooled glyph on the surface evaporates, so pools
+// don't fill the screen
+const EVAPORATION: f64 = 0.002;
+
+// Drops are single glyphs, so far more are spawned than stacks of rain
+const SPAWN_SCALE: f64 = 2.0;
+
+const TERRAIN_GLYPH: char = '█';
+
+// Glyphs falling onto a terrain silhouette along the bottom of the screen,
+// pooling on top of it and flowing sideways into gaps. The simulation runs
+// on glyph columns (two cells wide).
+#[derive(Default)]
+pub struct Waterfall {
+    columns: u16,
+    rows: u16,
+    terrain: Vec<u16>, // height of the terrain in each glyph column
+    water: Vec<Option<Glyph>>,
+    since_step: Option<Duration>, // none until the first step
+}
+
+impl Waterfall {
+    fn reset<R: Rng>(&mut self, width: u16, height: u16, rng: &mut R) {
+        self.columns = width / 2;
+        self.rows = height;
+        self.water = vec![None; self.columns as usize * height as usize];
+
+        // A random walk, smoothed so the hills and valleys are a few columns wide
+        let max_height = (height as f64 * MAX_TERRAIN) as i32;
+        let mut level = rng.random_range(0..=max_height);
+        let walk: Vec<i32> = (0..self.columns)
+            .map(|_| {
+                level = (level + rng.random_range(-2..=2)).clamp(0, max_height);
+                level
+            })
+            .collect();
+        self.terrain = (0..walk.len())
+            .map(|x| {
+                let window = &walk[x.saturating_sub(1)..(x + 2).min(walk.len())];
+                (window.iter().sum::<i32>() / window.len() as i32) as u16
+            })
+            .collect();
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        y as usize * self.columns as usize + x as usize
+    }
+
+    fn is_terrain(&self, x: u16, y: u16) -> bool {
+        y >= self.rows - self.terrain[x as usize]
+    }
+
+    // Whether a glyph could move into the cell. Cells off the sides of the
+    // screen are open, so pools spill over the edges.
+    fn is_open(&self, x: i32, y: u16) -> bool {
+        if x < 0 || x >= self.columns as i32 {
+            return true;
+        }
+        y < self.rows
+            && !self.is_terrain(x as u16, y)
+            && self.water[self.index(x as u16, y)].is_none()
+    }
+
+    // Moves every glyph a cell, returning how many moved
+    fn step(&mut self, rngs: &mut RngStreams, mutation_rate: f64, glyph_set: &GlyphSet) -> usize {
+        let rng = &mut rngs.spawn;
+        let mut moved = 0;
+        let mut stepped = vec![false; self.water.len()];
+
+        // Lower glyphs move first, to make room for the ones above them
+        for y in (0..self.rows).rev() {
+            let flip = rng.random_bool(0.5);
+            for i in 0..self.columns {
+                let x = if flip { self.columns - 1 - i } else { i };
+                let index = self.index(x, y);
+                let Some(mut glyph) = self.water[index] else {
+                    continue;
+                };
+                if stepped[index] {
+                    continue;
+                }
+
+                // Fall straight down, then diagonally, then flow sideways
+                let side = if rng.random_bool(0.5) { 1 } else { -1 };
+                let (x, y) = (x as i32, y);
+                let target = [
+                    (x, y + 1),
+                    (x + side, y + 1),
+                    (x - side, y + 1),
+                    (x + side, y),
+                    (x - side, y),
+                ]
+                .into_iter()
+                .find(|&(tx, ty)| ty < self.rows && self.is_open(tx, ty));
+
+                self.water[index] = None;
+                match target {
+                    // Spilled off the side of the screen
+                    Some((tx, _)) if tx < 0 || tx >= self.columns as i32 => moved += 1,
+                    Some((tx, ty)) => {
+                        glyph.color = if ty > y {
+                            AnsiColor::Green
+                        } else {
+                            AnsiColor::DarkGreen
+                        };
+                        let target = self.index(tx as u16, ty);
+                        self.water[target] = Some(glyph);
+                        stepped[target] = true;
+                        moved += 1;
+                    }
+                    None => {
+                        glyph.color = AnsiColor::DarkGreen;
+                        if rngs.mutation.random_bool(mutation_rate) {
+                            glyph.value = glyph_set.random_glyph(&mut rngs.glyphs);
+                        }
+                        // Only glyphs on the surface of a pool evaporate
+                        let surface = y == 0 || self.water[self.index(x as u16, y - 1)].is_none();
+                        if !(surface && rng.random_bool(EVAPORATION)) {
+                            self.water[index] = Some(glyph);
+                        }
+                    }
+                }
+            }
+        }
+
+        moved
+    }
+}
+
+impl Effect for Waterfall {
+    fn update(&mut self, params: &EffectParams, rngs: &mut RngStreams) -> EffectStats {
+        let mut stats = EffectStats::default();
+
+        if (self.columns, self.rows) != (params.width / 2, params.height) {
+            self.reset(params.width, params.height, &mut rngs.spawn);
+        }
+        if self.columns == 0 || self.rows == 0 {
+            return stats;
+        }
+
+        for _ in 0..params.spawn_count(&mut rngs.spawn, SPAWN_SCALE) {
+            let x = rngs.spawn.random_range(0..self.columns);
+            if self.is_open(x as i32, 0) {
+                let index = self.index(x, 0);
+                self.water[index] = Some(Glyph {
+                    value: params.glyph_set.random_glyph(&mut rngs.glyphs),
+                    color: AnsiColor::White,
+                    age: 0,
+                });
+                stats.spawned += 1;
+            }
+        }
+
+        // Every glyph steps together, at the middle of the update interval range
+        let range = &params.update_interval_ms;
+        let interval = Duration::from_millis((range.start() + range.end()) / 2)
+            .div_f64(params.speed as f64 / 10.0);
+        self.since_step = self.since_step.map(|since| since + params.dt);
+        if self.since_step.is_none_or(|since| since >= interval) {
+            self.since_step = Some(Duration::ZERO);
+            stats.glyphs = self.step(rngs, params.mutation_rate, &params.glyph_set);
+        }
+
+        stats
+    }
+
+    fn render(&self, view: &mut Viewport) {
+        for y in 0..self.rows {
+            for x in 0..self.columns {
+                if self.is_terrain(x, y) {
+                    let cell = Cell {
+                        ch: TERRAIN_GLYPH,
+                        color: AnsiColor::DarkGreen,
+                        background: None,
+                        attributes: Attributes::default(),
+                    };
+                    view.set(x * 2, y, cell);
+                    view.set(x * 2 + 1, y, cell);
+                } else if let Some(glyph) = self.water[self.index(x, y)] {
+                    let cell = Cell {
+                        ch: glyph.value,
+                        color: glyph.color,
+                        background: None,
+                        attributes: Attributes::default(),
+                    };
+                    view.set(x * 2, y, cell);
+                }
+            }
+        }
+    }
+}
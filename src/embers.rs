@@ -0,0 +1,128 @@
+use clap::ValueEnum;
+
+use crate::game::{AnsiColor, Attributes, Cell, Viewport};
+
+// Color of the glyphs left behind, the dimmest the rain is ever drawn
+const EMBER_COLOR: AnsiColor = AnsiColor::Rgb(0, 60, 0);
+
+// Background left behind, dimmer still than the glyphs
+const EMBER_TINT: AnsiColor = AnsiColor::Rgb(0, 30, 0);
+
+// What the cells the rain has faded out of become
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum EmberStyle {
+    // Cleared, back to the terminal's own background
+    #[default]
+    Off,
+    // The glyph last drawn there, kept at the dimmest brightness
+    Glyph,
+    // A dim background, which glyphs drawn there later are drawn over
+    Tint,
+}
+
+// Remembers what has been drawn on screen, so cells the rain has faded out
+// of keep an ember of it. Embers last until the rain passes over them again.
+#[derive(Default)]
+pub struct Embers {
+    style: EmberStyle,
+    cells: Viewport,
+}
+
+impl Embers {
+    pub fn style(&self) -> EmberStyle {
+        self.style
+    }
+
+    pub fn set_style(&mut self, style: EmberStyle) {
+        self.style = style;
+        self.clear();
+    }
+
+    // Forgets every ember, e.g. once the screen shows another part of the canvas
+    pub fn clear(&mut self) {
+        self.cells = Viewport::default();
+    }
+
+    // Leaves an ember in each cell of the view that has been drawn in before
+    // but is empty now, and remembers the cells drawn in now
+    pub fn apply(&mut self, view: &mut Viewport) {
+        if self.style == EmberStyle::Off {
+            return;
+        }
+        let (width, height) = (view.width(), view.height());
+        if (self.cells.width(), self.cells.height()) != (width, height) {
+            self.cells = Viewport::new(width, height);
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let ember = self.cells.get(x, y);
+                match (view.get(x, y), ember) {
+                    (Some(cell), _) => {
+                        if self.style == EmberStyle::Tint && cell.background.is_none() {
+                            let background = ember.and_then(|ember| ember.background);
+                            view.set(x, y, Cell { background, ..cell });
+                        }
+                        self.cells.set(x, y, self.ember(cell));
+                    }
+                    (None, Some(ember)) => view.set(x, y, ember),
+                    (None, None) => {}
+                }
+            }
+        }
+    }
+
+    // What a cell leaves behind once the rain has faded out of it
+    fn ember(&self, cell: Cell) -> Cell {
+        let attributes = Attributes::default();
+        match self.style {
+            EmberStyle::Tint => Cell {
+                ch: ' ',
+                color: EMBER_COLOR,
+                background: Some(EMBER_TINT),
+                attributes,
+            },
+            _ => Cell {
+                ch: cell.ch,
+                color: EMBER_COLOR,
+                background: None,
+                attributes,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyph(ch: char) -> Cell {
+        Cell {
+            ch,
+            color: AnsiColor::Green,
+            background: None,
+            attributes: Attributes::default(),
+        }
+    }
+
+    #[test]
+    fn faded_cells_keep_an_ember_until_rain_passes_again() {
+        let mut embers = Embers::default();
+        embers.set_style(EmberStyle::Glyph);
+        let mut view = Viewport::new(2, 1);
+        view.set(0, 0, glyph('a'));
+        embers.apply(&mut view);
+        assert_eq!(view.get(0, 0), Some(glyph('a')));
+
+        let mut faded = Viewport::new(2, 1);
+        embers.apply(&mut faded);
+        let ember = faded.get(0, 0).unwrap();
+        assert_eq!(ember.ch, 'a');
+        assert_ne!(ember.color, AnsiColor::Green);
+        assert_eq!(faded.get(1, 0), None);
+
+        let mut passed = Viewport::new(2, 1);
+        passed.set(0, 0, glyph('b'));
+        embers.apply(&mut passed);
+        assert_eq!(passed.get(0, 0), Some(glyph('b')));
+    }
+}
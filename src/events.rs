@@ -0,0 +1,28 @@
+// Things that happen during a run, collected by the game for the app to
+// react to (e.g. with notifications)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SimEvent {
+    // The title card has finished typing out
+    TitleRevealed,
+    // The run's `--duration` is up
+    TimerExpired,
+}
+
+impl SimEvent {
+    pub const ALL: [SimEvent; 2] = [SimEvent::TitleRevealed, SimEvent::TimerExpired];
+
+    // Name of the event in config files
+    pub fn name(self) -> &'static str {
+        match self {
+            SimEvent::TitleRevealed => "title-revealed",
+            SimEvent::TimerExpired => "timer-expired",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            SimEvent::TitleRevealed => "the title has been revealed",
+            SimEvent::TimerExpired => "time's up",
+        }
+    }
+}
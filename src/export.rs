@@ -0,0 +1,57 @@
+use std::env;
+use std::io;
+use std::time::Duration;
+
+use falling_glyphs::vt;
+
+use crate::cli::ExportArgs;
+use crate::compare::FRAME_END;
+use crate::replay;
+use crate::selftest;
+
+// How long past its frames (at a frame a second or faster) the run may take
+// to exit before it's killed
+const EXIT_GRACE: Duration = Duration::from_secs(10);
+
+// Runs the rain in a pseudo terminal, stepping it a tick per frame, and
+// saves what it draws as an asciicast with the frames a tick apart, the
+// way a terminal would have shown them. Options given before the subcommand
+// apply to the run.
+pub fn run(args: &ExportArgs, tick_rate: Duration) -> io::Result<()> {
+    let failed = |message: String| io::Error::other(format!("export failed: {}", message));
+
+    let mut run_args = selftest::run_args("export");
+    run_args.push("--lockstep".into());
+    run_args.push("--frames".into());
+    run_args.push(args.frames.to_string().into());
+    let (columns, rows) = args.size;
+    println!(
+        "Drawing {} frames in a {}x{} pty...",
+        args.frames, columns, rows
+    );
+    let limit = Duration::from_secs(args.frames) + EXIT_GRACE;
+    let output = vt::capture(&env::current_exe()?, run_args, args.size, limit)
+        .map_err(|e| failed(e.to_string()))?;
+
+    // What's written after the last frame restores the terminal, which would
+    // leave the recording blank
+    let frames: Vec<&str> = output
+        .split_inclusive(FRAME_END)
+        .filter(|frame| frame.ends_with(FRAME_END))
+        .collect();
+    if frames.is_empty() {
+        return Err(failed(
+            "no frames drawn in synchronized updates (drop --no-sync)".into(),
+        ));
+    }
+    let times = (0..).map(|frame| tick_rate * frame);
+    replay::write_asciicast(&args.output, args.size, times.zip(frames.iter().copied()))
+        .map_err(|e| failed(format!("couldn't write {}: {}", args.output.display(), e)))?;
+    println!(
+        "Saved {} frames ({:.1}s) to {}",
+        frames.len(),
+        (tick_rate * frames.len() as u32).as_secs_f64(),
+        args.output.display()
+    );
+    Ok(())
+}
@@ -1,43 +1,363 @@
-use rand::rngs::ThreadRng;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::VecDeque;
-use std::time::{Duration, Instant};
+use std::hash::{Hash, Hasher};
+use std::ops::{BitOr, RangeInclusive};
+use std::str::FromStr;
+use std::time::Duration;
+use unicode_width::UnicodeWidthChar;
 
-#[derive(Clone, Debug, Default)]
-pub struct DebugInfo {
+use crate::balancer::CoverageBalancer;
+use crate::banner::Banner;
+use crate::caption::Caption;
+use crate::charset::{Charset, GlyphSet};
+use crate::chime::Chime;
+use crate::compositor::Layer;
+use crate::effects::{Effect, EffectKind, EffectParams, EffectStats, PipelineStage};
+use crate::embers::{EmberStyle, Embers};
+use crate::events::SimEvent;
+use crate::gradient::ColorStops;
+use crate::heatmap::HeatMap;
+use crate::lifecycle::{DefaultPhaseStyle, PhaseStyle, StackPhase};
+use crate::lightning::{FlashRegion, FlashStyle, Lightning};
+use crate::mask::Mask;
+use crate::mirror::Mirror;
+use crate::origin::{Edge, Origin};
+use crate::pattern::{SpawnAction, SpawnPattern, SpawnQueue};
+use crate::pool::StackPool;
+use crate::reserved::Reserved;
+use crate::rng::RngStreams;
+use crate::telemetry::{RateSampler, Telemetry, Window};
+use crate::theme::{rotate_hue, ColorMode, Palette, Theme};
+use crate::title::TitleCard;
+use crate::zoom::Zoom;
+use crate::weather::WeatherPreset;
+use crate::wind::Wind;
+
+const DEFAULT_UPDATE_INTERVAL_MS: RangeInclusive<u64> = 50..=250;
+const DEFAULT_MUTATION_RATE: f64 = 0.05;
+
+pub const DENSITY_RANGE: RangeInclusive<f64> = 0.1..=1.0;
+pub const MAX_STACK_HEIGHT_RANGE: RangeInclusive<f64> = 0.1..=1.0;
+pub const SPEED_RANGE: RangeInclusive<u8> = 1..=50;
+pub const CELL_ASPECT_RANGE: RangeInclusive<f64> = 1.0..=4.0;
+pub const BRIGHTNESS_RANGE: RangeInclusive<f64> = 0.2..=2.0;
+
+// Most fonts' cells are about twice as tall as they're wide, making the
+// double-width glyphs roughly square
+const DEFAULT_CELL_ASPECT: f64 = 2.0;
+
+// Increment used by the keyboard controls for density and max stack height
+const SETTING_STEP: f64 = 0.1;
+
+// Increment used by the keyboard controls for the mutation rate
+const MUTATION_STEP: f64 = 0.01;
+
+// The update interval spawn rates, heat decay and lightning chances are
+// given for; longer or shorter time steps scale them to match, so the rain
+// looks the same at any frame rate
+pub const BASE_TICK: Duration = Duration::from_millis(75);
+
+// Simulation steps taken per update while fast-forwarding
+const FAST_FORWARD_STEPS: u32 = 8;
+
+// How long a dissolving stack takes to fade out, and the chance each of its
+// glyphs changes on an update as it goes
+const DISSOLVE_TIME: Duration = Duration::from_millis(500);
+const DISSOLVE_FLICKER_CHANCE: f64 = 0.3;
+
+// Most the oldest glyph of a trail fades toward the background
+const TAIL_FADE: f64 = 0.6;
+
+// How many base ticks a time step spans
+pub fn base_ticks(dt: Duration) -> f64 {
+    dt.as_secs_f64() / BASE_TICK.as_secs_f64()
+}
+
+// Rounds to the nearest step, so repeated increments don't drift
+fn round_step(value: f64) -> f64 {
+    (value / SETTING_STEP).round() * SETTING_STEP
+}
+
+/// Settings a game starts with, each adjustable afterwards through the
+/// `Game` setters (and clamped to the same ranges).
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Settings {
+    /// Stack spawn rate, from 0.1 to 1.0
     pub density: f64,
-    pub max_stack_height: f64,
+    /// Speed level, from 1 to 50 (10 is normal speed)
     pub speed: u8,
-    pub update_delay: u64,
-    pub updates_per_sec: f64,
-    pub glyphs_per_sec: f64,
-    pub glyphs_per_update: usize,
-    pub stacks_per_update: usize,
-    pub min_glyph_delay: u128,
-    pub max_glyph_delay: u128,
+    /// Tallest a stack grows, as a fraction (0.1 - 1.0) of the screen height
+    pub max_stack_height: f64,
+    /// Whether the debug overlay starts shown
+    pub debug: bool,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            density: 0.5,
+            speed: 10,
+            max_stack_height: 0.5,
+            debug: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum AnsiColor {
     White,
     Green,
     DarkGreen,
+    Rgb(u8, u8, u8), // blended colors, for terminals with truecolor support
 }
 
+// Step applied when shifting a blended color brighter or dimmer
+const RGB_SHIFT_STEP: f64 = 0.35;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+impl AnsiColor {
+    pub fn inverted(self) -> Self {
+        match self {
+            AnsiColor::White => AnsiColor::DarkGreen,
+            AnsiColor::Green => AnsiColor::Green,
+            AnsiColor::DarkGreen => AnsiColor::White,
+            AnsiColor::Rgb(r, g, b) => AnsiColor::Rgb(255 - r, 255 - g, 255 - b),
+        }
+    }
+
+    // Rank of the color from dimmest (0) to brightest
+    pub fn brightness(self) -> u8 {
+        match self {
+            AnsiColor::DarkGreen => 0,
+            AnsiColor::Green => 1,
+            AnsiColor::White => 2,
+            AnsiColor::Rgb(r, g, b) => match (r as u16 + g as u16 + b as u16) / 3 {
+                170.. => 2,
+                70..170 => 1,
+                _ => 0,
+            },
+        }
+    }
+
+    // Moves the color up (positive) or down (negative) the brightness steps,
+    // stopping at the brightest and dimmest
+    pub fn shifted(self, steps: i8) -> Self {
+        const STEPS: [AnsiColor; 3] = [AnsiColor::DarkGreen, AnsiColor::Green, AnsiColor::White];
+        if let AnsiColor::Rgb(..) = self {
+            let (target, amount) = if steps > 0 {
+                (AnsiColor::Rgb(255, 255, 255), steps as f64 * RGB_SHIFT_STEP)
+            } else {
+                (AnsiColor::Rgb(0, 0, 0), -steps as f64 * RGB_SHIFT_STEP)
+            };
+            return self.blend(target, amount);
+        }
+        let index = STEPS.iter().position(|c| *c == self).unwrap_or(0) as i8;
+        STEPS[(index + steps).clamp(0, STEPS.len() as i8 - 1) as usize]
+    }
+
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            AnsiColor::White => (255, 255, 255),
+            AnsiColor::Green => (0, 255, 0),
+            AnsiColor::DarkGreen => (0, 128, 0),
+            AnsiColor::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+
+    // Where the color sits in CIELAB, whose distances follow how different
+    // colors look (sRGB under a D65 white)
+    fn to_lab(self) -> (f64, f64, f64) {
+        let (r, g, b) = self.to_rgb();
+        let linear = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let (r, g, b) = (linear(r), linear(g), linear(b));
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+        let f = |t: f64| {
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+    }
+
+    /// How different another color looks: the distance between the two in
+    /// CIELAB (CIE76 delta E), where about 2.3 is just noticeable.
+    pub fn distance(self, other: AnsiColor) -> f64 {
+        let (l1, a1, b1) = self.to_lab();
+        let (l2, a2, b2) = other.to_lab();
+        ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+    }
+
+    // The color with its channels multiplied by the factor, up to full
+    pub fn scaled(self, factor: f64) -> Self {
+        let (r, g, b) = self.to_rgb();
+        let scale = |c: u8| (c as f64 * factor).round().min(255.0) as u8;
+        AnsiColor::Rgb(scale(r), scale(g), scale(b))
+    }
+
+    // Mixes in the given fraction (0.0 - 1.0) of another color
+    pub fn blend(self, other: AnsiColor, amount: f64) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+        let (r1, g1, b1) = self.to_rgb();
+        let (r2, g2, b2) = other.to_rgb();
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * amount).round() as u8;
+        AnsiColor::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+    }
+}
+
+/// Text attributes a glyph is drawn with, as flags combined with `|`. None
+/// by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Attributes(u8);
+
+impl Attributes {
+    pub const BOLD: Self = Self(1);
+    pub const DIM: Self = Self(2);
+    pub const ITALIC: Self = Self(4);
+
+    const NAMES: [(&'static str, Self); 3] =
+        [("bold", Self::BOLD), ("dim", Self::DIM), ("italic", Self::ITALIC)];
+
+    /// Whether all the given attributes are set.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether any of the given attributes are set.
+    pub fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for Attributes {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+// Parses a list of attributes, like `bold,italic`, or `none`
+impl FromStr for Attributes {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut attributes = Self::default();
+        for name in text.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            if name == "none" {
+                continue;
+            }
+            let (_, attribute) = Self::NAMES
+                .into_iter()
+                .find(|(known, _)| *known == name)
+                .ok_or_else(|| {
+                    format!("`{name}` isn't an attribute (use bold, dim, italic or none)")
+                })?;
+            attributes = attributes | attribute;
+        }
+        Ok(attributes)
+    }
+}
+
+// A color of the rain redrawn in a tint: the brightest stay white, and the
+// rest take the tint, darkened for the dimmest
+fn tinted(color: AnsiColor, tint: AnsiColor) -> AnsiColor {
+    match color.brightness() {
+        2 => color,
+        1 => tint,
+        _ => tint.blend(AnsiColor::Rgb(0, 0, 0), 0.5),
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn right(&self) -> u16 {
+        self.x.saturating_add(self.width)
+    }
+
+    pub fn bottom(&self) -> u16 {
+        self.y.saturating_add(self.height)
+    }
+
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Glyph {
     pub value: char,
     pub color: AnsiColor,
+    pub age: u16, // steps since it was pushed
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+// Columns a character takes up on the terminal: two for CJK, emoji and other
+// wide characters (like the katakana glyphs), one for the rest. Characters
+// with no width of their own still take up the cell they're drawn in.
+pub fn display_width(ch: char) -> u16 {
+    ch.width().unwrap_or(1).clamp(1, 2) as u16
+}
+
+// What a cell of a view shows once the view is drawn on the terminal
+#[derive(Clone, Copy, PartialEq)]
+enum Drawn {
+    Empty,
+    Glyph(Cell),
+    Covered, // by the right half of a wide glyph in the cell before
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Cell {
     pub ch: char,
     pub color: AnsiColor,
+    pub background: Option<AnsiColor>, // the terminal's own background if none
+    pub attributes: Attributes,
 }
 
-#[derive(Clone)]
+impl Cell {
+    // The change drawing this cell at a position
+    pub fn change(&self, x: u16, y: u16, priority: ChangePriority) -> Change {
+        let Cell {
+            ch,
+            color,
+            background,
+            attributes,
+        } = *self;
+        Change::Update(x, y, ch, color, background, attributes, priority)
+    }
+}
+
+#[derive(Clone, Default)]
 pub struct Viewport {
     width: u16,
     height: u16,
@@ -49,14 +369,22 @@ impl Viewport {
         Self {
             width,
             height,
-            grid: vec![None; (width * height) as usize],
+            grid: vec![None; width as usize * height as usize],
         }
     }
 
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
     pub fn get(&self, x: u16, y: u16) -> Option<Cell> {
         if x < self.width && y < self.height {
             self.grid
-                .get((y * self.width + x) as usize)
+                .get(y as usize * self.width as usize + x as usize)
                 .cloned()
                 .flatten()
         } else {
@@ -66,117 +394,481 @@ impl Viewport {
 
     pub fn set(&mut self, x: u16, y: u16, cell: Cell) {
         if x < self.width && y < self.height {
-            self.grid[(y * self.width + x) as usize] = Some(cell);
+            self.grid[y as usize * self.width as usize + x as usize] = Some(cell);
+        }
+    }
+
+    // Fraction (0.0 - 1.0) of the view's cells covered by glyphs
+    pub fn coverage(&self) -> f64 {
+        if self.grid.is_empty() {
+            return 0.0;
+        }
+        let covered: usize = self
+            .grid
+            .iter()
+            .flatten()
+            .map(|cell| display_width(cell.ch) as usize)
+            .sum();
+        (covered as f64 / self.grid.len() as f64).min(1.0)
+    }
+
+    pub fn clear(&mut self, x: u16, y: u16) {
+        if x < self.width && y < self.height {
+            self.grid[y as usize * self.width as usize + x as usize] = None;
+        }
+    }
+
+    // Passes the colors of every cell, and its background, through recolor
+    pub fn recolor(&mut self, recolor: impl Fn(AnsiColor) -> AnsiColor) {
+        for cell in self.grid.iter_mut().flatten() {
+            cell.color = recolor(cell.color);
+            cell.background = cell.background.map(&recolor);
+        }
+    }
+
+    // The view as it looks once drawn, without the cells hidden under wide
+    // glyphs or the wide glyphs with no room for their right halves
+    pub fn as_drawn(&self) -> Viewport {
+        let mut drawn_view = Viewport::new(self.width, self.height);
+        for y in 0..self.height {
+            let mut drawn = Drawn::Empty;
+            for x in 0..self.width {
+                drawn = self.drawn(x, y, drawn);
+                if let Drawn::Glyph(cell) = drawn {
+                    drawn_view.set(x, y, cell);
+                }
+            }
+        }
+        drawn_view
+    }
+
+    // What a cell shows once the view is drawn, given what the cell before it
+    // in the row shows. A wide glyph takes up the cell to its right too,
+    // hiding anything there, and one in the last column, with no room for its
+    // right half, isn't drawn.
+    fn drawn(&self, x: u16, y: u16, before: Drawn) -> Drawn {
+        if matches!(before, Drawn::Glyph(cell) if display_width(cell.ch) > 1) {
+            return Drawn::Covered;
+        }
+        match self.get(x, y) {
+            Some(cell) if display_width(cell.ch) > 1 && x + 1 >= self.width => Drawn::Empty,
+            Some(cell) => Drawn::Glyph(cell),
+            None => Drawn::Empty,
+        }
+    }
+
+    // Hash of the view's size and cells, for cheaply comparing views
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (self.width, self.height, &self.grid).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // Moves the rows of the scroll region down, blanking the rows scrolled in
+    pub fn scroll(&mut self, scroll: Scroll) {
+        let width = self.width as usize;
+        let bottom = scroll.bottom.min(self.height);
+        for y in (scroll.top..bottom).rev() {
+            let row = y as usize * width;
+            match y.checked_sub(scroll.rows).filter(|from| *from >= scroll.top) {
+                Some(from) => self
+                    .grid
+                    .copy_within(from as usize * width..from as usize * width + width, row),
+                None => self.grid[row..row + width].fill(None),
+            }
+        }
+    }
+
+    pub fn apply(&mut self, change: &Change) {
+        match *change {
+            Change::Update(x, y, ch, color, background, attributes, _) => {
+                let cell = Cell {
+                    ch,
+                    color,
+                    background,
+                    attributes,
+                };
+                self.set(x, y, cell)
+            }
+            Change::Remove(x, y) => self.clear(x, y),
+        }
+    }
+}
+
+/// How much a change matters to the look of the rain, for embedders that
+/// have to drop or reorder changes (e.g. over a slow link). Higher priorities
+/// compare greater.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChangePriority {
+    /// A glyph changing character in place
+    Mutation,
+    /// A glyph changing color or attributes only, like a trail fading
+    Color,
+    /// A glyph clearing, like the tail of a stack moving on
+    TailClear,
+    /// A glyph appearing in an empty cell, like the head of a stack moving
+    HeadMove,
+}
+
+impl ChangePriority {
+    pub fn classify(old: Option<Cell>, new: Option<Cell>) -> Self {
+        match (old, new) {
+            (_, None) => ChangePriority::TailClear,
+            (None, Some(_)) => ChangePriority::HeadMove,
+            (Some(old), Some(new)) if old.ch == new.ch => ChangePriority::Color,
+            (Some(_), Some(_)) => ChangePriority::Mutation,
         }
     }
 }
 
+/// A scroll for the terminal to make before drawing an update's changes:
+/// rows `top..bottom` move down `rows` rows, and the rows scrolled in at the
+/// top are blank.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scroll {
+    pub top: u16,
+    pub bottom: u16,
+    pub rows: u16,
+}
+
 pub enum Change {
-    Update(u16, u16, char, AnsiColor), // x, y, char, color
-    Remove(u16, u16),                 // x, y
+    // x, y, char, color, background (none for the terminal's own), attributes, priority
+    Update(u16, u16, char, AnsiColor, Option<AnsiColor>, Attributes, ChangePriority),
+    Remove(u16, u16), // x, y
+}
+
+impl Change {
+    pub fn position(&self) -> (u16, u16) {
+        match self {
+            Change::Update(x, y, _, _, _, _, _) | Change::Remove(x, y) => (*x, *y),
+        }
+    }
+
+    pub fn priority(&self) -> ChangePriority {
+        match self {
+            Change::Update(_, _, _, _, _, _, priority) => *priority,
+            Change::Remove(_, _) => ChangePriority::TailClear,
+        }
+    }
 }
 
+// Views of different sizes (a frame straddling a resize) are compared over
+// both of their areas: cells only the old view covered are removed if they
+// held a glyph, and cells only the new view covers are drawn if they hold one.
+// Views are compared as drawn: a wide glyph is drawn over both its cells, and
+// cleared from both by the change to its left cell when it goes (terminals
+// clear both halves of a wide glyph drawn over either), so the cell it
+// covered is never drawn in on its own to split it.
 pub fn diff_viewports(old: &Viewport, new: &Viewport) -> Vec<Change> {
     let mut changes = Vec::new();
-    for y in 0..new.height {
-        for x in 0..new.width {
-            let old_cell = old.get(x, y);
-            let new_cell = new.get(x, y);
-            if old_cell != new_cell {
-                match new_cell {
-                    Some(cell) => changes.push(Change::Update(x, y, cell.ch, cell.color)),
-                    None => changes.push(Change::Remove(x, y)),
+    for y in 0..old.height.max(new.height) {
+        let (mut old_drawn, mut new_drawn) = (Drawn::Empty, Drawn::Empty);
+        for x in 0..old.width.max(new.width) {
+            old_drawn = old.drawn(x, y, old_drawn);
+            new_drawn = new.drawn(x, y, new_drawn);
+            if old_drawn == new_drawn {
+                continue;
+            }
+            match (old_drawn, new_drawn) {
+                (_, Drawn::Glyph(cell)) => {
+                    let old_cell = match old_drawn {
+                        Drawn::Glyph(old_cell) => Some(old_cell),
+                        _ => None,
+                    };
+                    let priority = ChangePriority::classify(old_cell, Some(cell));
+                    changes.push(cell.change(x, y, priority));
+                }
+                // Both halves of a wide glyph are cleared, not just its first cell
+                (Drawn::Glyph(_) | Drawn::Covered, Drawn::Empty) => {
+                    changes.push(Change::Remove(x, y))
                 }
+                _ => {} // newly covered cells are drawn along with the glyph before them
             }
         }
     }
     changes
 }
 
+// Whether a change only recolors a cell of the view, and by less than the
+// threshold (in delta E) for each of its colors
+fn recolors_within(view: &Viewport, change: &Change, threshold: f64) -> bool {
+    let Change::Update(x, y, ch, color, background, attributes, _) = *change else {
+        return false;
+    };
+    let Some(cell) = view.get(x, y) else {
+        return false;
+    };
+    let close = |a: AnsiColor, b: AnsiColor| a.distance(b) < threshold;
+    let backgrounds_close = match (cell.background, background) {
+        (None, None) => true,
+        (Some(a), Some(b)) => close(a, b),
+        _ => false,
+    };
+    cell.ch == ch && cell.attributes == attributes && close(cell.color, color) && backgrounds_close
+}
+
+// Whether a change is left undrawn for now, to go out with a later update: a
+// recolor by less than the threshold, or one in a row of the field (rows of
+// one parity) an interlaced update doesn't draw
+fn held_back(view: &Viewport, change: &Change, threshold: Option<f64>, field: Option<u16>) -> bool {
+    threshold.is_some_and(|threshold| recolors_within(view, change, threshold))
+        || field.is_some_and(|field| change.position().1 % 2 != field)
+}
+
+// The most important of the changes, at most the budget's worth, kept in
+// the order they came in
+fn within_budget(changes: Vec<Change>, budget: usize) -> Vec<Change> {
+    if changes.len() <= budget {
+        return changes;
+    }
+    let mut by_priority: Vec<usize> = (0..changes.len()).collect();
+    by_priority.sort_by_key(|&i| Reverse(changes[i].priority()));
+    let mut kept = vec![false; changes.len()];
+    for &i in &by_priority[..budget] {
+        kept[i] = true;
+    }
+    changes
+        .into_iter()
+        .zip(kept)
+        .filter_map(|(change, kept)| kept.then_some(change))
+        .collect()
+}
+
+// A stack's position is a lane (x is the column, or the row for stacks
+// travelling sideways) and the distances its tail (min_y) and head (max_y)
+// have travelled from the edge it spawned at.
 pub struct GlyphStack {
     pub x: u16,
+    pub edge: Edge,
     pub min_y: i16,
     pub max_y: i16,
     pub stack: VecDeque<Glyph>,
     pub length: u16,
-    pub last_update: Instant,
+    pub since_update: Duration, // simulated time since the last step
     pub update_interval: Duration,
+    pub step_interval: Duration, // update_interval with this step's jitter applied
+    pub phase: StackPhase,
+    pub next_value: char, // the glyph the next step will push, previewed when interpolating
+    pub tint: Option<AnsiColor>, // drawn in this color instead of green, keeping its brightness
+    pub hue: Option<f64>,        // drawn in this hue instead of the theme's colors
+    pub dissolved: Duration,     // time spent dissolving, faded out by DISSOLVE_TIME
+    pub text: Option<VecDeque<char>>, // glyphs to push before random ones, which never mutate
+    pub head_age: u32,                // updates since the head glyph was pushed
+    pub gust: f64,                    // speed multiplier of the wind in its lane
+    pub glyph_set: GlyphSet,          // the glyphs it draws from
+    current_interval: Duration, // step_interval scaled by the speed
+}
+
+// The color of a glyph by its age, in steps, in a stream of the given
+// length: white for the head, green through the younger half of the length,
+// and dark green through the older half
+fn age_color(age: u16, length: usize) -> AnsiColor {
+    match age {
+        0 => AnsiColor::White,
+        _ if (age as usize) * 2 < length => AnsiColor::Green,
+        _ => AnsiColor::DarkGreen,
+    }
 }
 
-fn random_glyph() -> char {
-    let mut rng = ThreadRng::default();
-    std::char::from_u32(rng.gen_range(0x30A0..0x30FF)).unwrap_or('?')
+// How far (0.0 - TAIL_FADE) a glyph at a position (0.0 - 1.0) along its stack
+// has faded toward the background with age: not at all through the younger
+// half, then steadily down to the end of the trail
+fn tail_fade(position: f64) -> f64 {
+    ((position - 0.5) * 2.0).clamp(0.0, 1.0) * TAIL_FADE
+}
+
+// Steps a stream of glyphs forward: ages the glyphs, coloring each by its
+// age, pushes a new, white head glyph, and drops the oldest once the stream
+// is longer than its length. Returns whether the oldest glyph was dropped.
+pub(crate) fn advance_glyphs(
+    glyphs: &mut VecDeque<Glyph>,
+    value: char,
+    length: usize,
+    mutation_rate: f64,
+    glyph_set: &GlyphSet,
+    rngs: &mut RngStreams,
+) -> bool {
+    for glyph in glyphs.iter_mut() {
+        glyph.age = glyph.age.saturating_add(1);
+        glyph.color = age_color(glyph.age, length);
+    }
+
+    // Push a new, white glyph onto the stack
+    glyphs.push_front(Glyph {
+        value,
+        color: AnsiColor::White,
+        age: 0,
+    });
+
+    // If the internal stack is > length, pop the oldest from the stack
+    let popped = glyphs.len() > length;
+    if popped {
+        glyphs.pop_back();
+    }
+
+    // Chance to change a random glyph (5% by default)
+    if glyphs.len() > 1 && rngs.mutation.random_bool(mutation_rate) {
+        let index = rngs.mutation.random_range(0..glyphs.len());
+        if let Some(glyph) = glyphs.get_mut(index) {
+            glyph.value = glyph_set.random_glyph(&mut rngs.glyphs);
+        }
+    }
+
+    popped
 }
 
 impl GlyphStack {
-    pub fn new(x: u16, max_height: u16) -> Self {
-        let mut rng = ThreadRng::default();
-        let length = rng.gen_range(1..=max_height);
-        let update_interval = Duration::from_millis(rng.gen_range(50..=250));
+    pub fn new(
+        x: u16,
+        edge: Edge,
+        max_height: u16,
+        interval_range: RangeInclusive<u64>,
+        rngs: &mut RngStreams,
+    ) -> Self {
+        let mut pool = StackPool::default();
+        let glyph_set = GlyphSet::default();
+        Self::from_pool(x, edge, max_height, interval_range, glyph_set, &mut pool, rngs)
+    }
+
+    // A new stack, its glyphs kept in a buffer from the pool
+    pub(crate) fn from_pool(
+        x: u16,
+        edge: Edge,
+        max_height: u16,
+        interval_range: RangeInclusive<u64>,
+        glyph_set: GlyphSet,
+        pool: &mut StackPool,
+        rngs: &mut RngStreams,
+    ) -> Self {
+        let length = rngs.spawn.random_range(1..=max_height);
+        let update_interval = Duration::from_millis(rngs.spawn.random_range(interval_range));
 
-        let mut stack = VecDeque::with_capacity(length as usize);
+        // Each step pushes its glyph before dropping the oldest, so room for
+        // one more than the length keeps the buffer from ever growing
+        let mut stack = pool.take(length as usize + 1);
         stack.push_front(Glyph {
-            value: random_glyph(),
+            value: glyph_set.random_glyph(&mut rngs.glyphs),
             color: AnsiColor::White,
+            age: 0,
         });
 
         Self {
             x,
+            edge,
             min_y: 0,
             max_y: 0,
             stack,
             length,
-            last_update: Instant::now(),
+            since_update: Duration::ZERO,
             update_interval,
+            step_interval: update_interval,
+            phase: StackPhase::Spawning,
+            next_value: glyph_set.random_glyph(&mut rngs.glyphs),
+            tint: None,
+            hue: None,
+            dissolved: Duration::ZERO,
+            text: None,
+            head_age: 0,
+            gust: 1.0,
+            glyph_set,
+            current_interval: update_interval,
         }
     }
 
-    pub fn update(&mut self, speed: u8) {
-        let interval = Duration::from_millis(
-            (self.update_interval.as_millis() as f64 / (speed as f64 / 10.0)) as u64,
-        );
-        if self.last_update.elapsed() >= interval {
-            self.last_update = Instant::now();
-
-            // Push a new, white glyph onto the stack
-            self.stack.push_front(Glyph {
-                value: random_glyph(),
-                color: AnsiColor::White,
-            });
-
-            // Set the prior leading glyph to light green
-            if self.stack.len() > 1 {
-                if let Some(glyph) = self.stack.get_mut(1) {
-                    glyph.color = AnsiColor::Green;
-                }
-            }
+    // Feeds everything about the stack that steers how it steps to the hasher
+    fn hash_state<H: Hasher>(&self, state: &mut H) {
+        (self.x, self.edge, self.min_y, self.max_y).hash(state);
+        (&self.stack, self.length, self.next_value, self.tint).hash(state);
+        (self.since_update, self.update_interval, self.step_interval).hash(state);
+        (self.current_interval, self.dissolved, self.head_age).hash(state);
+        self.phase.hash(state);
+        (self.hue.map(f64::to_bits), self.gust.to_bits(), &self.text).hash(state);
+        self.glyph_set.hash(state);
+    }
 
-            // If the internal stack is > length, pop the oldest from the stack
-            if self.stack.len() > self.length as usize {
-                self.stack.pop_back();
-                self.min_y += 1;
-            }
+    // How far (0.0 - 1.0) the stack is through dissolving
+    pub fn dissolve_fraction(&self) -> f64 {
+        (self.dissolved.as_secs_f64() / DISSOLVE_TIME.as_secs_f64()).min(1.0)
+    }
 
-            // Find the middle of the stack, and update that glyph to dark green
-            if self.stack.len() > 2 {
-                let mid = self.stack.len() / 2;
-                if let Some(glyph) = self.stack.get_mut(mid) {
-                    if glyph.color == AnsiColor::Green {
-                        glyph.color = AnsiColor::DarkGreen;
-                    }
-                }
+    // Flickers the glyphs left in a dissolving stack and blanks them out at
+    // random, so they're all gone by the time it has faded out
+    fn dissolve(&mut self, dt: Duration, rngs: &mut RngStreams) {
+        let remaining = DISSOLVE_TIME.saturating_sub(self.dissolved);
+        self.dissolved += dt;
+        if self.dissolved >= DISSOLVE_TIME {
+            self.phase = StackPhase::Dead;
+            return;
+        }
+        let vanish_chance = (dt.as_secs_f64() / remaining.as_secs_f64()).min(1.0);
+        for glyph in self.stack.iter_mut().filter(|glyph| glyph.value != ' ') {
+            if rngs.glyphs.random_bool(vanish_chance) {
+                glyph.value = ' ';
+            } else if rngs.glyphs.random_bool(DISSOLVE_FLICKER_CHANCE) {
+                glyph.value = self.glyph_set.random_glyph(&mut rngs.glyphs);
             }
+        }
+    }
 
-            // 5% chance to change a random glyph
-            let mut rng = ThreadRng::default();
-            if self.stack.len() > 1 && rng.gen_bool(0.05) {
-                let index = rng.gen_range(0..self.stack.len());
-                if let Some(glyph) = self.stack.get_mut(index) {
-                    glyph.value = random_glyph();
-                }
+    // How far (0.0 - 1.0) the stack is through its current step
+    pub fn fall_fraction(&self) -> f64 {
+        if self.current_interval.is_zero() {
+            return 1.0;
+        }
+        (self.since_update.as_secs_f64() / self.current_interval.as_secs_f64()).min(1.0)
+    }
+
+    // Jitter is the fraction (0.0 - 1.0) each step's interval may vary by
+    // around the stack's base interval. Dt is the time since the last update.
+    pub fn update(
+        &mut self,
+        dt: Duration,
+        speed: u8,
+        mutation_rate: f64,
+        jitter: f64,
+        travel: u16,
+        rngs: &mut RngStreams,
+    ) {
+        if self.phase == StackPhase::Dissolving {
+            self.dissolve(dt, rngs);
+            return;
+        }
+        let interval = Duration::from_millis(
+            (self.step_interval.as_millis() as f64 / (speed as f64 / 10.0 * self.gust)) as u64,
+        );
+        self.current_interval = interval;
+        self.head_age = self.head_age.saturating_add(1);
+        self.since_update += dt;
+        if self.since_update >= interval {
+            self.since_update = Duration::ZERO;
+            self.head_age = 0;
+
+            self.step_interval = if jitter > 0.0 {
+                self.update_interval.mul_f64(1.0 + rngs.spawn.random_range(-jitter..=jitter))
+            } else {
+                self.update_interval
+            };
+
+            let next_value = match self.text.as_mut().and_then(|text| text.pop_front()) {
+                Some(ch) => ch,
+                None => self.glyph_set.random_glyph(&mut rngs.glyphs),
+            };
+            let value = std::mem::replace(&mut self.next_value, next_value);
+            let length = self.length as usize;
+            let mutation_rate = if self.text.is_some() { 0.0 } else { mutation_rate };
+            let glyph_set = &self.glyph_set;
+            if advance_glyphs(&mut self.stack, value, length, mutation_rate, glyph_set, rngs) {
+                self.min_y += 1;
             }
 
             self.max_y += 1;
+            self.phase = self.phase.next(
+                self.min_y,
+                self.max_y,
+                self.stack.len(),
+                self.length,
+                travel as i16,
+            );
         }
     }
 }
@@ -185,155 +877,2084 @@ pub struct Game {
     width: u16,
     height: u16,
     stacks: Vec<GlyphStack>,
+    pool: StackPool,
     current_view: Viewport,
     density: f64,
     max_stack_height: f64,
     speed: u8,
-    pub debug: bool,
-    pub debug_info: DebugInfo,
-    last_update_time: Instant,
-    update_counter: u32,
-    glyph_counter: usize,
+    update_interval_ms: RangeInclusive<u64>,
+    mutation_rate: f64,
+    jitter: f64,
+    wind: Wind,
+    charset: Charset,
+    glyph_set: GlyphSet,
+    cell_aspect: f64,
+    normalized_speed: bool,
+    dissolve: bool,
+    glow: Option<AnsiColor>,
+    head_flash: u8,
+    flash_background: Option<AnsiColor>,
+    embers: Embers,
+    head_attributes: Attributes,
+    tail_attributes: Attributes,
+    smooth: bool,
+    lightning: Lightning,
+    mirror: Mirror,
+    mask: Option<Mask>,
+    title: Option<TitleCard>,
+    banner: Option<Banner>,
+    chime: Option<Chime>,
+    spawn_queue: Option<SpawnQueue>,
+    caption: Option<Caption>,
+    progress: Option<f64>,
+    pages: u16,
+    camera_y: u16,
+    zoom: Zoom,
+    origin: Origin,
+    effect_kind: EffectKind,
+    pipeline: Vec<(PipelineStage, Option<Box<dyn Effect>>)>,
+    effect: Option<Box<dyn Effect>>,
+    phase_style: Box<dyn PhaseStyle>,
+    spotlight: Option<u16>,
+    column_brightness: Vec<i8>,
+    debug: bool,
+    telemetry: Telemetry,
+    rates: RateSampler,
+    heat_map: HeatMap,
+    rngs: RngStreams,
+    events: Vec<SimEvent>,
+    balancer: Option<CoverageBalancer>,
+    fast_forward: bool,
+    paused: bool,
+    reserved: Reserved,
+    verify_frames: bool,
+    scroll_rendering: bool,
+    scrolled_rows: u16, // the effect has moved down as a whole this update
+    scroll: Option<Scroll>,
+    emitted: Viewport, // the view as drawn by the changes handed out, when verifying or held back
+    color_threshold: Option<f64>, // smallest recolor handed out, in delta E
+    interlace: Option<u16>, // the field of rows the next update draws, when interlacing
+    deferring: bool,   // the last update was held to a budget, so may owe changes
+    trail_colors: Option<ColorStops>,
+    theme: Theme,
+    color_mode: ColorMode,
+    light: bool,                 // drawn for a light background
+    brightness: f64,             // multiplies the rain's colors
+    hue_cycle: Option<Duration>, // period of a turn of the hue round the spectrum
+    hue_elapsed: Duration,       // time into the hue's turn
+    high_contrast: bool,
+    spawn_scale: f64, // set by the balancer
+    coverage: f64,
+    heat_map_enabled: bool,
 }
 
 impl Game {
-    pub fn new(width: u16, height: u16) -> Self {
-        Self {
+    pub fn new(width: u16, height: u16, settings: Settings) -> Self {
+        let mut game = Self {
             width,
             height,
             stacks: Vec::new(),
+            pool: StackPool::default(),
             current_view: Viewport::new(width, height),
-            density: 0.5,
-            max_stack_height: 0.5,
-            speed: 10,
-            debug: false,
-            debug_info: DebugInfo::default(),
-            last_update_time: Instant::now(),
-            update_counter: 0,
-            glyph_counter: 0,
+            density: 0.0,
+            max_stack_height: 0.0,
+            speed: 0,
+            update_interval_ms: DEFAULT_UPDATE_INTERVAL_MS,
+            mutation_rate: DEFAULT_MUTATION_RATE,
+            jitter: 0.0,
+            wind: Wind::default(),
+            charset: Charset::default(),
+            glyph_set: GlyphSet::default(),
+            cell_aspect: DEFAULT_CELL_ASPECT,
+            normalized_speed: false,
+            dissolve: false,
+            glow: None,
+            head_flash: 0,
+            flash_background: None,
+            embers: Embers::default(),
+            head_attributes: Attributes::default(),
+            tail_attributes: Attributes::default(),
+            smooth: false,
+            lightning: Lightning::default(),
+            mirror: Mirror::default(),
+            mask: None,
+            title: None,
+            banner: None,
+            chime: None,
+            spawn_queue: None,
+            caption: None,
+            progress: None,
+            pages: 1,
+            camera_y: 0,
+            zoom: Zoom::default(),
+            origin: Origin::default(),
+            effect_kind: EffectKind::default(),
+            pipeline: Vec::new(),
+            effect: None,
+            phase_style: Box::new(DefaultPhaseStyle),
+            spotlight: None,
+            column_brightness: vec![0; width as usize],
+            debug: settings.debug,
+            telemetry: Telemetry::default(),
+            rates: RateSampler::default(),
+            heat_map: HeatMap::new(width),
+            rngs: RngStreams::new(rand::random()),
+            events: Vec::new(),
+            balancer: None,
+            fast_forward: false,
+            paused: false,
+            reserved: Reserved::default(),
+            verify_frames: false,
+            scroll_rendering: false,
+            scrolled_rows: 0,
+            scroll: None,
+            emitted: Viewport::new(width, height),
+            color_threshold: None,
+            interlace: None,
+            deferring: false,
+            trail_colors: None,
+            theme: Theme::default(),
+            color_mode: ColorMode::default(),
+            light: false,
+            brightness: 1.0,
+            hue_cycle: None,
+            hue_elapsed: Duration::ZERO,
+            high_contrast: false,
+            spawn_scale: 1.0,
+            coverage: 0.0,
+            heat_map_enabled: false,
+        };
+        game.set_density(settings.density);
+        game.set_speed(settings.speed);
+        game.set_max_stack_height(settings.max_stack_height);
+        game
+    }
+
+    pub fn apply_weather(&mut self, preset: &WeatherPreset) {
+        self.set_density(preset.density);
+        self.set_speed(preset.speed);
+        self.set_max_stack_height(preset.max_stack_height);
+        self.set_update_interval_ms(preset.update_interval_ms.clone());
+        self.set_mutation_rate(preset.mutation_rate);
+        self.lightning.chance = preset.lightning_chance;
+    }
+
+    /// Sets the stack spawn rate, from 0.1 to 1.0 (default 0.5). At speed 10,
+    /// 1.0 spawns one stack every 75ms; the rate scales with the speed.
+    /// Values outside the range are clamped.
+    pub fn set_density(&mut self, density: f64) {
+        self.density = density.clamp(*DENSITY_RANGE.start(), *DENSITY_RANGE.end());
+    }
+
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+
+    /// The settings as they stand, to start another game like this one with.
+    pub fn settings(&self) -> Settings {
+        Settings {
+            density: self.density,
+            speed: self.speed,
+            max_stack_height: self.max_stack_height,
+            debug: self.debug,
         }
     }
 
+    pub fn increase_density(&mut self) {
+        self.set_density(round_step(self.density + SETTING_STEP));
+    }
+
+    pub fn decrease_density(&mut self) {
+        self.set_density(round_step(self.density - SETTING_STEP));
+    }
+
+    /// Sets the speed level, from 1 to 50 (default 10). Stacks step at
+    /// `speed / 10` times their base rate. Values outside the range are clamped.
+    pub fn set_speed(&mut self, speed: u8) {
+        self.speed = speed.clamp(*SPEED_RANGE.start(), *SPEED_RANGE.end());
+    }
+
+    pub fn speed(&self) -> u8 {
+        self.speed
+    }
+
+    /// Sets the speed as a multiplier of each stack's base step rate, from
+    /// 0.1 to 5.0 (default 1.0), rounded to the nearest speed level.
+    pub fn set_speed_multiplier(&mut self, multiplier: f64) {
+        let speed = (multiplier * 10.0).round().clamp(0.0, u8::MAX as f64) as u8;
+        self.set_speed(speed);
+    }
+
+    pub fn speed_multiplier(&self) -> f64 {
+        self.speed as f64 / 10.0
+    }
+
     pub fn increase_speed(&mut self) {
-        self.speed = (self.speed + 1).min(50);
+        self.set_speed(self.speed.saturating_add(1));
     }
 
     pub fn decrease_speed(&mut self) {
-        self.speed = (self.speed - 1).max(1);
+        self.set_speed(self.speed.saturating_sub(1));
+    }
+
+    /// Sets the longest a new stack may be, as a fraction of the screen
+    /// height from 0.1 to 1.0 (default 0.5). Values outside the range are clamped.
+    pub fn set_max_stack_height(&mut self, max_stack_height: f64) {
+        self.max_stack_height =
+            max_stack_height.clamp(*MAX_STACK_HEIGHT_RANGE.start(), *MAX_STACK_HEIGHT_RANGE.end());
+    }
+
+    pub fn max_stack_height(&self) -> f64 {
+        self.max_stack_height
     }
 
     pub fn increase_max_stack_height(&mut self) {
-        self.max_stack_height = (self.max_stack_height + 0.1).min(1.0);
+        self.set_max_stack_height(round_step(self.max_stack_height + SETTING_STEP));
     }
 
     pub fn decrease_max_stack_height(&mut self) {
-        self.max_stack_height = (self.max_stack_height - 0.1).max(0.1);
+        self.set_max_stack_height(round_step(self.max_stack_height - SETTING_STEP));
     }
 
-    pub fn toggle_debug(&mut self) {
-        self.debug = !self.debug;
+    /// Sets the range (in milliseconds, at speed 10) each new stack's base
+    /// step interval is picked from (default 50 - 250). An empty range is
+    /// treated as its start.
+    pub fn set_update_interval_ms(&mut self, range: RangeInclusive<u64>) {
+        self.update_interval_ms = if range.is_empty() {
+            *range.start()..=*range.start()
+        } else {
+            range
+        };
     }
 
-    pub fn get_dimensions(&self) -> (u16, u16) {
-        (self.width, self.height)
+    pub fn update_interval_ms(&self) -> RangeInclusive<u64> {
+        self.update_interval_ms.clone()
     }
 
-    pub fn resize(&mut self, width: u16, height: u16) {
-        self.width = width;
-        self.height = height;
-        self.current_view = Viewport::new(width, height);
-        self.stacks
-            .retain(|s| s.x < width && s.min_y < height as i16);
+    /// Sets the chance (0.0 - 1.0, default 0.05) of a random glyph in each
+    /// stack changing whenever the stack steps.
+    pub fn set_mutation_rate(&mut self, rate: f64) {
+        self.mutation_rate = rate.clamp(0.0, 1.0);
     }
 
-    pub fn increase_density(&mut self) {
-        self.density = (self.density + 0.1).min(1.0);
+    pub fn mutation_rate(&self) -> f64 {
+        self.mutation_rate
     }
 
-    pub fn decrease_density(&mut self) {
-        self.density = (self.density - 0.1).max(0.1);
+    pub fn increase_mutation_rate(&mut self) {
+        self.set_mutation_rate(((self.mutation_rate + MUTATION_STEP) / MUTATION_STEP).round() * MUTATION_STEP);
+    }
+
+    pub fn decrease_mutation_rate(&mut self) {
+        self.set_mutation_rate(((self.mutation_rate - MUTATION_STEP) / MUTATION_STEP).round() * MUTATION_STEP);
     }
 
-    pub fn update_and_get_changes(&mut self) -> Vec<Change> {
-        let mut rng = ThreadRng::default();
-        let mut stacks_this_update = 0;
-        let mut glyphs_this_update = 0;
+    /// Sets how far (as a percentage, 0 - 100, default 0) each stack's step
+    /// interval may randomly vary around its base on every step.
+    pub fn set_jitter(&mut self, percent: f64) {
+        self.jitter = (percent / 100.0).clamp(0.0, 1.0);
+    }
 
-        // Determine whether any new stacks should be spawned, scaling with speed
-        let speed_multiplier = self.speed as f64 / 10.0;
-        let effective_density = self.density * speed_multiplier;
+    pub fn jitter(&self) -> f64 {
+        self.jitter * 100.0
+    }
 
-        let guaranteed_spawns = effective_density.floor() as u32;
-        let chance_for_one_more = effective_density.fract();
+    /// Sets how much (as a percentage, 0 - 100, default 0) gusts of wind vary
+    /// the speed of neighbouring columns together, speeding groups of them up
+    /// and letting them relax as the gusts drift across the screen.
+    pub fn set_wind(&mut self, percent: f64) {
+        self.wind.set_strength(percent / 100.0);
+        self.wind.set_seed(self.rngs.seed());
+    }
 
-        for _ in 0..guaranteed_spawns {
-            let x = rng.gen_range(0..self.width / 2) * 2;
-            let max_len = (self.height as f64 * self.max_stack_height) as u16;
-            self.stacks.push(GlyphStack::new(x, max_len));
-            stacks_this_update += 1;
-        }
+    pub fn wind(&self) -> f64 {
+        self.wind.strength() * 100.0
+    }
 
-        if rng.gen_bool(chance_for_one_more) {
-            let x = rng.gen_range(0..self.width / 2) * 2;
-            let max_len = (self.height as f64 * self.max_stack_height) as u16;
-            self.stacks.push(GlyphStack::new(x, max_len));
-            stacks_this_update += 1;
-        }
+    /// Sets the glyphs the rain and the effects are drawn in (full-width
+    /// katakana by default), mirrored if they were before. Glyphs already on
+    /// screen keep their characters until they mutate or fall off.
+    pub fn set_charset(&mut self, charset: Charset) {
+        self.charset = charset;
+        let mut glyph_set = GlyphSet::from(charset);
+        glyph_set.set_mirrored(self.glyph_set.mirrored());
+        self.set_glyph_set(glyph_set);
+    }
 
-        // Update glyph stacks
+    /// Draws the rain and the effects from any set of glyphs, like one built
+    /// from ranges of code points with [`GlyphSet::from_ranges`]. Cycling
+    /// the charset goes on from the last named one.
+    pub fn set_glyph_set(&mut self, glyph_set: GlyphSet) {
         for stack in &mut self.stacks {
-            let before_len = stack.stack.len();
-            stack.update(self.speed);
-            let after_len = stack.stack.len();
-            if after_len > before_len {
-                glyphs_this_update += 1;
-            }
+            stack.glyph_set = glyph_set.clone();
         }
+        self.glyph_set = glyph_set;
+    }
 
-        // If y_min is outside of the viewport, delete the stack
-        self.stacks.retain(|s| s.min_y < self.height as i16);
+    pub fn charset(&self) -> Charset {
+        self.charset
+    }
 
-        let mut next_view = Viewport::new(self.width, self.height);
-        for stack in &self.stacks {
-            for (i, glyph) in stack.stack.iter().enumerate() {
-                let y = stack.max_y - i as i16;
-                if y >= 0 && y < self.height as i16 {
-                    let cell_to_add = Cell {
-                        ch: glyph.value,
-                        color: glyph.color,
-                    };
-                    next_view.set(stack.x, y as u16, cell_to_add);
-                }
+    pub fn cycle_charset(&mut self) {
+        self.set_charset(self.charset.next());
+    }
+
+    /// Draws glyphs as their mirror images, like the rain in the films, where
+    /// Unicode has a mirrored form or a glyph that looks like one (off by
+    /// default). Glyphs without one, like the katakana, are drawn as they are.
+    pub fn set_mirrored_glyphs(&mut self, mirrored: bool) {
+        let mut glyph_set = self.glyph_set.clone();
+        glyph_set.set_mirrored(mirrored);
+        self.set_glyph_set(glyph_set);
+    }
+
+    pub fn mirrored_glyphs(&self) -> bool {
+        self.glyph_set.mirrored()
+    }
+
+    /// Sets the height of the font's cells over their width, from 1.0 to 4.0
+    /// (default 2.0), so effects drawing shapes (like the vortex's spiral)
+    /// keep their proportions. Values outside the range are clamped.
+    pub fn set_cell_aspect(&mut self, aspect: f64) {
+        self.cell_aspect = aspect.clamp(*CELL_ASPECT_RANGE.start(), *CELL_ASPECT_RANGE.end());
+    }
+
+    pub fn cell_aspect(&self) -> f64 {
+        self.cell_aspect
+    }
+
+    /// Whether the rain's speed is normalized to the cells' visual size, so
+    /// stacks fall the same distance on screen per second whatever the cell
+    /// aspect, and as fast as stacks crossing sideways (a glyph's width per
+    /// step). Off by default, when every stack steps a cell at a time.
+    pub fn set_normalized_speed(&mut self, normalized: bool) {
+        self.normalized_speed = normalized;
+    }
+
+    pub fn normalized_speed(&self) -> bool {
+        self.normalized_speed
+    }
+
+    /// Whether stacks dissolve once their heads leave the canvas, what's
+    /// left of their tails flickering and fading out in place over a moment,
+    /// rather than draining off a glyph at a time. Off by default.
+    pub fn set_dissolve(&mut self, dissolve: bool) {
+        self.dissolve = dissolve;
+    }
+
+    /// Background color drawn behind the head of each stack, like a glow
+    /// around it, tinted along with tinted stacks. None by default.
+    pub fn set_glow(&mut self, glow: Option<AnsiColor>) {
+        self.glow = glow;
+    }
+
+    /// Updates (frames, for a frontend drawing one per update) each new head
+    /// glyph flashes for, drawn bold and at full brightness before settling,
+    /// optionally over a background color. Zero (the default) turns it off.
+    pub fn set_head_flash(&mut self, frames: u8, background: Option<AnsiColor>) {
+        self.head_flash = frames;
+        self.flash_background = background;
+    }
+
+    pub fn head_flash(&self) -> u8 {
+        self.head_flash
+    }
+
+    /// What cells the rain has faded out of become: cleared (the default),
+    /// a dim ember of the glyph last drawn there, or a dim background tint.
+    /// Embers last until the rain passes over them again.
+    pub fn set_embers(&mut self, style: EmberStyle) {
+        self.embers.set_style(style);
+    }
+
+    pub fn embers(&self) -> EmberStyle {
+        self.embers.style()
+    }
+
+    /// Text attributes the head of each stack, and the back half of its
+    /// trail, are drawn with (e.g. bold heads and dim tails). None by default.
+    pub fn set_attributes(&mut self, head: Attributes, tail: Attributes) {
+        self.head_attributes = head;
+        self.tail_attributes = tail;
+    }
+
+    /// Replaces the hook styling stacks in each phase of their life.
+    pub fn set_phase_style(&mut self, style: Box<dyn PhaseStyle>) {
+        self.phase_style = style;
+    }
+
+    /// Reseeds every source of randomness from the given seed, so the same
+    /// seed and settings replay the same spawns and glyphs.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rngs = RngStreams::new(seed);
+        self.wind.set_seed(seed);
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.rngs.seed()
+    }
+
+    /// A hash of the simulation's state: every stack (its position, glyphs
+    /// and timing, in the order they spawned), how far each random stream
+    /// has got, the settings that steer the simulation and the view last
+    /// drawn. Games given the same seed, settings and inputs hash the same
+    /// after every update until they diverge, so replays and synchronized
+    /// runs can be checked against each other frame by frame.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.stacks.len().hash(&mut hasher);
+        for stack in &self.stacks {
+            stack.hash_state(&mut hasher);
+        }
+        self.rngs.positions().hash(&mut hasher);
+        [self.density, self.max_stack_height, self.spawn_scale]
+            .map(f64::to_bits)
+            .hash(&mut hasher);
+        (self.mutation_rate.to_bits(), self.speed, self.camera_y).hash(&mut hasher);
+        (self.paused, self.fast_forward).hash(&mut hasher);
+        self.current_view.fingerprint().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The simulation's settings, what the last update did and its rates
+    /// over recent windows, as of the last update.
+    pub fn telemetry(&self) -> &Telemetry {
+        &self.telemetry
+    }
+
+    /// Keeps roughly this percentage (0 - 100) of the screen's cells lit by
+    /// continuously adjusting the spawn rate, or stops doing so (`None`).
+    /// The density setting still sets the starting rate.
+    pub fn set_target_coverage(&mut self, percent: Option<f64>) {
+        self.balancer = percent.map(|percent| CoverageBalancer::new(percent / 100.0));
+        self.spawn_scale = 1.0;
+    }
+
+    pub fn target_coverage(&self) -> Option<f64> {
+        self.balancer.as_ref().map(|b| b.target() * 100.0)
+    }
+
+    /// The fraction (0.0 - 1.0) of the screen's cells lit by the rain (or
+    /// effect) on the last update, before any overlays.
+    pub fn coverage(&self) -> f64 {
+        self.coverage
+    }
+
+    /// Multiplies the spawn rate, reaching past the top of the density range
+    /// (e.g. to stress a terminal). A target coverage sets the scale itself.
+    pub fn set_spawn_scale(&mut self, scale: f64) {
+        self.spawn_scale = scale.max(0.0);
+    }
+
+    /// How much the spawn rate is scaled, by `set_spawn_scale` or to reach
+    /// the target coverage.
+    pub fn spawn_scale(&self) -> f64 {
+        self.spawn_scale
+    }
+
+    /// Adds an event from outside the simulation (e.g. a timer running out)
+    /// to the stream of events.
+    pub fn emit(&mut self, event: SimEvent) {
+        self.events.push(event);
+    }
+
+    /// Takes the events that have happened since the last call.
+    pub fn take_events(&mut self) -> Vec<SimEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn strike_lightning(&mut self) {
+        self.lightning
+            .strike(&mut self.rngs.effects, self.width, self.height);
+    }
+
+    pub fn toggle_debug(&mut self) {
+        self.debug = !self.debug;
+    }
+
+    /// Shows or hides the debug overlay.
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
+
+    pub fn debug(&self) -> bool {
+        self.debug
+    }
+
+    /// Sets the chance of a lightning flash every 75ms (0.0 - 1.0).
+    pub fn set_lightning_chance(&mut self, chance: f64) {
+        self.lightning.chance = chance.clamp(0.0, 1.0);
+    }
+
+    pub fn lightning_chance(&self) -> f64 {
+        self.lightning.chance
+    }
+
+    /// Sets how lightning flashes the cells it strikes.
+    pub fn set_lightning_style(&mut self, style: FlashStyle) {
+        self.lightning.style = style;
+    }
+
+    pub fn lightning_style(&self) -> FlashStyle {
+        self.lightning.style
+    }
+
+    /// Sets whether lightning flashes the whole screen or a random region of it.
+    pub fn set_lightning_region(&mut self, region: FlashRegion) {
+        self.lightning.region = region;
+    }
+
+    pub fn lightning_region(&self) -> FlashRegion {
+        self.lightning.region
+    }
+
+    /// Interpolates between steps, so each frame moves the rain a little.
+    pub fn set_smooth(&mut self, smooth: bool) {
+        self.smooth = smooth;
+    }
+
+    pub fn smooth(&self) -> bool {
+        self.smooth
+    }
+
+    /// Mirrors the view as it's drawn.
+    pub fn set_mirror(&mut self, mirror: Mirror) {
+        self.mirror = mirror;
+    }
+
+    pub fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+
+    /// Limits the rain to the cells the mask lets through, or lifts the limit.
+    pub fn set_mask(&mut self, mask: Option<Mask>) {
+        self.mask = mask;
+    }
+
+    pub fn mask(&self) -> Option<&Mask> {
+        self.mask.as_ref()
+    }
+
+    /// Shows a title card over the rain, or removes it.
+    pub fn set_title(&mut self, title: Option<TitleCard>) {
+        self.title = title;
+    }
+
+    pub fn title(&self) -> Option<&TitleCard> {
+        self.title.as_ref()
+    }
+
+    /// Shows a banner over the rain, or removes it.
+    pub fn set_banner(&mut self, banner: Option<Banner>) {
+        self.banner = banner;
+    }
+
+    pub fn banner(&self) -> Option<&Banner> {
+        self.banner.as_ref()
+    }
+
+    /// Chimes the hour (or whatever time is given): a wave of stacks spawns
+    /// across the whole width of the screen at once, and the time shows in
+    /// large block digits over the rain for a few seconds.
+    pub fn chime(&mut self, hour: u8, minute: u8) {
+        self.chime = Some(Chime::new(hour, minute));
+        self.spawn_wave();
+    }
+
+    /// Spawns stacks on a schedule written as a spawn pattern (see
+    /// `SpawnPattern`) in place of spawning them at random by the density, or
+    /// goes back to the density (`None`). The pattern's times count from when
+    /// it's set, in simulated time.
+    pub fn set_spawn_pattern(&mut self, pattern: Option<SpawnPattern>) {
+        self.spawn_queue = pattern.map(SpawnQueue::new);
+    }
+
+    pub fn spawn_pattern(&self) -> Option<&SpawnPattern> {
+        self.spawn_queue.as_ref().map(SpawnQueue::pattern)
+    }
+
+    /// Shows a caption over the rain, or removes it.
+    pub fn set_caption(&mut self, caption: Option<Caption>) {
+        self.caption = caption;
+    }
+
+    /// Colors the rain by each glyph's position along its stack, from the
+    /// head (0.0) to the end of a full-length tail (1.0), in place of the
+    /// theme's colors (`None`).
+    pub fn set_trail_colors(&mut self, stops: Option<ColorStops>) {
+        self.trail_colors = stops;
+    }
+
+    /// Draws the rain in a theme's colors, where trail colors don't set them.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.theme
+    }
+
+    pub fn cycle_theme(&mut self) {
+        self.theme = self.theme.next();
+    }
+
+    /// Draws each stack spawned from now on in a random hue of its own, in
+    /// place of the theme's colors (`ColorMode::PerStack`), or in the theme's.
+    pub fn set_color_mode(&mut self, color_mode: ColorMode) {
+        self.color_mode = color_mode;
+    }
+
+    pub fn color_mode(&self) -> ColorMode {
+        self.color_mode
+    }
+
+    /// Draws the rain for a light background rather than a dark one, with
+    /// the darkest glyphs at the heads and the tails paling away.
+    pub fn set_light(&mut self, light: bool) {
+        self.light = light;
+    }
+
+    pub fn light(&self) -> bool {
+        self.light
+    }
+
+    // The colors the rain is drawn in: the theme's, or those of a stack's
+    // own hue, for the terminal's background
+    fn palette(&self, hue: Option<f64>) -> Palette {
+        let palette = hue.map_or_else(|| self.theme.palette(), Palette::of_hue);
+        if self.light {
+            palette.on_light()
+        } else {
+            palette
+        }
+    }
+
+    /// Scales the brightness of the rain's colors, from 0.2 to 2.0 (default
+    /// 1.0, leaving them as they are). Values outside the range are clamped.
+    pub fn set_brightness(&mut self, brightness: f64) {
+        self.brightness = brightness.clamp(*BRIGHTNESS_RANGE.start(), *BRIGHTNESS_RANGE.end());
+    }
+
+    pub fn brightness(&self) -> f64 {
+        self.brightness
+    }
+
+    pub fn increase_brightness(&mut self) {
+        self.set_brightness(round_step(self.brightness + SETTING_STEP));
+    }
+
+    pub fn decrease_brightness(&mut self) {
+        self.set_brightness(round_step(self.brightness - SETTING_STEP));
+    }
+
+    // A color of the rain at the brightness set
+    fn brightened(&self, color: AnsiColor) -> AnsiColor {
+        if self.brightness == 1.0 {
+            color
+        } else {
+            color.scaled(self.brightness)
+        }
+    }
+
+    /// Turns the hue of everything drawn beneath the overlays slowly round
+    /// the spectrum, once every `period`, or stops (`None`).
+    pub fn set_hue_cycle(&mut self, period: Option<Duration>) {
+        self.hue_cycle = period.filter(|period| !period.is_zero());
+        self.hue_elapsed = Duration::ZERO;
+    }
+
+    pub fn hue_cycle(&self) -> Option<Duration> {
+        self.hue_cycle
+    }
+
+    /// Separates the heads, the trails and the background as far as the
+    /// theme allows, keeping the trails one bright color.
+    pub fn set_high_contrast(&mut self, high_contrast: bool) {
+        self.high_contrast = high_contrast;
+    }
+
+    pub fn high_contrast(&self) -> bool {
+        self.high_contrast
+    }
+
+    /// Keeps the rain (and the title and banner) out of rows and columns
+    /// along the edges of the screen, so another program can draw there.
+    pub fn set_reserved(&mut self, reserved: Reserved) {
+        self.reserved = reserved;
+    }
+
+    pub fn reserved(&self) -> Reserved {
+        self.reserved
+    }
+
+    // Runs the simulation several times faster, for reaching a full screen quickly
+    pub fn toggle_fast_forward(&mut self) {
+        self.fast_forward = !self.fast_forward;
+    }
+
+    pub fn fast_forward(&self) -> bool {
+        self.fast_forward
+    }
+
+    // Freezes the simulation where it is, spawning nothing and moving nothing,
+    // until it's unpaused
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_heat_map(&mut self) {
+        self.heat_map_enabled = !self.heat_map_enabled;
+    }
+
+    pub fn heat_map_enabled(&self) -> bool {
+        self.heat_map_enabled
+    }
+
+    pub fn record_key(&mut self, key: char) {
+        if self.heat_map_enabled {
+            self.heat_map.record_key(key);
+        }
+    }
+
+    // Turns the spotlight on at the given column (or the center of the screen),
+    // or off if it's already on
+    pub fn toggle_spotlight(&mut self, column: Option<u16>) {
+        self.spotlight = match self.spotlight {
+            Some(_) => None,
+            None => Some(column.unwrap_or(self.width / 2).min(self.width.saturating_sub(1)) & !1),
+        };
+        self.update_column_brightness();
+    }
+
+    pub fn move_spotlight(&mut self, delta: i16) {
+        if let Some(x) = self.spotlight {
+            let max_x = self.width.saturating_sub(1) as i16 & !1;
+            self.spotlight = Some((x as i16 + delta * 2).clamp(0, max_x) as u16);
+            self.update_column_brightness();
+        }
+    }
+
+    pub fn spotlight(&self) -> Option<u16> {
+        self.spotlight
+    }
+
+    // Stacks passing through the spotlight render one step brighter
+    fn update_column_brightness(&mut self) {
+        self.column_brightness = vec![0; self.width as usize];
+        if let Some(x) = self.spotlight {
+            if let Some(brightness) = self.column_brightness.get_mut(x as usize) {
+                *brightness = 1;
             }
         }
+    }
 
-        let changes = diff_viewports(&self.current_view, &next_view);
-        self.current_view = next_view;
+    // Only columns left of the given percentage of the screen spawn stacks,
+    // so the rain fills in from left to right as a job makes progress
+    pub fn set_progress(&mut self, percent: f64) {
+        self.progress = Some(percent.clamp(0.0, 100.0));
+    }
+
+    pub fn progress(&self) -> Option<f64> {
+        self.progress
+    }
+
+    // The canvas stacks fall through is this many screens tall, and the
+    // camera pans over it
+    pub fn set_pages(&mut self, pages: u16) {
+        self.pages = pages.max(1);
+        self.pan(0);
+    }
 
-        // Update debug info
-        self.update_counter += 1;
-        self.glyph_counter += glyphs_this_update;
-        let elapsed = self.last_update_time.elapsed();
-        if elapsed >= Duration::from_secs(1) {
-            self.debug_info.updates_per_sec = self.update_counter as f64 / elapsed.as_secs_f64();
-            self.debug_info.glyphs_per_sec = self.glyph_counter as f64 / elapsed.as_secs_f64();
-            self.update_counter = 0;
-            self.glyph_counter = 0;
-            self.last_update_time = Instant::now();
-        }
-        self.debug_info.density = self.density;
-        self.debug_info.max_stack_height = self.max_stack_height;
-        self.debug_info.speed = self.speed;
-        self.debug_info.glyphs_per_update = glyphs_this_update;
-        self.debug_info.stacks_per_update = stacks_this_update;
-        let delays: Vec<u128> = self
-            .stacks
+    // Size of the simulated area the camera sees, which differs from the
+    // screen when zoomed in or out
+    fn sim_size(&self) -> (u16, u16) {
+        self.zoom.sim_size(self.width, self.height)
+    }
+
+    // Distances along a stack's lane are i16s, so the canvas is cut short
+    // where they'd overflow
+    fn canvas_height(&self) -> u16 {
+        self.sim_size().1.saturating_mul(self.pages).min(i16::MAX as u16)
+    }
+
+    // Moves the camera down (positive) or up (negative) the canvas by the given number of screen rows
+    pub fn pan(&mut self, rows: i32) {
+        let rows = if rows == 0 { 0 } else { self.zoom.to_sim_rows(rows) };
+        let max_y = self.canvas_height().saturating_sub(self.sim_size().1);
+        let camera_y = (self.camera_y as i32 + rows).clamp(0, max_y as i32) as u16;
+        // Embers belong to the part of the canvas they were left on
+        if camera_y != self.camera_y {
+            self.embers.clear();
+        }
+        self.camera_y = camera_y;
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.set_zoom(self.zoom.zoom_in());
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.set_zoom(self.zoom.zoom_out());
+    }
+
+    // Stacks keep their simulated positions, so zooming is anchored to the
+    // top left of the canvas
+    pub fn set_zoom(&mut self, zoom: Zoom) {
+        self.zoom = zoom;
+        self.embers.clear();
+        self.pan(0);
+        self.retain_stacks(Self::stack_fits);
+    }
+
+    pub fn zoom(&self) -> Zoom {
+        self.zoom
+    }
+
+    pub fn camera(&self) -> (u16, u16) {
+        (self.camera_y, self.canvas_height())
+    }
+
+    /// Sets the edge (or edges) new stacks spawn from.
+    pub fn set_origin(&mut self, origin: Origin) {
+        self.origin = origin;
+    }
+
+    pub fn origin(&self) -> Origin {
+        self.origin
+    }
+
+    // Distance a stack from the given edge travels before leaving the canvas
+    fn travel(&self, edge: Edge) -> u16 {
+        let (sim_width, _) = self.sim_size();
+        let travel = edge.travel(sim_width, self.canvas_height());
+        if self.origin.converges() {
+            travel.div_ceil(2)
+        } else {
+            travel
+        }
+    }
+
+    // Whether a stack is still on the canvas, after it has changed size
+    fn stack_fits(&self, stack: &GlyphStack) -> bool {
+        let (sim_width, _) = self.sim_size();
+        stack.x < stack.edge.lanes(sim_width, self.canvas_height())
+            && stack.min_y < self.travel(stack.edge) as i16
+    }
+
+    /// Switches to another effect in place of the rain (or back to the rain),
+    /// starting it afresh.
+    pub fn set_effect(&mut self, kind: EffectKind) {
+        self.effect_kind = kind;
+        self.effect = kind.build();
+        self.pipeline.clear();
+        self.retain_stacks(|_, _| false);
+        self.embers.clear();
+    }
+
+    /// Runs several effects at once, each composited over the ones before
+    /// it at its opacity (the rain, if included, is the game's own rain).
+    /// The last effect counts as the current one, for effect-specific keys.
+    /// Switching effects, or an empty pipeline, goes back to a single effect.
+    pub fn set_pipeline(&mut self, stages: &[PipelineStage]) {
+        self.set_effect(stages.last().map_or(self.effect_kind, |stage| stage.effect));
+        self.effect = None;
+        self.pipeline = stages
             .iter()
-            .map(|s| s.update_interval.as_millis())
+            .map(|stage| (*stage, stage.effect.build()))
+            .collect();
+        if self.pipeline.is_empty() {
+            self.effect = self.effect_kind.build();
+        }
+    }
+
+    pub fn pipeline(&self) -> Vec<PipelineStage> {
+        self.pipeline.iter().map(|(stage, _)| *stage).collect()
+    }
+
+    // Updates each effect of the pipeline, compositing them into the view in order
+    fn update_pipeline(&mut self, dt: Duration, view: &mut Viewport) -> EffectStats {
+        let params = self.effect_params(dt);
+        let mut pipeline = std::mem::take(&mut self.pipeline);
+        let mut stats = EffectStats::default();
+        let mut rain_updated = false;
+        for (stage, effect) in &mut pipeline {
+            let mut layer_view = Viewport::new(view.width(), view.height());
+            let stage_stats = match effect {
+                Some(effect) => {
+                    let stats = effect.update(&params, &mut self.rngs);
+                    effect.render(&mut layer_view);
+                    stats
+                }
+                // The rain only steps once, however often it's composited
+                None if rain_updated => EffectStats::default(),
+                None => {
+                    rain_updated = true;
+                    self.update_rain(dt)
+                }
+            };
+            if effect.is_none() {
+                self.render_rain(&mut layer_view);
+            }
+            Layer::from_view(&layer_view, stage.opacity).composite(view);
+            stats.spawned += stage_stats.spawned;
+            stats.glyphs += stage_stats.glyphs;
+        }
+        self.pipeline = pipeline;
+        stats
+    }
+
+    pub fn effect(&self) -> EffectKind {
+        self.effect_kind
+    }
+
+    pub fn cycle_effect(&mut self) {
+        self.set_effect(self.effect_kind.next());
+    }
+
+    fn effect_params(&self, dt: Duration) -> EffectParams {
+        let (width, height) = self.sim_size();
+        EffectParams {
+            width,
+            height,
+            density: self.density * self.spawn_scale,
+            speed: self.speed,
+            max_stack_height: self.max_stack_height,
+            update_interval_ms: self.update_interval_ms.clone(),
+            mutation_rate: self.mutation_rate,
+            cell_aspect: self.cell_aspect,
+            glyph_set: self.glyph_set.clone(),
+            dt,
+        }
+    }
+
+    pub fn cycle_mirror(&mut self) {
+        self.mirror = self.mirror.next();
+    }
+
+    // Changes that redraw every cell of the current view inside the region,
+    // for when something else has drawn over it. Empty cells are cleared
+    // before glyphs are drawn, so the clears can't clip double-width glyphs.
+    pub fn repaint_region(&self, region: Rect) -> Vec<Change> {
+        let mut removals = Vec::new();
+        let mut updates = Vec::new();
+        let view = &self.current_view;
+        for y in region.y..region.bottom().min(self.height) {
+            let mut drawn = Drawn::Empty;
+            for x in 0..region.right().min(self.width) {
+                drawn = view.drawn(x, y, drawn);
+                // A wide glyph reaching into the region is drawn again too
+                let reaches = x + 1 == region.x
+                    && matches!(drawn, Drawn::Glyph(cell) if display_width(cell.ch) > 1);
+                if x < region.x && !reaches {
+                    continue;
+                }
+                match drawn {
+                    Drawn::Glyph(cell) => {
+                        let priority = ChangePriority::HeadMove; // drawn over whatever was there
+                        updates.push(cell.change(x, y, priority))
+                    }
+                    Drawn::Empty => removals.push(Change::Remove(x, y)),
+                    Drawn::Covered => {}
+                }
+            }
+        }
+        removals.extend(updates);
+        removals
+    }
+
+    /// Changes that redraw every cell of the screen from the current view,
+    /// without diffing, for recovering from a screen that may have drifted
+    /// from it (e.g. after missed writes). Counted in the telemetry.
+    pub fn full_repaint(&mut self) -> Vec<Change> {
+        self.telemetry.full_repaints += 1;
+        self.scroll = None;
+        self.emitted = self.current_view.clone();
+        self.repaint_region(Rect::new(0, 0, self.width, self.height))
+    }
+
+    /// Checks each update that the changes handed out reproduce the view,
+    /// comparing hashes of the two and falling back on a full repaint if
+    /// they've drifted apart. With a color threshold, what's drawn is held
+    /// to the view but for the recolors held back.
+    pub fn set_verify_frames(&mut self, verify: bool) {
+        self.verify_frames = verify;
+        self.emitted = self.current_view.clone();
+    }
+
+    /// Holds back changes that only recolor a cell by less than a threshold,
+    /// the perceptual difference (see [`AnsiColor::distance`]) between the
+    /// colors drawn there and the new ones, so fades are drawn in fewer,
+    /// bigger steps: for smaller recordings and less scrollback churn.
+    /// Changes of glyph or attributes always go through. None (the default)
+    /// hands out every change.
+    pub fn set_color_threshold(&mut self, threshold: Option<f64>) {
+        self.color_threshold = threshold;
+        self.emitted = self.current_view.clone();
+    }
+
+    pub fn color_threshold(&self) -> Option<f64> {
+        self.color_threshold
+    }
+
+    /// Draws the changes to odd rows one update and even rows the next,
+    /// halving what goes out each update for slow terminals, such as serial
+    /// consoles. The rows skipped catch up with the view the next update.
+    pub fn set_interlace(&mut self, interlace: bool) {
+        self.interlace = interlace.then_some(0);
+        self.emitted = self.current_view.clone();
+    }
+
+    pub fn interlace(&self) -> bool {
+        self.interlace.is_some()
+    }
+
+    /// For terminals that can scroll: while the effect moves down as a whole
+    /// (like the sheet), updates are diffed against the last view scrolled
+    /// down with it, whenever that makes for fewer changes, and `scroll`
+    /// says how the screen has to be scrolled before they're drawn.
+    pub fn set_scroll_rendering(&mut self, enabled: bool) {
+        self.scroll_rendering = enabled;
+    }
+
+    /// The scroll the last update's changes are to be drawn after, if any
+    pub fn scroll(&self) -> Option<Scroll> {
+        self.scroll
+    }
+
+    // The scroll matching the effect's motion, between the reserved rows.
+    // Reserved columns would be scrolled along with the rows, so rule it out.
+    fn scroll_candidate(&self) -> Option<Scroll> {
+        let reserved = self.reserved;
+        let bottom = self.height.saturating_sub(reserved.bottom);
+        let rows = self.scrolled_rows.min(bottom.saturating_sub(reserved.top));
+        let usable = self.scroll_rendering
+            && rows > 0
+            && self.zoom == Zoom::Normal
+            && reserved.left == 0
+            && reserved.right == 0;
+        usable.then_some(Scroll {
+            top: reserved.top,
+            bottom,
+            rows,
+        })
+    }
+
+    pub fn get_dimensions(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        // A paused frame stays on screen, cropped to the new size
+        let frozen = std::mem::replace(&mut self.current_view, Viewport::new(width, height));
+        if self.paused {
+            for y in 0..height.min(frozen.height()) {
+                for x in 0..width.min(frozen.width()) {
+                    if let Some(cell) = frozen.get(x, y) {
+                        self.current_view.set(x, y, cell);
+                    }
+                }
+            }
+        }
+        self.emitted = Viewport::new(width, height);
+        self.heat_map.resize(width);
+        self.spotlight = self.spotlight.map(|x| x.min(width.saturating_sub(1) & !1));
+        self.update_column_brightness();
+        self.pan(0);
+        self.retain_stacks(Self::stack_fits);
+    }
+
+    fn spawn_stack(&mut self, x: u16, edge: Edge) {
+        // Stack lengths are relative to the visible distance across the screen
+        let (sim_width, sim_height) = self.sim_size();
+        let visible = edge.travel(sim_width, sim_height);
+        let max_len = ((visible as f64 * self.max_stack_height) as u16).max(1);
+        let interval_range = self.update_interval_ms.clone();
+        let mut stack = GlyphStack::from_pool(
+            x,
+            edge,
+            max_len,
+            interval_range,
+            self.glyph_set.clone(),
+            &mut self.pool,
+            &mut self.rngs,
+        );
+        if self.color_mode == ColorMode::PerStack {
+            stack.hue = Some(self.rngs.spawn.random_range(0.0..360.0));
+        }
+        self.stacks.push(stack);
+    }
+
+    // Despawns the stacks failing the test, pooling their glyph buffers
+    fn retain_stacks(&mut self, keep: impl Fn(&Self, &GlyphStack) -> bool) {
+        let mut stacks = std::mem::take(&mut self.stacks);
+        let mut pool = std::mem::take(&mut self.pool);
+        stacks.retain_mut(|stack| {
+            let kept = keep(self, stack);
+            if !kept {
+                pool.give(std::mem::take(&mut stack.stack));
+            }
+            kept
+        });
+        self.stacks = stacks;
+        self.pool = pool;
+    }
+
+    // Spawns a stack in a random lane from one of the origin's edges. Stacks
+    // falling vertically are limited to the first `spawn_width` columns.
+    fn spawn_random_stack(&mut self, spawn_width: u16) -> bool {
+        let edge = self.origin.pick_edge(&mut self.rngs.spawn);
+        let (lanes, step) = if edge.is_horizontal() {
+            (self.canvas_height(), 1)
+        } else {
+            let step = self.zoom.column_step();
+            (spawn_width / step, step)
+        };
+        if lanes == 0 {
+            return false;
+        }
+        let x = self.rngs.spawn.random_range(0..lanes) * step;
+        if self.lane_is_reserved(edge, x, step) {
+            return false;
+        }
+        self.spawn_stack(x, edge);
+        true
+    }
+
+    /// Spawns a stack in the given color that rains the characters of the
+    /// text (read from top to bottom) before random glyphs, with a trail
+    /// long enough to show them all where the screen allows. Returns
+    /// whether there was anywhere to spawn it.
+    pub fn spawn_text(&mut self, text: &str, color: AnsiColor) -> bool {
+        let mut chars: VecDeque<char> = text
+            .chars()
+            .map(|ch| if ch.is_control() { ' ' } else { ch })
             .collect();
-        self.debug_info.min_glyph_delay = delays.iter().min().cloned().unwrap_or(0);
-        self.debug_info.max_glyph_delay = delays.iter().max().cloned().unwrap_or(0);
+        let Some(first) = chars.pop_front() else {
+            return false;
+        };
+        if !self.spawn_random_stack(self.spawn_width()) {
+            return false;
+        }
+        let (sim_width, sim_height) = self.sim_size();
+        if let Some(stack) = self.stacks.last_mut() {
+            let visible = stack.edge.travel(sim_width, sim_height);
+            stack.length = (chars.len() as u16 + 1).clamp(1, visible.max(1));
+            stack.stack[0].value = first;
+            stack.next_value = chars.pop_front().unwrap_or(stack.next_value);
+            stack.tint = Some(color);
+            stack.text = Some(chars);
+        }
+        true
+    }
+
+    // Spawns a stack in every lane of the origin's vertical edge at once
+    fn spawn_wave(&mut self) -> usize {
+        let spawn_width = self.spawn_width();
+        let step = self.zoom.column_step();
+        let edge = self.vertical_edge();
+        let mut spawned = 0;
+        for x in (0..spawn_width / step).map(|lane| lane * step) {
+            if !self.lane_is_reserved(edge, x, step) {
+                self.spawn_stack(x, edge);
+                spawned += 1;
+            }
+        }
+        spawned
+    }
+
+    // Spawns the stacks of the spawn pattern's actions that have come due
+    fn spawn_pattern_actions(&mut self, dt: Duration) -> usize {
+        let Some(queue) = &mut self.spawn_queue else {
+            return 0;
+        };
+        let mut spawned = 0;
+        for action in queue.update(dt) {
+            spawned += match action {
+                SpawnAction::Stack(None) => self.spawn_random_stack(self.spawn_width()) as usize,
+                SpawnAction::Stack(Some(lane)) => {
+                    let step = self.zoom.column_step();
+                    let (x, edge) = (lane.saturating_mul(step), self.vertical_edge());
+                    if x < self.spawn_width() && !self.lane_is_reserved(edge, x, step) {
+                        self.spawn_stack(x, edge);
+                        1
+                    } else {
+                        0
+                    }
+                }
+                SpawnAction::Wave => self.spawn_wave(),
+                SpawnAction::Message(text) => self.spawn_text(&text, AnsiColor::White) as usize,
+            };
+        }
+        spawned
+    }
+
+    // The top edge, or the bottom one for rain rising from it
+    fn vertical_edge(&self) -> Edge {
+        match self.origin {
+            Origin::Bottom => Edge::Bottom,
+            _ => Edge::Top,
+        }
+    }
+
+    // Stacks only spawn in the source half of a horizontal mirror, and in
+    // the columns activated by the progress so far (in simulated cells)
+    fn spawn_width(&self) -> u16 {
+        let mut spawn_width = self.mirror.source_width(self.width);
+        if let Some(percent) = self.progress {
+            spawn_width = spawn_width.min((self.width as f64 * percent / 100.0).round() as u16);
+        }
+        self.zoom.to_sim_x(spawn_width)
+    }
+
+    /// Spawns a stack drawn in the given color rather than green (keeping
+    /// its white head and fading trail), e.g. to mark an event in the rain.
+    /// Returns whether there was anywhere to spawn it.
+    pub fn spawn_tinted(&mut self, color: AnsiColor) -> bool {
+        if !self.spawn_random_stack(self.spawn_width()) {
+            return false;
+        }
+        if let Some(stack) = self.stacks.last_mut() {
+            stack.tint = Some(color);
+        }
+        true
+    }
+
+    // Whether a lane (in simulated cells) lies in the screen's reserved rows
+    // or columns, so stacks spawned there would never be seen
+    fn lane_is_reserved(&self, edge: Edge, lane: u16, step: u16) -> bool {
+        let (sim_width, sim_height) = self.sim_size();
+        if edge.is_horizontal() {
+            let Some(row) = lane.checked_sub(self.camera_y).filter(|row| *row < sim_height) else {
+                return false;
+            };
+            let top = self.zoom.to_sim_rows(self.reserved.top as i32) as u16;
+            let bottom = self.zoom.to_sim_rows(self.reserved.bottom as i32) as u16;
+            row < top || row >= sim_height.saturating_sub(bottom)
+        } else {
+            let left = self.zoom.to_sim_x(self.reserved.left);
+            let right = sim_width.saturating_sub(self.zoom.to_sim_x(self.reserved.right));
+            lane < left || lane + step > right
+        }
+    }
+
+    // Maps a distance along a stack's lane to a cell of the simulated view
+    // under the camera
+    fn canvas_to_view(&self, stack: &GlyphStack, distance: i16) -> Option<(u16, u16)> {
+        let (sim_width, sim_height) = self.sim_size();
+        let (x, y) = stack
+            .edge
+            .to_cell(stack.x, distance, sim_width, self.canvas_height())?;
+        let y = y.checked_sub(self.camera_y)?;
+        (y < sim_height).then_some((x, y))
+    }
+
+    // Whether the stack's head glyph was pushed recently enough to flash
+    fn head_flashing(&self, stack: &GlyphStack) -> bool {
+        stack.head_age < self.head_flash as u32
+            && matches!(stack.phase, StackPhase::Spawning | StackPhase::Falling)
+    }
+
+    // Dims the head as the stack moves through its step (once it's done
+    // flashing), and fades the upcoming glyph in on the next cell along its lane
+    fn interpolate_head(&self, stack: &GlyphStack, view: &mut Viewport) {
+        if matches!(stack.phase, StackPhase::Draining | StackPhase::Dissolving) {
+            return;
+        }
+        let fraction = stack.fall_fraction();
+        let palette = self.palette(stack.hue);
+        let palette = Palette {
+            head: self.brightened(palette.head),
+            body: self.brightened(palette.body),
+            ..palette
+        };
+        if let Some((x, y)) = self.canvas_to_view(stack, stack.max_y) {
+            let dims = |c: &Cell| c.color == palette.head && !self.head_flashing(stack);
+            if let Some(head) = view.get(x, y).filter(dims) {
+                let color = palette.head.blend(palette.body, fraction);
+                view.set(x, y, Cell { color, ..head });
+            }
+        }
+        if let Some((x, y)) = self.canvas_to_view(stack, stack.max_y + 1) {
+            if view.get(x, y).is_none() {
+                let color = palette.background.blend(palette.head, fraction);
+                view.set(
+                    x,
+                    y,
+                    Cell {
+                        ch: stack.next_value,
+                        color,
+                        background: None,
+                        attributes: self.head_attributes,
+                    },
+                );
+            }
+        }
+    }
+
+    // Spawns and steps the stacks of rain
+    fn update_rain(&mut self, dt: Duration) -> EffectStats {
+        let mut stats = EffectStats::default();
+
+        // A spawn pattern spawns in place of the density, on its schedule
+        let spawn_width = self.spawn_width();
+        if self.spawn_queue.is_some() {
+            stats.spawned += self.spawn_pattern_actions(dt);
+        } else {
+            // Determine whether any new stacks should be spawned, scaling with speed
+            let speed_multiplier = self.speed as f64 / 10.0;
+            let effective_density =
+                self.density * speed_multiplier * self.spawn_scale * base_ticks(dt);
+
+            let guaranteed_spawns = effective_density.floor() as u32;
+            let chance_for_one_more = effective_density.fract();
+
+            for _ in 0..guaranteed_spawns {
+                if self.spawn_random_stack(spawn_width) {
+                    stats.spawned += 1;
+                }
+            }
+
+            if self.rngs.spawn.random_bool(chance_for_one_more)
+                && self.spawn_random_stack(spawn_width)
+            {
+                stats.spawned += 1;
+            }
+        }
+
+        // Recently pressed keys add localized downpours over their columns
+        if self.heat_map_enabled {
+            let step = self.zoom.column_step();
+            let edge = self.vertical_edge();
+            for x in self.heat_map.spawn_columns(&mut self.rngs.spawn, 2, base_ticks(dt)) {
+                let x = self.zoom.to_sim_x(x) / step * step;
+                if x + step <= spawn_width && !self.lane_is_reserved(edge, x, step) {
+                    self.spawn_stack(x, edge);
+                    stats.spawned += 1;
+                }
+            }
+        }
+
+        // Update glyph stacks
+        let travel = [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right].map(|e| self.travel(e));
+        // Rows are taller than glyphs are wide, so normalized stacks take
+        // longer over each step down than each step sideways
+        let row_scale = if self.normalized_speed {
+            self.cell_aspect / DEFAULT_CELL_ASPECT
+        } else {
+            1.0
+        };
+        let (sim_width, _) = self.sim_size();
+        self.wind.update(dt, sim_width.max(self.canvas_height()));
+        for stack in &mut self.stacks {
+            let before_len = stack.stack.len();
+            let travel = travel[stack.edge as usize];
+            stack.gust = self.wind.gust(stack.x);
+            let dt = if stack.edge.is_horizontal() {
+                dt
+            } else {
+                dt.div_f64(row_scale)
+            };
+            stack.update(
+                dt,
+                self.speed,
+                self.mutation_rate,
+                self.jitter,
+                travel,
+                &mut self.rngs,
+            );
+            let after_len = stack.stack.len();
+            if after_len > before_len {
+                stats.glyphs += 1;
+            }
+            if self.dissolve && stack.phase == StackPhase::Draining {
+                stack.phase = StackPhase::Dissolving;
+            }
+        }
 
+        // Stacks that have drained off the canvas are deleted
+        self.retain_stacks(|_, s| s.phase != StackPhase::Dead);
+
+        stats
+    }
+
+    // Only the part of the canvas under the camera is rendered
+    fn render_rain(&self, view: &mut Viewport) {
+        for stack in &self.stacks {
+            let len = stack.stack.len();
+            let flashing = self.head_flashing(stack);
+            let palette = self.palette(stack.hue);
+            for (i, glyph) in stack.stack.iter().enumerate() {
+                // Blanks (spaces in text, or glyphs dissolved away) show what's beneath
+                if glyph.value == ' ' {
+                    continue;
+                }
+                let Some((x, y)) = self.canvas_to_view(stack, stack.max_y - i as i16) else {
+                    continue;
+                };
+                let position = glyph.age as f64 / (stack.length.max(2) - 1) as f64;
+                let color = match (&self.trail_colors, stack.hue) {
+                    (Some(stops), _) => stops.color_at(position),
+                    (None, Some(_)) => palette.color(glyph.color, position, self.high_contrast),
+                    (None, None) => {
+                        let (high_contrast, light) = (self.high_contrast, self.light);
+                        self.theme.color(glyph.color, position, high_contrast, light)
+                    }
+                };
+                let mut color = self.brightened(color);
+                // The older half of the trail dims glyph by glyph, as they age
+                let fade = tail_fade(position);
+                if fade > 0.0 && self.trail_colors.is_none() && !self.high_contrast {
+                    color = color.blend(palette.background, fade);
+                }
+                let mut color = self.phase_style.style(stack.phase, i, len, color);
+                if let Some(tint) = stack.tint {
+                    color = tinted(color, tint);
+                }
+                if stack.phase == StackPhase::Dissolving {
+                    color = color.blend(palette.background, stack.dissolve_fraction());
+                }
+                // A new head flashes at full brightness, over its own background if it has one
+                let flash = i == 0 && flashing;
+                if flash {
+                    let brightest = palette.high_contrast().head;
+                    color = stack.tint.map_or(brightest, |t| tinted(brightest, t));
+                }
+                let glow = match self.flash_background {
+                    Some(background) if flash => Some(background),
+                    _ => self.glow,
+                };
+                let background = match (glow, stack.tint) {
+                    (Some(_), _) if i != 0 || stack.phase == StackPhase::Dissolving => None,
+                    (Some(glow), Some(tint)) => Some(tinted(glow, tint)),
+                    (glow, _) => glow,
+                };
+                let attributes = match i {
+                    0 if flash => self.head_attributes | Attributes::BOLD,
+                    0 => self.head_attributes,
+                    _ if i > len / 2 => self.tail_attributes,
+                    _ => Attributes::default(),
+                };
+                let cell_to_add = Cell {
+                    ch: glyph.value,
+                    color,
+                    background,
+                    attributes,
+                };
+                view.set(x, y, cell_to_add);
+            }
+
+            // Between steps, the head's brightness blends from its row into the next one
+            if self.smooth {
+                self.interpolate_head(stack, view);
+            }
+        }
+    }
+
+    /// Advances the simulation by `dt` (the time since the last update), and
+    /// returns the cells that changed on screen. Passing a fixed `dt` makes
+    /// runs with the same seed reproducible. While fast-forwarding, the
+    /// simulation takes several steps of `dt` per update, and while paused
+    /// it takes none, so nothing changes.
+    pub fn update_and_get_changes(&mut self, dt: Duration) -> Vec<Change> {
+        self.changes_after(self.steps_per_update(), dt, None)
+    }
+
+    /// Like [`Game::update_and_get_changes`], but hands out at most
+    /// `max_changes` changes, for embedders bounding the time they spend
+    /// drawing each frame. The most important changes (see
+    /// [`ChangePriority`]) go first, in the order they'd otherwise come, and
+    /// the rest are deferred to later updates, which catch up on them as the
+    /// budget allows.
+    pub fn update_with_budget(&mut self, dt: Duration, max_changes: usize) -> Vec<Change> {
+        self.changes_after(self.steps_per_update(), dt, Some(max_changes))
+    }
+
+    /// Advances the simulation by a single step of `dt`, paused or not, and
+    /// returns the cells that changed on screen, for stepping through a
+    /// paused run a tick at a time.
+    pub fn step_and_get_changes(&mut self, dt: Duration) -> Vec<Change> {
+        self.changes_after(1, dt, None)
+    }
+
+    fn steps_per_update(&self) -> u32 {
+        match (self.paused, self.fast_forward) {
+            (true, _) => 0,
+            (false, true) => FAST_FORWARD_STEPS,
+            (false, false) => 1,
+        }
+    }
+
+    // Takes steps of dt, working out the changes they make on screen, at most
+    // the budget's worth if there is one
+    fn changes_after(&mut self, steps: u32, dt: Duration, budget: Option<usize>) -> Vec<Change> {
+        // Held back and deferred changes leave the screen behind the view, so
+        // the changes are worked out from what's been drawn
+        let holding_back = self.color_threshold.is_some() || self.interlace.is_some();
+        if budget.is_some() && !self.deferring && !holding_back && !self.verify_frames {
+            self.emitted = self.current_view.clone(); // not kept up to date until now
+        }
+        let holding_back = holding_back || budget.is_some() || self.deferring;
+        self.deferring = budget.is_some();
+        let mut previous_view = if holding_back {
+            self.emitted.clone()
+        } else {
+            self.current_view.clone()
+        };
+        self.scrolled_rows = 0;
+        for _ in 0..steps {
+            self.step(dt);
+        }
+        let mut changes = diff_viewports(&previous_view, &self.current_view);
+
+        // Scrolling is only worth it if it leaves less to draw
+        self.scroll = None;
+        if let Some(scroll) = self.scroll_candidate() {
+            previous_view.scroll(scroll);
+            let scrolled_changes = diff_viewports(&previous_view, &self.current_view);
+            if scrolled_changes.len() < changes.len() {
+                changes = scrolled_changes;
+                self.scroll = Some(scroll);
+            }
+        }
+        if !self.verify_frames && !holding_back {
+            return changes;
+        }
+
+        if let Some(scroll) = self.scroll {
+            self.emitted.scroll(scroll);
+        }
+        let (threshold, field) = (self.color_threshold, self.interlace);
+        if holding_back {
+            let emitted = &self.emitted;
+            changes.retain(|change| !held_back(emitted, change, threshold, field));
+        }
+        if let Some(budget) = budget {
+            changes = within_budget(changes, budget);
+        }
+        self.interlace = field.map(|field| 1 - field);
+        for change in &changes {
+            self.emitted.apply(change);
+        }
+        if !self.verify_frames {
+            return changes;
+        }
+        let expected = self.current_view.fingerprint();
+        self.telemetry.frame_hash = expected;
+        // Held back changes are all that may tell what's drawn from the view,
+        // and deferred ones may be anywhere
+        let drifted = if budget.is_some() {
+            false
+        } else if holding_back {
+            diff_viewports(&self.emitted, &self.current_view)
+                .iter()
+                .any(|change| !held_back(&self.emitted, change, threshold, field))
+        } else {
+            self.emitted.fingerprint() != expected
+        };
+        if drifted {
+            return self.full_repaint();
+        }
         changes
     }
+
+    // Advances the simulation by dt, updating the current view
+    fn step(&mut self, dt: Duration) {
+        self.heat_map.decay(base_ticks(dt));
+
+        // Other effects stand in for the rain, beneath the same overlays
+        let (sim_width, sim_height) = self.sim_size();
+        let mut sim_view = Viewport::new(sim_width, sim_height);
+        let params = self.effect_params(dt);
+        let stats = if !self.pipeline.is_empty() {
+            self.update_pipeline(dt, &mut sim_view)
+        } else if let Some(effect) = &mut self.effect {
+            let stats = effect.update(&params, &mut self.rngs);
+            effect.render(&mut sim_view);
+            self.scrolled_rows = self.scrolled_rows.saturating_add(effect.scrolled());
+            stats
+        } else {
+            let stats = self.update_rain(dt);
+            self.render_rain(&mut sim_view);
+            stats
+        };
+        let mut next_view = self.zoom.project(&sim_view, self.width, self.height);
+        self.coverage = next_view.coverage();
+        if let Some(balancer) = &mut self.balancer {
+            self.spawn_scale = balancer.update(self.coverage);
+        }
+
+        // Embers are left after measuring the coverage, which counts only what moves
+        self.embers.apply(&mut next_view);
+
+        // Stacks passing through the spotlight render brighter
+        for (x, brightness) in self.column_brightness.iter().enumerate() {
+            if *brightness != 0 {
+                for y in 0..self.height {
+                    if let Some(cell) = next_view.get(x as u16, y) {
+                        let color = cell.color.shifted(*brightness);
+                        next_view.set(x as u16, y, Cell { color, ..cell });
+                    }
+                }
+            }
+        }
+
+        // The colors turn round the spectrum, in step with the simulation
+        if let Some(period) = self.hue_cycle {
+            let elapsed = (self.hue_elapsed + dt).as_nanos() % period.as_nanos();
+            self.hue_elapsed = Duration::from_nanos(elapsed as u64);
+            let degrees = self.hue_elapsed.as_secs_f64() / period.as_secs_f64() * 360.0;
+            next_view.recolor(|color| rotate_hue(color, degrees));
+        }
+
+        next_view = self.mirror.apply(&next_view);
+
+        // Lightning overrides the brightness of the screen (or a region) for a frame or two
+        if let Some(flash) = self
+            .lightning
+            .update(&mut self.rngs.effects, self.width, self.height, base_ticks(dt))
+        {
+            next_view = flash.apply(&next_view);
+        }
+
+        if let Some(mask) = &self.mask {
+            next_view = mask.apply(&next_view);
+        }
+
+        // Overlays are layers, blended down onto the view in order
+        let (width, height) = (self.width, self.height);
+        if let Some(banner) = &mut self.banner {
+            banner.update(dt);
+            banner
+                .layer(width, height, &mut self.rngs.effects)
+                .composite(&mut next_view);
+        }
+
+        if let Some(title) = &mut self.title {
+            if title.update(dt) {
+                self.events.push(SimEvent::TitleRevealed);
+            }
+            title
+                .layer(width, height, &mut self.rngs.effects)
+                .composite(&mut next_view);
+        }
+
+        if self.chime.as_mut().is_some_and(|chime| !chime.update(dt)) {
+            self.chime = None;
+        }
+        if let Some(chime) = &self.chime {
+            chime
+                .layer(width, height, &mut self.rngs.effects)
+                .composite(&mut next_view);
+        }
+
+        if self.caption.as_mut().is_some_and(|caption| !caption.update(dt)) {
+            self.caption = None;
+        }
+        if let Some(caption) = &self.caption {
+            caption.layer(width, height).composite(&mut next_view);
+        }
+
+        self.reserved.apply(&mut next_view);
+        self.current_view = next_view;
+
+        // Update the telemetry, and its rates once a second
+        let telemetry = &mut self.telemetry;
+        if self.rates.record(dt, stats.glyphs, stats.spawned, self.coverage) {
+            telemetry.last_second = self.rates.rates(Window::Second);
+            telemetry.last_ten_seconds = self.rates.rates(Window::TenSeconds);
+            telemetry.last_minute = self.rates.rates(Window::Minute);
+        }
+        telemetry.density = self.density;
+        telemetry.max_stack_height = self.max_stack_height;
+        telemetry.speed = self.speed;
+        telemetry.heat = self.heat_map.total();
+        telemetry.coverage = self.coverage;
+        telemetry.glyphs_per_update = stats.glyphs;
+        telemetry.stacks_per_update = stats.spawned;
+        telemetry.stack_pool = self.pool.stats();
+        let delays = self.stacks.iter().map(|s| s.update_interval.as_millis() as u64);
+        telemetry.min_glyph_delay_ms = delays.clone().min().unwrap_or(0);
+        telemetry.max_glyph_delay_ms = delays.max().unwrap_or(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyph(ch: char) -> Cell {
+        Cell {
+            ch,
+            color: AnsiColor::Green,
+            background: None,
+            attributes: Attributes::default(),
+        }
+    }
+
+    fn positions(changes: &[Change]) -> Vec<(u16, u16)> {
+        let mut positions: Vec<_> = changes.iter().map(Change::position).collect();
+        positions.sort();
+        positions
+    }
+
+    #[test]
+    fn diff_of_same_size_views_reports_changed_cells() {
+        let mut old = Viewport::new(4, 3);
+        old.set(0, 0, glyph('a'));
+        old.set(1, 1, glyph('b'));
+        let mut new = old.clone();
+        new.clear(0, 0);
+        new.set(2, 2, glyph('c'));
+
+        let changes = diff_viewports(&old, &new);
+        assert_eq!(positions(&changes), vec![(0, 0), (2, 2)]);
+        assert!(matches!(changes[0], Change::Remove(0, 0)));
+        assert!(matches!(changes[1], Change::Update(2, 2, 'c', _, _, _, ChangePriority::HeadMove)));
+    }
+
+    #[test]
+    fn diff_never_splits_a_wide_glyph_with_the_cell_it_covers() {
+        let old = Viewport::new(5, 1);
+        let mut new = Viewport::new(5, 1);
+        new.set(0, 0, glyph('日'));
+        new.set(1, 0, glyph('a')); // hidden under the right half of 日
+        new.set(2, 0, glyph('b'));
+        new.set(4, 0, glyph('月')); // no room for its right half
+
+        let changes = diff_viewports(&old, &new);
+        assert_eq!(positions(&changes), vec![(0, 0), (2, 0)]);
+
+        // Clearing the wide glyph's left cell clears both of its cells
+        let mut newer = Viewport::new(5, 1);
+        newer.set(1, 0, glyph('a'));
+        let changes = diff_viewports(&new, &newer);
+        assert_eq!(positions(&changes), vec![(0, 0), (1, 0), (2, 0)]);
+        assert!(matches!(changes[0], Change::Remove(0, 0)));
+        assert!(matches!(changes[1], Change::Update(1, 0, 'a', _, _, _, _)));
+
+        // Removing the wide glyph clears the cell it covered too
+        let changes = diff_viewports(&new, &Viewport::new(5, 1));
+        assert!(matches!(changes[..2], [Change::Remove(0, 0), Change::Remove(1, 0)]));
+    }
+
+    #[test]
+    fn diff_reports_background_changes_as_color_changes() {
+        let mut old = Viewport::new(2, 1);
+        old.set(0, 0, glyph('a'));
+        let mut new = old.clone();
+        let background = Some(AnsiColor::Rgb(0, 68, 0));
+        new.set(0, 0, Cell { background, ..glyph('a') });
+
+        let changes = diff_viewports(&old, &new);
+        assert_eq!(positions(&changes), vec![(0, 0)]);
+        assert!(matches!(
+            changes[0],
+            Change::Update(0, 0, 'a', _, Some(AnsiColor::Rgb(0, 68, 0)), _, ChangePriority::Color)
+        ));
+    }
+
+    #[test]
+    fn diff_reports_attribute_changes_as_color_changes() {
+        let mut old = Viewport::new(2, 1);
+        old.set(0, 0, glyph('a'));
+        let mut new = old.clone();
+        let attributes = Attributes::BOLD | Attributes::ITALIC;
+        new.set(0, 0, Cell { attributes, ..glyph('a') });
+
+        let changes = diff_viewports(&old, &new);
+        assert_eq!(positions(&changes), vec![(0, 0)]);
+        assert!(matches!(
+            changes[0],
+            Change::Update(0, 0, 'a', _, None, attributes, ChangePriority::Color)
+                if attributes.contains(Attributes::BOLD) && !attributes.contains(Attributes::DIM)
+        ));
+    }
+
+    #[test]
+    fn diff_into_smaller_view_removes_vanished_glyphs() {
+        let mut old = Viewport::new(6, 4);
+        old.set(1, 1, glyph('a'));
+        old.set(5, 0, glyph('b'));
+        old.set(2, 3, glyph('c'));
+        let mut new = Viewport::new(4, 2);
+        new.set(1, 1, glyph('a'));
+
+        let changes = diff_viewports(&old, &new);
+        assert_eq!(positions(&changes), vec![(2, 3), (5, 0)]);
+        assert!(changes.iter().all(|c| matches!(c, Change::Remove(..))));
+    }
+
+    #[test]
+    fn diff_into_larger_view_draws_new_glyphs() {
+        let mut old = Viewport::new(2, 2);
+        old.set(0, 0, glyph('a'));
+        let mut new = Viewport::new(5, 3);
+        new.set(0, 0, glyph('a'));
+        new.set(4, 0, glyph('b'));
+        new.set(1, 2, glyph('c'));
+
+        let changes = diff_viewports(&old, &new);
+        assert_eq!(positions(&changes), vec![(1, 2), (4, 0)]);
+        assert!(changes.iter().all(|c| c.priority() == ChangePriority::HeadMove));
+    }
+
+    #[test]
+    fn diff_across_resize_with_different_aspect() {
+        // Wider but shorter: the bottom rows vanish while new columns appear
+        let mut old = Viewport::new(3, 4);
+        old.set(2, 3, glyph('a'));
+        old.set(0, 0, glyph('b'));
+        let mut new = Viewport::new(5, 2);
+        new.set(0, 0, glyph('c'));
+        new.set(4, 1, glyph('d'));
+
+        let changes = diff_viewports(&old, &new);
+        assert_eq!(positions(&changes), vec![(0, 0), (2, 3), (4, 1)]);
+        for change in &changes {
+            match change.position() {
+                (0, 0) => assert_eq!(change.priority(), ChangePriority::Mutation),
+                (2, 3) => assert!(matches!(change, Change::Remove(..))),
+                _ => assert_eq!(change.priority(), ChangePriority::HeadMove),
+            }
+        }
+    }
+
+    #[test]
+    fn diff_of_empty_views_across_resize_is_empty() {
+        let changes = diff_viewports(&Viewport::new(10, 5), &Viewport::new(3, 8));
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn small_recolors_are_held_back_but_glyph_changes_are_not() {
+        let mut view = Viewport::new(1, 1);
+        view.set(0, 0, glyph('a'));
+        let attributes = Attributes::default();
+        let recolor = |ch, color| {
+            Change::Update(0, 0, ch, color, None, attributes, ChangePriority::Color)
+        };
+
+        let slight = AnsiColor::Green.blend(AnsiColor::DarkGreen, 0.05);
+        assert!(recolors_within(&view, &recolor('a', slight), 10.0));
+        assert!(!recolors_within(&view, &recolor('a', AnsiColor::DarkGreen), 10.0));
+        assert!(!recolors_within(&view, &recolor('b', slight), 10.0));
+        assert!(!recolors_within(&view, &Change::Remove(0, 0), 10.0));
+    }
+
+    #[test]
+    fn frames_are_verified_with_recolors_held_back() {
+        let mut game = Game::new(20, 10, Settings::default());
+        game.set_seed(3);
+        game.set_color_threshold(Some(10.0));
+        game.set_verify_frames(true);
+        for _ in 0..50 {
+            game.update_and_get_changes(BASE_TICK);
+        }
+        assert_eq!(game.telemetry().full_repaints(), 0);
+        assert_eq!(game.telemetry().frame_hash, game.current_view.fingerprint());
+    }
+
+    #[test]
+    fn mirrored_glyphs_are_kept_across_glyph_sets() {
+        let mut game = Game::new(20, 10, Settings::default());
+        game.set_glyph_set(GlyphSet::from_text("R(").unwrap());
+        game.set_mirrored_glyphs(true);
+        let mut rngs = RngStreams::new(1);
+        let mut seen: Vec<char> = (0..100)
+            .map(|_| game.glyph_set.random_glyph(&mut rngs.glyphs))
+            .collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen, [')', 'Я']);
+        game.set_charset(Charset::BoxDrawing);
+        assert!(game.mirrored_glyphs());
+    }
+
+    #[test]
+    fn stacks_fall_through_canvases_too_tall_for_their_distances() {
+        let mut game = Game::new(20, 2000, Settings::default());
+        game.set_seed(1);
+        game.set_zoom(Zoom::Braille);
+        game.set_pages(100);
+        for _ in 0..20 {
+            game.update_and_get_changes(Duration::from_millis(50));
+        }
+        assert!(!game.stacks.is_empty());
+    }
+
+    #[test]
+    fn pressed_keys_rain_outside_reserved_columns() {
+        let mut game = Game::new(40, 10, Settings::default());
+        game.set_seed(2);
+        game.set_density(0.1);
+        game.set_reserved(Reserved {
+            left: 20,
+            ..Reserved::default()
+        });
+        game.toggle_heat_map();
+        for _ in 0..100 {
+            "qwaszx".chars().for_each(|key| game.record_key(key));
+            game.update_and_get_changes(Duration::from_millis(50));
+            assert!(game.stacks.iter().all(|stack| stack.x >= 20));
+        }
+    }
+
+    #[test]
+    fn paused_games_change_nothing_and_keep_their_frame_across_resizes() {
+        let mut game = Game::new(20, 10, Settings::default());
+        game.set_seed(1);
+        for _ in 0..40 {
+            game.update_and_get_changes(Duration::from_millis(50));
+        }
+        game.set_paused(true);
+        let frozen = game.current_view.fingerprint();
+        for _ in 0..40 {
+            assert!(game.update_and_get_changes(Duration::from_millis(50)).is_empty());
+        }
+        assert_eq!(game.current_view.fingerprint(), frozen);
+
+        game.resize(30, 12);
+        game.resize(20, 10);
+        assert_eq!(game.current_view.fingerprint(), frozen);
+        game.set_paused(false);
+        assert!(!game.update_and_get_changes(Duration::from_millis(50)).is_empty());
+    }
+
+    #[test]
+    fn paused_games_step_as_running_ones_do() {
+        let (mut running, mut paused) = (
+            Game::new(20, 10, Settings::default()),
+            Game::new(20, 10, Settings::default()),
+        );
+        running.set_seed(3);
+        paused.set_seed(3);
+        paused.set_paused(true);
+        let dt = Duration::from_millis(50);
+        for _ in 0..60 {
+            let expected = running.update_and_get_changes(dt);
+            assert!(paused.update_and_get_changes(dt).is_empty());
+            let changes = paused.step_and_get_changes(dt);
+            assert_eq!(positions(&changes), positions(&expected));
+        }
+        let frame = running.current_view.fingerprint();
+        assert_eq!(paused.current_view.fingerprint(), frame);
+    }
+
+    #[test]
+    fn despawned_stacks_lend_their_buffers_to_new_ones() {
+        let mut game = Game::new(20, 10, Settings::default());
+        game.set_seed(1);
+        for _ in 0..600 {
+            game.update_and_get_changes(Duration::from_millis(50));
+        }
+
+        let pool = game.telemetry().stack_pool();
+        assert!(pool.reused > pool.allocated, "{:?}", pool);
+        for stack in &game.stacks {
+            assert!(stack.stack.capacity() > stack.length as usize);
+        }
+    }
+
+    #[test]
+    fn interlaced_updates_draw_alternate_rows_and_catch_up() {
+        let mut game = Game::new(20, 10, Settings::default());
+        game.set_seed(5);
+        game.set_interlace(true);
+        let mut screen = Viewport::new(20, 10);
+        for frame in 0..60 {
+            let changes = game.update_and_get_changes(Duration::from_millis(50));
+            assert!(changes
+                .iter()
+                .all(|change| change.position().1 % 2 == frame % 2));
+            changes.iter().for_each(|change| screen.apply(change));
+        }
+        game.set_paused(true);
+        for change in game.update_and_get_changes(Duration::ZERO) {
+            screen.apply(&change);
+        }
+        assert_eq!(screen.fingerprint(), game.current_view.fingerprint());
+    }
+
+    #[test]
+    fn budgeted_updates_defer_the_least_important_changes_and_catch_up() {
+        let mut game = Game::new(20, 10, Settings::default());
+        game.set_seed(5);
+        let mut screen = Viewport::new(20, 10);
+        for _ in 0..60 {
+            let changes = game.update_with_budget(Duration::from_millis(50), 8);
+            assert!(changes.len() <= 8);
+            changes.iter().for_each(|change| screen.apply(change));
+        }
+        game.set_paused(true);
+        loop {
+            let changes = game.update_with_budget(Duration::ZERO, 8);
+            if changes.is_empty() {
+                break;
+            }
+            changes.iter().for_each(|change| screen.apply(change));
+        }
+        assert_eq!(screen.fingerprint(), game.current_view.fingerprint());
+
+        let changes = vec![
+            Change::Remove(0, 0),
+            Change::Remove(1, 0),
+            glyph('a').change(2, 0, ChangePriority::HeadMove),
+        ];
+        let kept = within_budget(changes, 2);
+        assert_eq!(positions(&kept), [(0, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn glyphs_are_colored_by_age_and_fade_down_the_older_half() {
+        let mut rngs = RngStreams::new(1);
+        let mut glyphs = VecDeque::new();
+        for value in "abcdef".chars() {
+            advance_glyphs(&mut glyphs, value, 4, 0.0, &GlyphSet::default(), &mut rngs);
+        }
+        let ages: Vec<(char, u16, AnsiColor)> =
+            glyphs.iter().map(|g| (g.value, g.age, g.color)).collect();
+        assert_eq!(
+            ages,
+            [
+                ('f', 0, AnsiColor::White),
+                ('e', 1, AnsiColor::Green),
+                ('d', 2, AnsiColor::DarkGreen),
+                ('c', 3, AnsiColor::DarkGreen),
+            ]
+        );
+        assert_eq!(tail_fade(0.5), 0.0);
+        assert!(tail_fade(0.75) < tail_fade(1.0));
+        assert_eq!(tail_fade(1.0), TAIL_FADE);
+    }
+
+    #[test]
+    fn state_hashes_agree_until_games_diverge() {
+        let (mut a, mut b) = (
+            Game::new(30, 12, Settings::default()),
+            Game::new(30, 12, Settings::default()),
+        );
+        a.set_seed(8);
+        b.set_seed(8);
+        let dt = Duration::from_millis(50);
+        let mut last = a.state_hash();
+        for _ in 0..40 {
+            a.update_and_get_changes(dt);
+            b.update_and_get_changes(dt);
+            assert_eq!(a.state_hash(), b.state_hash());
+            assert_ne!(a.state_hash(), last);
+            last = a.state_hash();
+        }
+        b.increase_density();
+        assert_ne!(a.state_hash(), b.state_hash());
+    }
 }
@@ -0,0 +1,86 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use falling_glyphs::game::AnsiColor;
+
+// How often the repository is checked for new commits
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// Commits rain in cyan, standing out from the green
+pub const COMMIT_COLOR: AnsiColor = AnsiColor::Rgb(0, 200, 255);
+
+// A commit that landed in the watched repository
+pub struct Commit {
+    pub short_hash: String,
+    pub subject: String,
+}
+
+// Runs git in the repository, returning what it printed
+fn git(repo: &Path, args: &[&str]) -> io::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(stderr.trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn head(repo: &Path) -> io::Result<String> {
+    git(repo, &["rev-parse", "HEAD"]).map(|hash| hash.trim().to_string())
+}
+
+// The commits reachable from `to` but not `from`, oldest first
+fn commits_between(repo: &Path, from: &str, to: &str) -> io::Result<Vec<Commit>> {
+    let range = format!("{}..{}", from, to);
+    let log = git(repo, &["log", "--reverse", "--format=%h%x00%s", &range])?;
+    let commits = log
+        .lines()
+        .filter_map(|line| line.split_once('\0'))
+        .map(|(short_hash, subject)| Commit {
+            short_hash: short_hash.to_string(),
+            subject: subject.to_string(),
+        })
+        .collect();
+    Ok(commits)
+}
+
+// Polls a repository's HEAD with the git CLI on a thread of its own,
+// sending each commit that lands after watching starts. Failed polls (say,
+// mid-rebase) are retried at the next one.
+pub struct GitWatcher {
+    pub commits: Receiver<Commit>,
+}
+
+impl GitWatcher {
+    pub fn spawn(repo: PathBuf) -> io::Result<Self> {
+        let mut last = head(&repo)?;
+        let (sender, commits) = mpsc::channel();
+        thread::spawn(move || loop {
+            thread::sleep(POLL_INTERVAL);
+            let Ok(current) = head(&repo) else {
+                continue;
+            };
+            if current == last {
+                continue;
+            }
+            let Ok(new_commits) = commits_between(&repo, &last, &current) else {
+                continue;
+            };
+            last = current;
+            for commit in new_commits {
+                if sender.send(commit).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Self { commits })
+    }
+}
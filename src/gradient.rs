@@ -0,0 +1,107 @@
+use std::str::FromStr;
+
+use crate::game::AnsiColor;
+
+// Colors bound to positions along a stack, from its head (0.0) to the end of
+// its tail (1.0), so trails of any length color the same way. Glyphs between
+// two stops blend between their colors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorStops {
+    stops: Vec<(f64, AnsiColor)>, // sorted by position
+}
+
+impl ColorStops {
+    pub fn new(mut stops: Vec<(f64, AnsiColor)>) -> Self {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    // Color at a position (0.0 - 1.0) along the stack
+    pub fn color_at(&self, position: f64) -> AnsiColor {
+        let after = self.stops.iter().position(|(at, _)| *at >= position);
+        match after {
+            None => self
+                .stops
+                .last()
+                .map_or(AnsiColor::Green, |(_, color)| *color),
+            Some(0) => self.stops[0].1,
+            Some(i) => {
+                let (start, from) = self.stops[i - 1];
+                let (end, to) = self.stops[i];
+                if position == end {
+                    to
+                } else {
+                    from.blend(to, (position - start) / (end - start))
+                }
+            }
+        }
+    }
+}
+
+// Parses a color: `white`, `green`, `dark-green` or `#rrggbb`
+pub fn parse_color(text: &str) -> Result<AnsiColor, String> {
+    match text {
+        "white" => Ok(AnsiColor::White),
+        "green" => Ok(AnsiColor::Green),
+        "dark-green" => Ok(AnsiColor::DarkGreen),
+        _ => {
+            let hex = text
+                .strip_prefix('#')
+                .filter(|hex| hex.len() == 6)
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| {
+                    format!("`{text}` isn't a color (use white, green, dark-green or #rrggbb)")
+                })?;
+            Ok(AnsiColor::Rgb(
+                (hex >> 16) as u8,
+                (hex >> 8) as u8,
+                hex as u8,
+            ))
+        }
+    }
+}
+
+// Parses a list of percentages and colors, like `0=white,30=green,100=#003300`
+impl FromStr for ColorStops {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut stops = Vec::new();
+        for part in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (percent, color) = part
+                .split_once('=')
+                .ok_or_else(|| format!("`{part}` isn't a percentage and color, like `0=white`"))?;
+            let percent: f64 = percent
+                .trim()
+                .trim_end_matches('%')
+                .parse()
+                .ok()
+                .filter(|percent| (0.0..=100.0).contains(percent))
+                .ok_or_else(|| format!("`{percent}` isn't a percentage between 0 and 100"))?;
+            stops.push((percent / 100.0, parse_color(color.trim())?));
+        }
+        if stops.is_empty() {
+            return Err("no color stops given".to_string());
+        }
+        Ok(Self::new(stops))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_stops_parse_and_blend_between_stops() {
+        let stops: ColorStops = "100=#000000, 0%=white".parse().unwrap();
+        let black = AnsiColor::Rgb(0, 0, 0);
+        assert_eq!(stops.color_at(0.0), AnsiColor::White);
+        assert_eq!(stops.color_at(1.0), black);
+        assert_eq!(stops.color_at(0.5), AnsiColor::White.blend(black, 0.5));
+        assert_eq!(parse_color("#00ff80"), Ok(AnsiColor::Rgb(0, 255, 128)));
+        assert!(parse_color("#0f8").is_err());
+        assert!("".parse::<ColorStops>().is_err());
+        assert!("0=white,101=green".parse::<ColorStops>().is_err());
+        assert!("0:white".parse::<ColorStops>().is_err());
+    }
+}
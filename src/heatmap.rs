@@ -0,0 +1,139 @@
+use rand::Rng;
+
+// QWERTY rows, with the horizontal stagger (in key widths) of each row on a
+// physical keyboard.
+const KEY_ROWS: [(&str, f64); 4] = [
+    ("1234567890-=", 0.0),
+    ("qwertyuiop[]", 0.5),
+    ("asdfghjkl;'", 0.75),
+    ("zxcvbnm,./", 1.25),
+];
+
+// Width of the keyboard in key widths, including the widest row's stagger.
+const KEYBOARD_WIDTH: f64 = 13.5;
+
+// Shifted symbols on the number row share a column with their digit
+const SHIFTED_DIGITS: &str = "!@#$%^&*()_+";
+
+// Heat added for a single key press, and the fraction that remains after each
+// base tick
+const KEY_HEAT: f64 = 1.0;
+const DECAY: f64 = 0.92;
+
+// Heat below this is treated as cold, so quiet columns stop rolling for spawns
+const MIN_HEAT: f64 = 0.01;
+
+// Maps a key to the screen column under its position on a QWERTY keyboard
+// stretched across the given width.
+pub fn key_column(key: char, width: u16) -> Option<u16> {
+    if width == 0 {
+        return None;
+    }
+
+    let key = key.to_ascii_lowercase();
+    let key = SHIFTED_DIGITS
+        .find(key)
+        .and_then(|i| KEY_ROWS[0].0.chars().nth(i))
+        .unwrap_or(key);
+
+    KEY_ROWS.iter().find_map(|(row, stagger)| {
+        row.chars().position(|c| c == key).map(|i| {
+            let position = (i as f64 + stagger + 0.5) / KEYBOARD_WIDTH;
+            ((position * width as f64) as u16).min(width - 1)
+        })
+    })
+}
+
+// A decaying per-column heat array, warmed by key presses.
+pub struct HeatMap {
+    heat: Vec<f64>,
+}
+
+impl HeatMap {
+    pub fn new(width: u16) -> Self {
+        Self {
+            heat: vec![0.0; width as usize],
+        }
+    }
+
+    pub fn resize(&mut self, width: u16) {
+        self.heat.resize(width as usize, 0.0);
+    }
+
+    // Warms the column under the key, spreading into its neighbours.
+    pub fn record_key(&mut self, key: char) {
+        let width = self.heat.len() as u16;
+        let Some(center) = key_column(key, width) else {
+            return;
+        };
+
+        // Spread heat across roughly one key width
+        let radius = (width as f64 / KEYBOARD_WIDTH / 2.0).max(1.0);
+        let start = (center as f64 - radius).floor().max(0.0) as usize;
+        let end = ((center as f64 + radius).ceil() as usize).min(self.heat.len() - 1);
+        for x in start..=end {
+            let distance = (x as f64 - center as f64).abs() / radius;
+            if distance <= 1.0 {
+                self.heat[x] += KEY_HEAT * (1.0 - distance * distance);
+            }
+        }
+    }
+
+    // Cools every column over a time step of the given number of base ticks
+    pub fn decay(&mut self, ticks: f64) {
+        let decay = DECAY.powf(ticks);
+        for heat in &mut self.heat {
+            *heat *= decay;
+            if *heat < MIN_HEAT {
+                *heat = 0.0;
+            }
+        }
+    }
+
+    // Rolls for an extra spawn at each column, in proportion to its heat and
+    // the number of base ticks the time step spans.
+    pub fn spawn_columns<R: Rng>(&self, rng: &mut R, step: u16, ticks: f64) -> Vec<u16> {
+        self.heat
+            .iter()
+            .enumerate()
+            .step_by(step.max(1) as usize)
+            .filter(|(_, heat)| **heat > 0.0 && rng.random_bool((**heat * 0.5 * ticks).min(1.0)))
+            .map(|(x, _)| x as u16)
+            .collect()
+    }
+
+    pub fn total(&self) -> f64 {
+        self.heat.iter().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_map_to_the_columns_under_them_on_the_keyboard() {
+        let column = |key| key_column(key, 100).unwrap();
+        assert!(column('q') < column('w') && column('w') < column('p'));
+        assert!(column('a') > column('q') && column('z') > column('a'));
+        assert_eq!(column('Q'), column('q'));
+        assert_eq!(column('!'), column('1'));
+        assert_eq!(key_column('p', 1), Some(0));
+        assert_eq!(key_column('q', 0), None);
+        assert_eq!(key_column('€', 100), None);
+    }
+
+    #[test]
+    fn heat_cools_as_much_over_one_long_step_as_over_several_short_ones() {
+        let (mut short, mut long) = (HeatMap::new(80), HeatMap::new(80));
+        short.record_key('g');
+        long.record_key('g');
+        let before = long.total();
+        for _ in 0..5 {
+            short.decay(0.2);
+        }
+        long.decay(1.0);
+        assert!((short.total() - long.total()).abs() < 1e-9);
+        assert!((long.total() - before * DECAY).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,103 @@
+# The text shown on screen, in English: every message is here, and the other
+# catalogs fall back on these for any they don't have. Each `{ $name }` is
+# filled in with the argument of that name.
+
+## Captions
+config-reloaded = config reloaded
+config-not-reloaded = config not reloaded: { $error }
+replay-saved = saved { $seconds }s replay to { $path }
+replay-not-saved = failed to save replay: { $error }
+no-replay-buffer = no replay buffer (see --replay-buffer)
+
+## Presets
+preset-none = none
+preset-saved = { $preset } saved
+preset-not-saved = { $preset } not saved ({ $error })
+preset-empty = { $slot } is empty
+
+## Debug overlay
+on = on
+off = off
+overlay-density = Density:
+overlay-max-stack-height = Max Stack Height:
+overlay-speed = Speed Level:
+overlay-update-delay = Update delay (ms): { $delay }
+overlay-target-fps = Target FPS: { $fps }
+overlay-unlimited = unlimited
+overlay-updates = Updates/sec: { $rate } ({ $minute } over 1m)
+overlay-glyphs = Glyphs/sec: { $rate } ({ $minute } over 1m)
+overlay-glyphs-per-update = Glyphs/update: { $glyphs }
+overlay-stacks-per-update = Stacks/update: { $stacks }
+overlay-stack-delay = Min/Max stack update delay (ms): { $min }/{ $max }
+overlay-stack-pool = Stack pool: { $pooled } pooled, { $reused }% of spawns reused
+overlay-key-heat = Key heat: { $heat }
+overlay-progress = Progress: { $progress }
+overlay-coverage = Coverage: { $coverage }% ({ $trend }%/s)
+overlay-target-coverage = Target coverage: { $target }
+overlay-spawn-rate = { $target }% (spawn rate x{ $scale })
+overlay-fast-forward = Fast-forward: { $state }
+overlay-effect = Effect: { $effect }
+overlay-pipeline = Pipeline: { $stages }
+overlay-preset = Preset: { $preset }
+overlay-mutation-rate = Mutation rate: { $rate }
+overlay-zoom = Zoom: { $zoom }
+overlay-camera = Camera row: { $row }/{ $rows }
+overlay-spotlight = Spotlight: { $spotlight }
+overlay-spotlight-column = column { $column }
+overlay-terminal = Terminal: { $terminal }
+overlay-seed = Seed: { $seed }
+overlay-frame-hash = Frame hash: { $hash }
+overlay-full-repaints = Full repaints: { $repaints }
+
+## Frame time budget, in the debug overlay and the stress test's summary
+phase-input = Input
+phase-simulate = Simulate
+phase-compose = Compose
+phase-write = Write
+budget-phase = { $phase } (ms/frame): { $time } ({ $share }%)
+budget-fps = Frames/sec: { $fps }
+budget-written = Written (KiB/s): { $rate }
+stress-start = Stressing the simulation at { $width }x{ $height } for { $seconds }s with seed { $seed } (pass --terminal to stress the terminal)
+stress-second = { $budget } ({ $changes } changes/s{ $allocations }, { $coverage }% coverage, { $reused }% of stacks reused)
+stress-allocations = , { $allocations } allocations/s
+
+## Validating the config
+validate-ok = { $path }: no problems found
+validate-problem = 1 problem in { $path }
+validate-problems = { $count } problems in { $path }
+
+## Tuning
+tune-result = Tuned { $terminal }: { $fps } frames/s ({ $rate } KiB/s), truecolor: { $truecolor }, sync: { $sync }
+tune-yes = yes
+tune-no = no
+tune-unknown = didn't say
+tune-would-save = Would save: { $settings }
+tune-saved = Saved to { $path }: { $settings }
+
+## Setup wizard
+wizard-title = falling_glyphs setup
+wizard-step-title = falling_glyphs setup ({ $step }/{ $steps })
+wizard-welcome = Set up falling_glyphs now? Your choices become the default profile in its config file.
+wizard-set-up = Set up now
+wizard-skip = Skip, and don't ask again
+wizard-hint = Up/Down to choose, Enter to confirm, Esc to skip setup
+wizard-no = No
+wizard-yes = Yes
+wizard-weather = How heavy should the rain be?
+wizard-drizzle = Light drizzle
+wizard-steady = Steady (the default)
+wizard-downpour = Downpour
+wizard-storm = Storm, with lightning
+wizard-effect = Which effect should run?
+wizard-rain = Falling rain
+wizard-vortex = A vortex spiralling into the center
+wizard-helix = A double helix
+wizard-waterfall = A waterfall over hills
+wizard-sheet = Rain falling as one sheet
+wizard-theme = Which colors should the rain be?
+wizard-classic = Classic greens
+wizard-deuteranopia = Blues, for deuteranopia
+wizard-protanopia = Yellows, for protanopia
+wizard-tritanopia = Reds, for tritanopia
+wizard-high-contrast = Use high contrast? (bold heads over bright, unfading trails)
+wizard-smooth = Blend the glyphs smoothly between rows? (best on truecolor terminals)
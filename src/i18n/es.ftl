@@ -0,0 +1,101 @@
+# El texto que se muestra en pantalla, en español
+
+## Captions
+config-reloaded = configuración recargada
+config-not-reloaded = configuración no recargada: { $error }
+replay-saved = repetición de { $seconds }s guardada en { $path }
+replay-not-saved = no se pudo guardar la repetición: { $error }
+no-replay-buffer = no hay búfer de repetición (véase --replay-buffer)
+
+## Presets
+preset-none = ninguno
+preset-saved = { $preset } guardado
+preset-not-saved = { $preset } no guardado ({ $error })
+preset-empty = { $slot } está vacío
+
+## Debug overlay
+on = activado
+off = desactivado
+overlay-density = Densidad:
+overlay-max-stack-height = Altura máx. de pila:
+overlay-speed = Velocidad:
+overlay-update-delay = Retardo de actualización (ms): { $delay }
+overlay-target-fps = FPS objetivo: { $fps }
+overlay-unlimited = sin límite
+overlay-updates = Actualizaciones/s: { $rate } ({ $minute } en 1m)
+overlay-glyphs = Glifos/s: { $rate } ({ $minute } en 1m)
+overlay-glyphs-per-update = Glifos/actualización: { $glyphs }
+overlay-stacks-per-update = Pilas/actualización: { $stacks }
+overlay-stack-delay = Retardo mín./máx. de pila (ms): { $min }/{ $max }
+overlay-stack-pool = Reserva de pilas: { $pooled } en reserva, { $reused }% de apariciones reutilizadas
+overlay-key-heat = Calor de teclas: { $heat }
+overlay-progress = Progreso: { $progress }
+overlay-coverage = Cobertura: { $coverage }% ({ $trend }%/s)
+overlay-target-coverage = Cobertura objetivo: { $target }
+overlay-spawn-rate = { $target }% (tasa de aparición x{ $scale })
+overlay-fast-forward = Avance rápido: { $state }
+overlay-effect = Efecto: { $effect }
+overlay-pipeline = Cadena: { $stages }
+overlay-preset = Preajuste: { $preset }
+overlay-mutation-rate = Tasa de mutación: { $rate }
+overlay-zoom = Zoom: { $zoom }
+overlay-camera = Fila de cámara: { $row }/{ $rows }
+overlay-spotlight = Foco: { $spotlight }
+overlay-spotlight-column = columna { $column }
+overlay-terminal = Terminal: { $terminal }
+overlay-seed = Semilla: { $seed }
+overlay-frame-hash = Hash del fotograma: { $hash }
+overlay-full-repaints = Redibujados completos: { $repaints }
+
+## Frame time budget
+phase-input = Entrada
+phase-simulate = Simulación
+phase-compose = Composición
+phase-write = Escritura
+budget-phase = { $phase } (ms/fotograma): { $time } ({ $share }%)
+budget-fps = Fotogramas/s: { $fps }
+budget-written = Escrito (KiB/s): { $rate }
+stress-start = Sometiendo la simulación a estrés a { $width }x{ $height } durante { $seconds }s con semilla { $seed } (usa --terminal para someter a estrés el terminal)
+stress-second = { $budget } ({ $changes } cambios/s{ $allocations }, { $coverage }% de cobertura, { $reused }% de pilas reutilizadas)
+stress-allocations = , { $allocations } asignaciones/s
+
+## Validating the config
+validate-ok = { $path }: no se encontraron problemas
+validate-problem = 1 problema en { $path }
+validate-problems = { $count } problemas en { $path }
+
+## Tuning
+tune-result = Ajustado { $terminal }: { $fps } fotogramas/s ({ $rate } KiB/s), color verdadero: { $truecolor }, sincronización: { $sync }
+tune-yes = sí
+tune-no = no
+tune-unknown = sin respuesta
+tune-would-save = Se guardaría: { $settings }
+tune-saved = Guardado en { $path }: { $settings }
+
+## Setup wizard
+wizard-title = Configuración de falling_glyphs
+wizard-step-title = Configuración de falling_glyphs ({ $step }/{ $steps })
+wizard-welcome = ¿Configurar falling_glyphs ahora? Lo que elijas será el perfil predeterminado de su archivo de configuración.
+wizard-set-up = Configurar ahora
+wizard-skip = Omitir, y no volver a preguntar
+wizard-hint = Arriba/Abajo para elegir, Intro para confirmar, Esc para omitir la configuración
+wizard-no = No
+wizard-yes = Sí
+wizard-weather = ¿Con qué intensidad debe llover?
+wizard-drizzle = Llovizna suave
+wizard-steady = Constante (la predeterminada)
+wizard-downpour = Aguacero
+wizard-storm = Tormenta, con relámpagos
+wizard-effect = ¿Qué efecto se debe mostrar?
+wizard-rain = Lluvia cayendo
+wizard-vortex = Un vórtice en espiral hacia el centro
+wizard-helix = Una doble hélice
+wizard-waterfall = Una cascada sobre colinas
+wizard-sheet = Lluvia cayendo como una sola cortina
+wizard-theme = ¿De qué colores debe ser la lluvia?
+wizard-classic = Verdes clásicos
+wizard-deuteranopia = Azules, para deuteranopía
+wizard-protanopia = Amarillos, para protanopía
+wizard-tritanopia = Rojos, para tritanopía
+wizard-high-contrast = ¿Usar alto contraste? (cabezas en negrita sobre estelas brillantes que no se desvanecen)
+wizard-smooth = ¿Fundir los glifos suavemente entre filas? (mejor en terminales de color verdadero)
@@ -0,0 +1,101 @@
+# Le texte affiché à l'écran, en français
+
+## Captions
+config-reloaded = configuration rechargée
+config-not-reloaded = configuration non rechargée : { $error }
+replay-saved = rediffusion de { $seconds } s enregistrée dans { $path }
+replay-not-saved = échec de l'enregistrement de la rediffusion : { $error }
+no-replay-buffer = pas de tampon de rediffusion (voir --replay-buffer)
+
+## Presets
+preset-none = aucun
+preset-saved = { $preset } enregistré
+preset-not-saved = { $preset } non enregistré ({ $error })
+preset-empty = { $slot } est vide
+
+## Debug overlay
+on = activé
+off = désactivé
+overlay-density = Densité :
+overlay-max-stack-height = Hauteur max. des piles :
+overlay-speed = Vitesse :
+overlay-update-delay = Délai de mise à jour (ms) : { $delay }
+overlay-target-fps = IPS visées : { $fps }
+overlay-unlimited = illimitées
+overlay-updates = Mises à jour/s : { $rate } ({ $minute } sur 1 min)
+overlay-glyphs = Glyphes/s : { $rate } ({ $minute } sur 1 min)
+overlay-glyphs-per-update = Glyphes/mise à jour : { $glyphs }
+overlay-stacks-per-update = Piles/mise à jour : { $stacks }
+overlay-stack-delay = Délai min./max. des piles (ms) : { $min }/{ $max }
+overlay-stack-pool = Réserve de piles : { $pooled } en réserve, { $reused } % des apparitions réutilisées
+overlay-key-heat = Chaleur des touches : { $heat }
+overlay-progress = Progression : { $progress }
+overlay-coverage = Couverture : { $coverage } % ({ $trend } %/s)
+overlay-target-coverage = Couverture visée : { $target }
+overlay-spawn-rate = { $target } % (taux d'apparition x{ $scale })
+overlay-fast-forward = Avance rapide : { $state }
+overlay-effect = Effet : { $effect }
+overlay-pipeline = Chaîne : { $stages }
+overlay-preset = Préréglage : { $preset }
+overlay-mutation-rate = Taux de mutation : { $rate }
+overlay-zoom = Zoom : { $zoom }
+overlay-camera = Ligne de la caméra : { $row }/{ $rows }
+overlay-spotlight = Projecteur : { $spotlight }
+overlay-spotlight-column = colonne { $column }
+overlay-terminal = Terminal : { $terminal }
+overlay-seed = Graine : { $seed }
+overlay-frame-hash = Empreinte de l'image : { $hash }
+overlay-full-repaints = Redessins complets : { $repaints }
+
+## Frame time budget
+phase-input = Entrée
+phase-simulate = Simulation
+phase-compose = Composition
+phase-write = Écriture
+budget-phase = { $phase } (ms/image) : { $time } ({ $share } %)
+budget-fps = Images/s : { $fps }
+budget-written = Écrit (Kio/s) : { $rate }
+stress-start = Test de charge de la simulation en { $width }x{ $height } pendant { $seconds } s avec la graine { $seed } (passez --terminal pour tester le terminal)
+stress-second = { $budget } ({ $changes } changements/s{ $allocations }, { $coverage } % de couverture, { $reused } % des piles réutilisées)
+stress-allocations = , { $allocations } allocations/s
+
+## Validating the config
+validate-ok = { $path } : aucun problème trouvé
+validate-problem = 1 problème dans { $path }
+validate-problems = { $count } problèmes dans { $path }
+
+## Tuning
+tune-result = { $terminal } réglé : { $fps } images/s ({ $rate } Kio/s), couleurs vraies : { $truecolor }, synchronisation : { $sync }
+tune-yes = oui
+tune-no = non
+tune-unknown = sans réponse
+tune-would-save = Enregistrerait : { $settings }
+tune-saved = Enregistré dans { $path } : { $settings }
+
+## Setup wizard
+wizard-title = Configuration de falling_glyphs
+wizard-step-title = Configuration de falling_glyphs ({ $step }/{ $steps })
+wizard-welcome = Configurer falling_glyphs maintenant ? Vos choix deviendront le profil par défaut de son fichier de configuration.
+wizard-set-up = Configurer maintenant
+wizard-skip = Passer, et ne plus demander
+wizard-hint = Haut/Bas pour choisir, Entrée pour confirmer, Échap pour passer la configuration
+wizard-no = Non
+wizard-yes = Oui
+wizard-weather = Quelle intensité pour la pluie ?
+wizard-drizzle = Bruine légère
+wizard-steady = Régulière (par défaut)
+wizard-downpour = Averse
+wizard-storm = Orage, avec des éclairs
+wizard-effect = Quel effet afficher ?
+wizard-rain = Pluie qui tombe
+wizard-vortex = Un vortex en spirale vers le centre
+wizard-helix = Une double hélice
+wizard-waterfall = Une cascade sur des collines
+wizard-sheet = Pluie tombant d'un seul rideau
+wizard-theme = De quelles couleurs doit être la pluie ?
+wizard-classic = Verts classiques
+wizard-deuteranopia = Bleus, pour la deutéranopie
+wizard-protanopia = Jaunes, pour la protanopie
+wizard-tritanopia = Rouges, pour la tritanopie
+wizard-high-contrast = Utiliser un contraste élevé ? (têtes en gras sur des traînées vives qui ne s'estompent pas)
+wizard-smooth = Fondre les glyphes en douceur entre les lignes ? (idéal sur les terminaux en couleurs vraies)
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+
+// The catalogs built in, by language. English has every message, and the
+// others fall back on it for any they don't have.
+const CATALOGS: [(&str, &str); 3] = [
+    ("en", include_str!("en.ftl")),
+    ("es", include_str!("es.ftl")),
+    ("fr", include_str!("fr.ftl")),
+];
+
+// The variables gettext picks the language of messages from, in the order
+// it reads them
+const LOCALE_VARS: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+
+// The text shown on screen (captions, the debug overlay, the setup wizard
+// and what subcommands print), in one language. The rain's glyphs aren't
+// translated.
+pub struct Messages {
+    messages: HashMap<&'static str, &'static str>,
+    english: HashMap<&'static str, &'static str>,
+}
+
+impl Messages {
+    // The messages in the language of a locale like `fr` or `es_ES.UTF-8`,
+    // or in English if there's no catalog for it
+    pub fn new(locale: &str) -> Self {
+        let language = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default();
+        let catalog = CATALOGS
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(language))
+            .map_or("", |(_, catalog)| catalog);
+        Self {
+            messages: parse(catalog),
+            english: parse(CATALOGS[0].1),
+        }
+    }
+
+    // The messages in the language asked for (with `--language`), or else
+    // the locale's
+    pub fn for_language(language: Option<&str>) -> Self {
+        let locale = language.map(str::to_string).or_else(|| {
+            LOCALE_VARS
+                .iter()
+                .filter_map(|var| env::var(var).ok())
+                .find(|locale| !locale.is_empty())
+        });
+        Self::new(&locale.unwrap_or_default())
+    }
+
+    pub fn get(&self, id: &str) -> String {
+        self.format(id, &[])
+    }
+
+    // The message, with each `{ $name }` in it filled in with the argument of
+    // that name. Messages missing from every catalog show as their ids.
+    pub fn format(&self, id: &str, args: &[(&str, &dyn Display)]) -> String {
+        let Some(message) = self.messages.get(id).or_else(|| self.english.get(id)) else {
+            return id.to_string();
+        };
+        let mut text = message.to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{ ${} }}", name), &value.to_string());
+        }
+        text
+    }
+}
+
+// Reads a catalog of `id = message` lines, in the style of Fluent's, passing
+// over blank lines and `#` comments
+fn parse(catalog: &'static str) -> HashMap<&'static str, &'static str> {
+    catalog
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(" = "))
+        .map(|(id, message)| (id.trim(), message.trim()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The names of the arguments a message takes
+    fn arguments(message: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = message
+            .split("{ $")
+            .skip(1)
+            .filter_map(|rest| rest.split_once(" }").map(|(name, _)| name))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn catalogs_only_translate_english_messages_with_the_same_arguments() {
+        let english = parse(CATALOGS[0].1);
+        for (language, catalog) in CATALOGS {
+            for (id, message) in parse(catalog) {
+                let Some(original) = english.get(id) else {
+                    panic!(
+                        "`{}` in the {} catalog isn't an English message",
+                        id, language
+                    );
+                };
+                assert_eq!(
+                    arguments(message),
+                    arguments(original),
+                    "{} in {}",
+                    id,
+                    language
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn locales_pick_their_language_falling_back_on_english() {
+        let spanish = Messages::new("es_ES.UTF-8");
+        assert_eq!(spanish.get("config-reloaded"), "configuración recargada");
+        assert_eq!(Messages::new("FR").get("wizard-yes"), "Oui");
+        for locale in ["", "C", "POSIX", "xx_XX"] {
+            assert_eq!(
+                Messages::new(locale).get("config-reloaded"),
+                "config reloaded"
+            );
+        }
+
+        let partial = Messages {
+            messages: parse("greeting = hola { $name }"),
+            english: parse("greeting = hello { $name }\nfarewell = bye"),
+        };
+        assert_eq!(partial.format("greeting", &[("name", &"Ana")]), "hola Ana");
+        assert_eq!(partial.get("farewell"), "bye");
+        assert_eq!(partial.get("missing"), "missing");
+    }
+}
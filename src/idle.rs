@@ -0,0 +1,93 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// How often the watched terminal is checked for input
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// When the terminal last had input read from it. The kernel bumps a tty's
+// access time as programs read it (to within 8 seconds), so keys are seen
+// without being taken from the shell waiting for them.
+fn last_input(tty: &Path) -> io::Result<SystemTime> {
+    fs::metadata(tty)?.accessed()
+}
+
+// Fields of a process's /proc stat after its command name, on Linux
+fn proc_stat(pid: &str) -> Option<Vec<String>> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let fields = stat.rsplit_once(')')?.1.split_whitespace();
+    Some(fields.map(str::to_string).collect())
+}
+
+// Our parent (the shell that started us in the background), if /proc says
+fn parent_shell() -> Option<String> {
+    // State, then parent
+    proc_stat("self")?.get(1).cloned()
+}
+
+// Whether the shell is in the foreground of its terminal (waiting at its
+// prompt) rather than running a program, if /proc says
+fn shell_in_foreground(shell: &str) -> Option<bool> {
+    let fields = proc_stat(shell)?;
+    // State, parent, process group, session, terminal, foreground group
+    Some(fields.get(2)? == fields.get(5)?)
+}
+
+// Watches a terminal for input, for ending the rain on a key press that's
+// left for the shell to read
+pub struct ActivityMonitor {
+    tty: PathBuf,
+    since: SystemTime,
+}
+
+impl ActivityMonitor {
+    pub fn new(tty: &Path) -> io::Result<Self> {
+        Ok(Self {
+            tty: tty.to_path_buf(),
+            since: last_input(tty)?,
+        })
+    }
+
+    // Whether the terminal has read input since watching started, or gone away
+    pub fn seen(&self) -> bool {
+        last_input(&self.tty).map_or(true, |last| last > self.since)
+    }
+}
+
+// Waits in the background for the terminal to sit idle at the shell's
+// prompt, then runs the rain over it (as this program with the same options)
+// until a key is pressed, and goes back to waiting. Ends once the terminal
+// or its shell goes away.
+pub fn watch(tty: &Path, idle_after: Duration) -> io::Result<()> {
+    let shell = parent_shell();
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let Ok(last) = last_input(tty) else {
+            return Ok(());
+        };
+        if shell.is_some() && parent_shell() != shell {
+            return Ok(());
+        }
+
+        // Programs the shell is running are left alone, however long they idle
+        if shell.as_deref().and_then(shell_in_foreground) == Some(false) {
+            continue;
+        }
+        if SystemTime::now().duration_since(last).unwrap_or_default() < idle_after {
+            continue;
+        }
+
+        let status = Command::new(env::current_exe()?)
+            .args(env::args_os().skip(1))
+            .arg("--until-activity")
+            .arg(tty)
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("the rain exited with {}", status)));
+        }
+    }
+}
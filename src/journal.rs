@@ -0,0 +1,86 @@
+use std::io::{self, BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use falling_glyphs::game::AnsiColor;
+
+// How serious a journal entry is, from its syslog priority
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    const ALL: [Severity; 3] = [Severity::Error, Severity::Warning, Severity::Info];
+
+    // The syslog priorities (emerg = 0 to debug = 7) of the severity, as a
+    // journalctl --priority range
+    fn priorities(self) -> &'static str {
+        match self {
+            Severity::Error => "0..3",
+            Severity::Warning => "4..4",
+            Severity::Info => "5..7",
+        }
+    }
+
+    pub fn color(self) -> AnsiColor {
+        match self {
+            Severity::Error => AnsiColor::Rgb(255, 64, 64),
+            Severity::Warning => AnsiColor::Rgb(255, 200, 0),
+            Severity::Info => AnsiColor::Green,
+        }
+    }
+}
+
+// Follows the systemd journal, sending the severity of each new entry. A
+// journalctl follows each severity's priorities, as entries printed without
+// metadata don't say which they have. They're killed when this is dropped.
+pub struct JournalFollower {
+    children: Vec<Child>,
+    pub entries: Receiver<Severity>,
+}
+
+impl JournalFollower {
+    pub fn spawn() -> io::Result<Self> {
+        let (sender, entries) = mpsc::channel();
+        let mut follower = Self {
+            children: Vec::new(),
+            entries,
+        };
+        for severity in Severity::ALL {
+            let mut child = Command::new("journalctl")
+                .args(["--follow", "--lines=0", "--output=cat"])
+                .arg(format!("--priority={}", severity.priorities()))
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()?;
+            let Some(stdout) = child.stdout.take() else {
+                continue;
+            };
+            follower.children.push(child);
+
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for line in BufReader::new(stdout).lines() {
+                    if line.is_err() || sender.send(severity).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        Ok(follower)
+    }
+}
+
+impl Drop for JournalFollower {
+    fn drop(&mut self) {
+        for child in &mut self.children {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
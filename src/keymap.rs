@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use falling_glyphs::effects::EffectKind;
+use serde::Deserialize;
+
+use crate::config::PRESET_SLOTS;
+
+// Shifted number keys save presets, in slot order
+const SHIFTED_DIGITS: &str = "!@#$%^&*(";
+
+// Something a key can be bound to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    IncreaseDensity,
+    DecreaseDensity,
+    IncreaseStackHeight,
+    DecreaseStackHeight,
+    IncreaseSpeed,
+    DecreaseSpeed,
+    IncreaseMutation,
+    DecreaseMutation,
+    PanUp,
+    PanDown,
+    PageUp,
+    PageDown,
+    PanTop,
+    PanBottom,
+    ZoomIn,
+    ZoomOut,
+    ToggleHeatMap,
+    ToggleFastForward,
+    TogglePause,
+    Step,
+    IncreaseFps,
+    DecreaseFps,
+    CycleMirror,
+    CycleEffect,
+    CycleCharset,
+    CycleTheme,
+    IncreaseBrightness,
+    DecreaseBrightness,
+    StrikeLightning,
+    ToggleSpotlight,
+    SpotlightLeft,
+    SpotlightRight,
+    ToggleDebug,
+    Repaint,
+    SaveReplay,
+    Suspend,
+    // Preset slots are numbered 1 to 9
+    SavePreset(u8),
+    RecallPreset(u8),
+    // Unbinds the key, so it does nothing (even if bound outside the effect)
+    None,
+}
+
+// A key, with whether Ctrl is held. Shift is part of the character itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    code: KeyCode,
+    ctrl: bool,
+}
+
+impl KeyBinding {
+    pub fn from_event(event: &KeyEvent) -> Self {
+        Self {
+            code: event.code,
+            ctrl: event.modifiers.contains(KeyModifiers::CONTROL),
+        }
+    }
+
+    // Parses keys like `d`, `D`, `+`, `pageup` or `ctrl-c`
+    pub fn parse(key: &str) -> Result<Self, String> {
+        let (ctrl, name) = match key.to_ascii_lowercase().strip_prefix("ctrl-") {
+            Some(_) => (true, &key[5..]),
+            None => (false, key),
+        };
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match name.to_ascii_lowercase().as_str() {
+                "esc" => KeyCode::Esc,
+                "enter" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                _ => return Err(format!("unknown key `{}`", key)),
+            },
+        };
+        Ok(Self { code, ctrl })
+    }
+}
+
+// Key bindings, with extra maps layered over them while particular effects run
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    global: HashMap<KeyBinding, Action>,
+    effects: HashMap<EffectKind, HashMap<KeyBinding, Action>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut global: HashMap<KeyBinding, Action> = [
+            ("q", Action::Quit),
+            ("esc", Action::Quit),
+            ("ctrl-c", Action::Quit),
+            ("ctrl-z", Action::Suspend),
+            ("d", Action::IncreaseDensity),
+            ("D", Action::DecreaseDensity),
+            ("h", Action::IncreaseStackHeight),
+            ("H", Action::DecreaseStackHeight),
+            ("s", Action::IncreaseSpeed),
+            ("S", Action::DecreaseSpeed),
+            ("up", Action::PanUp),
+            ("down", Action::PanDown),
+            ("pageup", Action::PageUp),
+            ("pagedown", Action::PageDown),
+            ("home", Action::PanTop),
+            ("end", Action::PanBottom),
+            ("+", Action::ZoomIn),
+            ("=", Action::ZoomIn),
+            ("-", Action::ZoomOut),
+            ("k", Action::ToggleHeatMap),
+            ("f", Action::ToggleFastForward),
+            ("p", Action::TogglePause),
+            (".", Action::Step),
+            (">", Action::IncreaseFps),
+            ("<", Action::DecreaseFps),
+            ("m", Action::CycleMirror),
+            ("e", Action::CycleEffect),
+            ("c", Action::CycleCharset),
+            ("t", Action::CycleTheme),
+            ("b", Action::IncreaseBrightness),
+            ("B", Action::DecreaseBrightness),
+            ("L", Action::StrikeLightning),
+            ("l", Action::ToggleSpotlight),
+            ("[", Action::SpotlightLeft),
+            ("]", Action::SpotlightRight),
+            ("?", Action::ToggleDebug),
+            ("r", Action::Repaint),
+            ("R", Action::SaveReplay),
+        ]
+        .into_iter()
+        .map(|(key, action)| (KeyBinding::parse(key).unwrap(), action))
+        .collect();
+
+        // Number keys recall presets, and with Shift save them
+        for (slot, shifted) in PRESET_SLOTS.zip(SHIFTED_DIGITS.chars()) {
+            let recall = KeyBinding::parse(&slot.to_string()).unwrap();
+            let save = KeyBinding::parse(&shifted.to_string()).unwrap();
+            global.insert(recall, Action::RecallPreset(slot));
+            global.insert(save, Action::SavePreset(slot));
+        }
+
+        // The waterfall pools glyphs for a long time, so its keys tune how much they churn
+        let waterfall = [
+            ("u", Action::IncreaseMutation),
+            ("U", Action::DecreaseMutation),
+        ]
+        .into_iter()
+        .map(|(key, action)| (KeyBinding::parse(key).unwrap(), action))
+        .collect();
+
+        Self {
+            global,
+            effects: HashMap::from([(EffectKind::Waterfall, waterfall)]),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn bind(&mut self, effect: Option<EffectKind>, key: KeyBinding, action: Action) {
+        match effect {
+            Some(effect) => self.effects.entry(effect).or_default().insert(key, action),
+            None => self.global.insert(key, action),
+        };
+    }
+
+    // The running effect's bindings take precedence over the global ones
+    pub fn action(&self, effect: EffectKind, event: &KeyEvent) -> Option<Action> {
+        let key = KeyBinding::from_event(event);
+        self.effects
+            .get(&effect)
+            .and_then(|keys| keys.get(&key))
+            .or_else(|| self.global.get(&key))
+            .copied()
+            .filter(|action| *action != Action::None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_parse_from_characters_names_and_ctrl() {
+        let key = |code, ctrl| KeyBinding { code, ctrl };
+        let parsed = |text| KeyBinding::parse(text).unwrap();
+        assert_eq!(parsed("D"), key(KeyCode::Char('D'), false));
+        assert_eq!(parsed("+"), key(KeyCode::Char('+'), false));
+        assert_eq!(parsed("PageUp"), key(KeyCode::PageUp, false));
+        assert_eq!(parsed("space"), key(KeyCode::Char(' '), false));
+        assert_eq!(parsed("ctrl-c"), key(KeyCode::Char('c'), true));
+        assert_eq!(parsed("Ctrl-Up"), key(KeyCode::Up, true));
+        assert!(KeyBinding::parse("ctrl-").is_err());
+        assert!(KeyBinding::parse("f13").is_err());
+    }
+}
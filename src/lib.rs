@@ -0,0 +1,40 @@
+//! A falling matrix of green glyphs.
+//!
+//! [`game::Game`] runs the simulation independently of any terminal: feed it
+//! the screen size, call [`game::Game::update_and_get_changes`] once per
+//! frame with the time since the last, and draw the [`game::Change`]s it
+//! returns. The game never reads the clock itself, so a fixed time step
+//! replays a seeded run exactly.
+
+pub mod balancer;
+pub mod banner;
+pub mod caption;
+pub mod charset;
+pub mod chime;
+pub mod compositor;
+pub mod effects;
+pub mod embers;
+pub mod events;
+pub mod font;
+pub mod game;
+pub mod gradient;
+pub mod heatmap;
+pub mod lifecycle;
+pub mod lightning;
+pub mod mask;
+pub mod mirror;
+pub mod noise;
+pub mod origin;
+pub mod pattern;
+mod pool;
+pub mod reserved;
+pub mod rng;
+pub mod scenario;
+pub mod telemetry;
+pub mod theme;
+pub mod title;
+#[cfg(feature = "vt")]
+pub mod vt;
+pub mod weather;
+pub mod wind;
+pub mod zoom;
@@ -0,0 +1,54 @@
+use crate::game::AnsiColor;
+
+// Where a glyph stack is in its life, from spawning at the edge of the canvas
+// to draining off the far side
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StackPhase {
+    // Growing towards its full length
+    #[default]
+    Spawning,
+    // At full length, with its head on the canvas
+    Falling,
+    // Its head has left the far side of the canvas, and its tail follows
+    Draining,
+    // Its head has left the far side of the canvas, and what's left of its
+    // tail flickers and fades out in place (see Game::set_dissolve)
+    Dissolving,
+    // Entirely off the canvas, waiting to be removed
+    Dead,
+}
+
+impl StackPhase {
+    // Works out the phase of a stack from its extent and length, given the
+    // distance it travels before leaving the canvas
+    pub fn next(self, min_y: i16, max_y: i16, len: usize, length: u16, travel: i16) -> Self {
+        if min_y >= travel {
+            StackPhase::Dead
+        } else if max_y >= travel {
+            StackPhase::Draining
+        } else if self == StackPhase::Spawning && len < length as usize {
+            StackPhase::Spawning
+        } else {
+            StackPhase::Falling
+        }
+    }
+}
+
+// A hook for styling the glyphs of a stack differently in each phase
+pub trait PhaseStyle {
+    // Color of the glyph `index` places behind the head of a stack `len` glyphs long
+    fn style(&self, phase: StackPhase, index: usize, len: usize, color: AnsiColor) -> AnsiColor;
+}
+
+// Draining stacks dim the back two thirds of their tail a step, so they
+// fade out faster as they leave the screen
+pub struct DefaultPhaseStyle;
+
+impl PhaseStyle for DefaultPhaseStyle {
+    fn style(&self, phase: StackPhase, index: usize, len: usize, color: AnsiColor) -> AnsiColor {
+        match phase {
+            StackPhase::Draining if index > len / 3 => color.shifted(-1),
+            _ => color,
+        }
+    }
+}
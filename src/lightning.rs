@@ -0,0 +1,149 @@
+use clap::ValueEnum;
+use rand::Rng;
+
+use crate::game::{display_width, AnsiColor, Attributes, Cell, Rect, Viewport};
+
+// Glyphs drawn into empty cells while a flash lights them up
+const BRIGHT_FILL: char = '░';
+const INVERTED_FILL: char = '█';
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum FlashStyle {
+    // Every cell renders at maximum brightness
+    #[default]
+    Bright,
+    // Bright cells go dark and dark cells go bright
+    Inverted,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum FlashRegion {
+    // Flashes cover the whole screen
+    #[default]
+    Full,
+    // Flashes cover a random rectangle of the screen
+    Random,
+}
+
+// A brightness override applied to a region of the composed viewport
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BrightnessOverride {
+    pub region: Rect,
+    pub style: FlashStyle,
+}
+
+impl BrightnessOverride {
+    // Rewrites every cell inside the region, skipping the right half of
+    // wide glyphs so they aren't clobbered.
+    pub fn apply(&self, view: &Viewport) -> Viewport {
+        let mut result = view.clone();
+        for y in self.region.y..self.region.bottom().min(view.height()) {
+            for x in self.region.x..self.region.right().min(view.width()) {
+                let covered = x > 0 && view.get(x - 1, y).is_some_and(|c| display_width(c.ch) > 1);
+                let cell = match view.get(x, y) {
+                    Some(cell) => Cell {
+                        color: match self.style {
+                            FlashStyle::Bright => AnsiColor::White,
+                            FlashStyle::Inverted => cell.color.inverted(),
+                        },
+                        ..cell
+                    },
+                    None if covered => continue,
+                    None => Cell {
+                        ch: match self.style {
+                            FlashStyle::Bright => BRIGHT_FILL,
+                            FlashStyle::Inverted => INVERTED_FILL,
+                        },
+                        color: AnsiColor::White,
+                        background: None,
+                        attributes: Attributes::default(),
+                    },
+                };
+                result.set(x, y, cell);
+            }
+        }
+        result
+    }
+}
+
+// Occasional lightning flashes, lasting one or two frames
+#[derive(Clone, Debug, Default)]
+pub struct Lightning {
+    pub chance: f64, // chance of a strike per base tick
+    pub style: FlashStyle,
+    pub region: FlashRegion,
+    active: Option<BrightnessOverride>,
+    frames_left: u8,
+}
+
+impl Lightning {
+    pub fn strike<R: Rng>(&mut self, rng: &mut R, width: u16, height: u16) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let region = match self.region {
+            FlashRegion::Full => Rect::new(0, 0, width, height),
+            FlashRegion::Random => {
+                let w = rng.random_range(width / 4..=width).max(1);
+                let h = rng.random_range(height / 4..=height).max(1);
+                Rect::new(
+                    rng.random_range(0..=width - w),
+                    rng.random_range(0..=height - h),
+                    w,
+                    h,
+                )
+            }
+        };
+        self.active = Some(BrightnessOverride {
+            region,
+            style: self.style,
+        });
+        self.frames_left = rng.random_range(1..=2);
+    }
+
+    // Advances any flash in progress and rolls for a new strike over a time
+    // step of the given number of base ticks, returning the override to
+    // apply to this frame.
+    pub fn update<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        width: u16,
+        height: u16,
+        ticks: f64,
+    ) -> Option<BrightnessOverride> {
+        if self.frames_left > 0 {
+            self.frames_left -= 1;
+        } else if self.chance > 0.0 && rng.random_bool((self.chance * ticks).min(1.0)) {
+            self.strike(rng, width, height);
+        }
+        if self.frames_left == 0 {
+            self.active = None;
+        }
+        self.active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flashes_fill_empty_cells_but_the_right_halves_of_wide_glyphs() {
+        let glyph = |ch| Cell {
+            ch,
+            color: AnsiColor::Green,
+            background: None,
+            attributes: Attributes::default(),
+        };
+        let mut view = Viewport::new(4, 1);
+        view.set(0, 0, glyph('a'));
+        view.set(2, 0, glyph('日'));
+        let flash = BrightnessOverride {
+            region: Rect::new(0, 0, 4, 1),
+            style: FlashStyle::Bright,
+        };
+        let flashed = flash.apply(&view);
+        assert_eq!(flashed.get(1, 0).map(|cell| cell.ch), Some(BRIGHT_FILL));
+        assert_eq!(flashed.get(3, 0), None);
+    }
+}
@@ -1,29 +1,116 @@
 use crossterm::{
-    cursor::{Hide, MoveTo, Show},
-    event::{self, Event, KeyCode, KeyModifiers},
-    style::{Color, Print, SetForegroundColor},
-    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
+    cursor::{self, Hide, MoveTo, Show},
+    event::{Event, KeyCode},
+    style::{Attribute, Color, Print, SetAttribute},
+    terminal::{
+        self, BeginSynchronizedUpdate, Clear, ClearType, EndSynchronizedUpdate, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+    ExecutableCommand, QueueableCommand,
 };
-use std::io::{self, stdout, Write};
-use std::time::{Duration, Instant};
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, stdout, IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::TryRecvError;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-mod game;
-use game::{Change, DebugInfo, Game};
+mod cli;
+mod commands;
+#[cfg(feature = "vt")]
+mod compare;
+mod config;
+mod damage;
+#[cfg(feature = "vt")]
+mod export;
+mod gitwatch;
+mod i18n;
+mod idle;
+#[cfg(feature = "journal")]
+mod journal;
+mod keymap;
+mod metrics;
+mod notify;
+mod pacer;
+mod palette;
+mod reload;
+mod renderer;
+mod replay;
+mod schedule;
+#[cfg(feature = "vt")]
+mod selftest;
+mod session;
+mod stress;
+mod terminal_env;
+mod tune;
+mod wizard;
+use clap::{CommandFactory, Parser, ValueEnum};
+use cli::{Backend, Cli, ListArgs, Listing, Mode};
+use commands::Command;
+use config::{Config, Preset, DEFAULT_PROFILE};
+use damage::DamageTracker;
+use falling_glyphs::banner::Banner;
+use falling_glyphs::caption::Caption;
+use falling_glyphs::charset::{Charset, GlyphSet};
+use falling_glyphs::effects::EffectKind;
+use falling_glyphs::events::SimEvent;
+use falling_glyphs::game::{Attributes, Change, Game, Rect, Settings, BASE_TICK};
+use falling_glyphs::mask::Mask;
+use falling_glyphs::telemetry::{Telemetry, Window};
+use falling_glyphs::theme::Theme;
+use falling_glyphs::title::TitleCard;
+use falling_glyphs::weather::Weather;
+use i18n::Messages;
+use idle::ActivityMonitor;
+use keymap::{Action, Keymap};
+use metrics::MetricsSink;
+use pacer::FramePacer;
+use palette::{ColorDepth, SetBackground, SetForeground};
+use reload::ConfigWatcher;
+use renderer::Renderer;
+use replay::ReplayBuffer;
+use stress::{FrameBudget, Phase};
+use terminal_env::TerminalEnv;
 
-fn convert_color(ansi_color: game::AnsiColor) -> Color {
-    match ansi_color {
-        game::AnsiColor::White => Color::White,
-        game::AnsiColor::Green => Color::Green,
-        game::AnsiColor::DarkGreen => Color::DarkGreen,
-    }
+const TICK_RATE: Duration = BASE_TICK;
+
+// Tick rate when interpolating between steps, so the in-between frames are seen
+const SMOOTH_TICK_RATE: Duration = Duration::from_millis(16);
+
+// Most time a seeded run catches up on at once, e.g. after being suspended
+const MAX_CATCH_UP: Duration = Duration::from_secs(1);
+
+// How long the size has to stay the same before the screen is resized to it,
+// so dragging a window's edge resizes it once, to where it's let go
+const RESIZE_SETTLE: Duration = Duration::from_millis(80);
+
+// The SGR attribute setting each text attribute a glyph can have
+const TEXT_ATTRIBUTES: [(Attributes, Attribute); 3] = [
+    (Attributes::BOLD, Attribute::Bold),
+    (Attributes::DIM, Attribute::Dim),
+    (Attributes::ITALIC, Attribute::Italic),
+];
+
+// Scrolls rows top..bottom of the screen down, by setting them as the scroll
+// region and reverse indexing at its top, then resets the region
+fn queue_scroll(frame: &mut Vec<u8>, top: u16, bottom: u16, rows: u16) -> io::Result<()> {
+    frame
+        .queue(Print(format!("\x1b[{};{}r", top + 1, bottom)))?
+        .queue(MoveTo(0, top))?
+        .queue(Print("\x1bM".repeat(rows as usize)))?
+        .queue(Print("\x1b[r"))?;
+    Ok(())
 }
 
 fn render_debug_info(
-    stdout: &mut io::Stdout,
-    debug_info: &DebugInfo,
+    frame: &mut Vec<u8>,
+    telemetry: &Telemetry,
     width: u16,
     perf_lines: &[String],
+    colors: ColorDepth,
+    messages: &Messages,
 ) -> io::Result<u16> {
     let mut lines = Vec::new();
 
@@ -35,7 +122,15 @@ fn render_debug_info(
     };
 
     // --- Configurable Settings with Bars ---
-    let label_width = 22;
+    let labels = [
+        messages.get("overlay-density"),
+        messages.get("overlay-max-stack-height"),
+        messages.get("overlay-speed"),
+    ];
+    // Room for the longest label, which is longer in some languages
+    let longest = labels.iter().map(|label| label.chars().count()).max();
+    let label_width = longest.map_or(22, |longest| (longest + 1).max(22));
+    let [density_label, height_label, speed_label] = labels;
     let value_width = 6;
     let bar_padding = 2; // for "  " around the bar
     let bar_width = if width > (label_width + value_width + bar_padding) as u16 {
@@ -47,40 +142,41 @@ fn render_debug_info(
     // Add settings lines to a temporary vector to be rendered with colors
     let mut settings_lines = Vec::new();
     // Density
-    let density_percent = (debug_info.density - 0.1) / 0.9;
+    let density_percent = (telemetry.density() - 0.1) / 0.9;
     settings_lines.push((
-        "Density:",
+        density_label,
         create_bar(density_percent, bar_width),
-        format!("{:.1}", debug_info.density),
+        format!("{:.1}", telemetry.density()),
         Color::Green,
     ));
     // Max Stack Height
-    let height_percent = (debug_info.max_stack_height - 0.1) / 0.9;
+    let height_percent = (telemetry.max_stack_height() - 0.1) / 0.9;
     settings_lines.push((
-        "Max Stack Height:",
+        height_label,
         create_bar(height_percent, bar_width),
-        format!("{:.1}", debug_info.max_stack_height),
+        format!("{:.1}", telemetry.max_stack_height()),
         Color::Yellow,
     ));
     // Speed
-    let speed_percent = (debug_info.speed as f64 - 1.0) / 49.0;
+    let speed_percent = (telemetry.speed() as f64 - 1.0) / 49.0;
     settings_lines.push((
-        "Speed Level:",
+        speed_label,
         create_bar(speed_percent, bar_width),
-        debug_info.speed.to_string(),
+        telemetry.speed().to_string(),
         Color::Blue,
     ));
 
     // --- Render all lines ---
-    stdout.execute(SetForegroundColor(Color::White))?;
+    frame.queue(SetForeground(Color::White, colors))?;
     for (i, (label, bar, value, color)) in settings_lines.iter().enumerate() {
-        stdout
-            .execute(MoveTo(0, i as u16))?
-            .execute(Print(format!("{:<label_width$}", label, label_width = label_width)))?
-            .execute(SetForegroundColor(*color))?
-            .execute(Print(&bar))?
-            .execute(SetForegroundColor(Color::White))?
-            .execute(Print(format!(" {:>value_width$}", value, value_width = value_width)))?;
+        frame
+            .queue(MoveTo(0, i as u16))?
+            .queue(Print(format!("{:<label_width$}", label, label_width = label_width)))?
+            .queue(SetForeground(*color, colors))?
+            .queue(Print(&bar))?
+            .queue(SetForeground(Color::White, colors))?
+            .queue(Print(format!(" {:>value_width$}", value, value_width = value_width)))?
+            .queue(Clear(ClearType::UntilNewLine))?;
     }
     lines.extend(vec!["".to_string(); settings_lines.len()]);
 
@@ -89,84 +185,1027 @@ fn render_debug_info(
 
     // --- Render performance lines ---
     let base_y = settings_lines.len() as u16 + 1;
+    frame
+        .queue(MoveTo(0, base_y - 1))?
+        .queue(Clear(ClearType::CurrentLine))?;
     for (i, line) in perf_lines.iter().enumerate() {
-        stdout
-            .execute(MoveTo(0, base_y + i as u16))?
-            .execute(Print(format!("{:<width$}", line, width = width as usize)))?;
+        frame
+            .queue(MoveTo(0, base_y + i as u16))?
+            .queue(Print(format!("{:<width$}", line, width = width as usize)))?;
     }
 
     let num_lines = lines.len() as u16;
     let underscore_line = "_".repeat(width as usize);
-    stdout
-        .execute(MoveTo(0, num_lines))?
-        .execute(Print(underscore_line))?;
+    frame
+        .queue(MoveTo(0, num_lines))?
+        .queue(Print(underscore_line))?;
 
     Ok(num_lines + 1)
 }
 
+// Parses the command line over the given settings (from a profile) and
+// then the environment's
+fn parse_cli(settings: Vec<String>, env_args: &[String]) -> Cli {
+    try_parse_cli(settings, env_args).unwrap_or_else(|e| e.exit())
+}
+
+fn try_parse_cli(settings: Vec<String>, env_args: &[String]) -> Result<Cli, clap::Error> {
+    let mut args: Vec<OsString> = env::args_os().collect();
+    // Each layer gives way to the options set over it, including ones it
+    // can't be combined with
+    let given: Vec<String> = args[1..].iter().map(|a| a.to_string_lossy().into_owned()).collect();
+    let env_args = config::drop_overridden(env_args.to_vec(), &given);
+    let over: Vec<String> = env_args.iter().chain(&given).cloned().collect();
+    let settings = config::drop_overridden(settings, &over);
+    args.splice(1..1, settings.into_iter().chain(env_args).map(OsString::from));
+    let mut cli = Cli::try_parse_from(args)?;
+    // `run` only spells out what runs without a subcommand
+    if let Some(Mode::Run) = cli.mode {
+        cli.mode = None;
+    }
+    Ok(cli)
+}
+
+// The settings from the config file, as arguments for the real ones to be
+// parsed over: a profile's settings over the settings tuned for the
+// terminal, over the ones the terminal can't do without
+fn config_settings(config: &Config, profile: Option<&str>) -> io::Result<Vec<String>> {
+    let tuned = config.terminal_args(&terminal_env::profile_key()).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("invalid tuned settings: {}", e))
+    })?;
+    let mut settings = config::drop_overridden(terminal_env::default_args(), &tuned);
+    settings.extend(tuned);
+    let profile = match profile {
+        Some(name) => Some(name),
+        None => config.has_profile(DEFAULT_PROFILE).then_some(DEFAULT_PROFILE),
+    };
+    if let Some(name) = profile {
+        let profile_args = config.profile_args(name).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("invalid profile: {}", e))
+        })?;
+        settings = config::drop_overridden(settings, &profile_args);
+        settings.extend(profile_args);
+    }
+    Ok(settings)
+}
+
+// The time simulated each frame: a tick at the frame rate asked for, or
+// the default one, no faster than the highest frame rate
+fn tick_rate(cli: &Cli) -> Duration {
+    let tick_rate = match cli.fps {
+        Some(fps) => Duration::from_secs_f64(1.0 / fps),
+        None if cli.smooth => SMOOTH_TICK_RATE,
+        None => TICK_RATE,
+    };
+    match cli.max_fps {
+        Some(max_fps) => tick_rate.max(Duration::from_secs_f64(1.0 / max_fps)),
+        None => tick_rate,
+    }
+}
+
+// Prints the names of the values options take, one kind to a line
+fn list(args: &ListArgs) {
+    fn names<T: ValueEnum>() -> String {
+        let names: Vec<String> = T::value_variants()
+            .iter()
+            .filter_map(|value| value.to_possible_value())
+            .map(|value| value.get_name().to_string())
+            .collect();
+        names.join(", ")
+    }
+    let listings = [
+        (Listing::Charsets, "Charsets", names::<Charset>()),
+        (Listing::Effects, "Effects", names::<EffectKind>()),
+        (Listing::Themes, "Themes", names::<Theme>()),
+        (Listing::Weathers, "Weathers", names::<Weather>()),
+    ];
+    for (listing, title, names) in listings {
+        match args.names {
+            Some(only) if only == listing => println!("{}", names.replace(", ", "\n")),
+            Some(_) => {}
+            None => println!("{}: {}", title, names),
+        }
+    }
+}
+
+// Checks the config file, printing each problem with the line it's on, and
+// fails if there were any
+fn validate_config(path: Option<PathBuf>, messages: &Messages) -> io::Result<()> {
+    let path = path.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "no config directory to find the config file in",
+        )
+    })?;
+    let text = fs::read_to_string(&path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    let problems = config::validate(&text);
+    let lines: Vec<&str> = text.lines().collect();
+    for problem in &problems {
+        println!("{}:{}: {}", path.display(), problem.line, problem.message);
+        if let Some(line) = lines.get(problem.line - 1) {
+            println!("    {}", line.trim_end());
+        }
+    }
+    match problems.len() {
+        0 => {
+            println!(
+                "{}",
+                messages.format("validate-ok", &[("path", &path.display())])
+            );
+            Ok(())
+        }
+        count => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            match count {
+                1 => messages.format("validate-problem", &[("path", &path.display())]),
+                _ => messages.format(
+                    "validate-problems",
+                    &[("count", &count), ("path", &path.display())],
+                ),
+            },
+        )),
+    }
+}
+
+// Makes room for inline rain below the cursor (on the next line, if the
+// cursor's partway along one), scrolling what's above up to leave at least
+// half the screen, and returns the row the rain starts at
+fn make_room_inline(stdout: &mut io::Stdout, height: u16) -> io::Result<u16> {
+    let (column, row) = cursor::position()?;
+    let row = if column > 0 { row + 1 } else { row };
+    let scroll = (row + height.div_ceil(2)).saturating_sub(height);
+    stdout.queue(MoveTo(0, height.saturating_sub(1)))?;
+    for _ in 0..scroll {
+        stdout.queue(Print("\n"))?;
+    }
+    let top = row - scroll;
+    stdout
+        .queue(MoveTo(0, top))?
+        .queue(Clear(ClearType::FromCursorDown))?
+        .flush()?;
+    Ok(top)
+}
+
+// Reloads the config file, applying what it changes of the settings the
+// rain can change as it runs: the density, speed and stack height, effect,
+// charset and colors, key bindings and presets
+fn reload_config(
+    cli: &Cli,
+    env_args: &[String],
+    defaults: Settings,
+    game: &mut Game,
+) -> Result<(Cli, Keymap, HashMap<u8, Preset>), String> {
+    let config = Config::load(cli.config.as_deref()).map_err(|e| e.to_string())?;
+    let keymap = config.keymap().map_err(|e| format!("invalid key binding: {}", e))?;
+    let presets = config.presets().map_err(|e| format!("invalid preset: {}", e))?;
+    let settings = config_settings(&config, cli.profile.as_deref()).map_err(|e| e.to_string())?;
+    let new = try_parse_cli(settings, env_args).map_err(|e| {
+        let message = e.to_string();
+        let line = message.lines().next().unwrap_or_default();
+        line.trim_start_matches("error: ").to_string()
+    })?;
+
+    if new.density != cli.density {
+        game.set_density(new.density.unwrap_or(defaults.density));
+    }
+    if new.speed != cli.speed {
+        game.set_speed(new.speed.unwrap_or(defaults.speed));
+    }
+    if new.max_stack_height != cli.max_stack_height {
+        game.set_max_stack_height(new.max_stack_height.unwrap_or(defaults.max_stack_height));
+    }
+    if new.effect != cli.effect {
+        game.set_effect(new.effect);
+    }
+    if new.charset != cli.charset {
+        game.set_charset(new.charset);
+    }
+    if new.trail_colors != cli.trail_colors {
+        game.set_trail_colors(new.trail_colors.clone());
+    }
+    if new.theme != cli.theme {
+        game.set_theme(new.theme);
+    }
+    if new.color_mode != cli.color_mode {
+        game.set_color_mode(new.color_mode);
+    }
+    if new.light != cli.light || new.no_light != cli.no_light {
+        game.set_light(new.light || (terminal_env::light_background() && !new.no_light));
+    }
+    if new.brightness != cli.brightness {
+        game.set_brightness(new.brightness);
+    }
+    if new.hue_cycle != cli.hue_cycle {
+        game.set_hue_cycle(new.hue_cycle);
+    }
+    if new.high_contrast != cli.high_contrast {
+        game.set_high_contrast(new.high_contrast);
+    }
+    Ok((new, keymap, presets))
+}
+
+// Saves the replay buffer in the current directory, returning a line saying
+// where to or why it couldn't
+fn save_replay(replay: &ReplayBuffer, (width, height): (u16, u16), messages: &Messages) -> String {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let path = PathBuf::from(format!("falling_glyphs-{}.cast", time));
+    match replay.save(&path, width, height) {
+        Ok(length) => messages.format(
+            "replay-saved",
+            &[
+                ("seconds", &format!("{:.0}", length.as_secs_f64())),
+                ("path", &path.display()),
+            ],
+        ),
+        Err(e) => messages.format("replay-not-saved", &[("error", &e)]),
+    }
+}
+
 fn main() -> io::Result<()> {
+    // Options are taken from the command line, then the environment, then
+    // the config file's profile
+    let env_args = config::env_args().map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("invalid environment variable: {}", e))
+    })?;
+    let cli = parse_cli(Vec::new(), &env_args);
+
+    if let Some(Mode::List(args)) = &cli.mode {
+        list(args);
+        return Ok(());
+    }
+    // Completions are generated from the options as they're defined, so
+    // they're never out of date
+    if let Some(Mode::Completions(args)) = &cli.mode {
+        let mut script = Vec::new();
+        clap_complete::generate(args.shell, &mut Cli::command(), "falling_glyphs", &mut script);
+        return stdout().write_all(&script);
+    }
+
+    // Until a profile picks the language, it's the command line's or the locale's
+    let messages = Messages::for_language(cli.language.as_deref());
+    if cli.validate_config {
+        return validate_config(cli.config.clone().or_else(Config::default_path), &messages);
+    }
+
+    // Runs that exit on their own are scripted, so don't stop to ask or
+    // carry settings over from one run to the next
+    let scripted = cli.progress
+        || cli.duration.is_some()
+        || cli.frames.is_some()
+        || cli.watch_idle.is_some()
+        || cli.until_activity.is_some()
+        || cli.mode.is_some();
+
+    // Offer to set things up on the first run, before anything reads the config
+    let first_run = cli.config.is_none()
+        && !scripted
+        && io::stdout().is_terminal()
+        && Config::default_path().is_some_and(|path| !path.exists());
+    if cli.setup || first_run {
+        if let Some(path) = cli.config.clone().or_else(Config::default_path) {
+            wizard::run(&path, &messages)?;
+        }
+    }
+    // The first run also tunes for the terminal, carrying on untuned if it can't
+    if first_run && io::stdin().is_terminal() {
+        if let Some(path) = Config::default_path() {
+            if let Err(e) = tune::run(&path, false, &messages) {
+                eprintln!("falling_glyphs: couldn't tune for the terminal: {}", e);
+            }
+        }
+    }
+    if let Some(Mode::Tune(args)) = &cli.mode {
+        let path = cli.config.clone().or_else(Config::default_path).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no config directory to save settings in")
+        })?;
+        return tune::run(&path, args.dry_run, &messages);
+    }
+    let config = Config::load(cli.config.as_deref())?;
+
+    let settings = config_settings(&config, cli.profile.as_deref())?;
+    let mut cli = match settings.is_empty() {
+        true => cli,
+        false => parse_cli(settings, &env_args),
+    };
+    let mut messages = Messages::for_language(cli.language.as_deref());
+
+    // The watcher runs the rain in a child each time the terminal goes idle,
+    // which ends on activity rather than watching in turn
+    if let (Some(tty), None) = (&cli.watch_idle, &cli.until_activity) {
+        return idle::watch(tty, cli.idle_after);
+    }
+
+    #[cfg(feature = "vt")]
+    match &cli.mode {
+        Some(Mode::Selftest(args)) => return selftest::run(args),
+        Some(Mode::Compare(args)) => return compare::run(args, cli.seed),
+        Some(Mode::Export(args)) => return export::run(args, tick_rate(&cli)),
+        _ => {}
+    }
+
+    // Stressing the simulation alone doesn't need the terminal
+    let stress = match &cli.mode {
+        Some(Mode::Stress(args)) => Some(args.clone()),
+        _ => None,
+    };
+    if let Some(args) = stress.as_ref().filter(|args| !args.terminal) {
+        return stress::run_headless(args, cli.seed, &messages);
+    }
+    let duration = stress.as_ref().and_then(|args| args.duration).or(cli.duration);
+
+    // Load the stencil before taking over the terminal, so errors are readable
+    let mask = match (&cli.stencil, cli.mask) {
+        (Some(path), _) => Some(Mask::load_stencil(path).map_err(|e| {
+            io::Error::new(e.kind(), format!("failed to read stencil {}: {}", path.display(), e))
+        })?),
+        (None, Some(pattern)) => Some(Mask::Pattern {
+            pattern,
+            size: cli.mask_size,
+        }),
+        (None, None) => None,
+    };
+
+    let mut metrics = MetricsSink::new(cli.metrics_interval);
+    if let Some(path) = &cli.metrics {
+        metrics.add_csv(path).map_err(|e| {
+            let message = format!("failed to create metrics file {}: {}", path.display(), e);
+            io::Error::new(e.kind(), message)
+        })?;
+    }
+    #[cfg(feature = "stats")]
+    if let Some(address) = &cli.statsd {
+        metrics.add_statsd(address).map_err(|e| {
+            io::Error::new(e.kind(), format!("failed to reach statsd at {}: {}", address, e))
+        })?;
+    }
+    #[cfg(feature = "stats")]
+    if let Some(path) = &cli.prometheus_textfile {
+        metrics.add_prometheus(path).map_err(|e| {
+            let message = format!("failed to create metrics file {}: {}", path.display(), e);
+            io::Error::new(e.kind(), message)
+        })?;
+    }
+
+    let mut keymap = config
+        .keymap()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("invalid key binding: {}", e)))?;
+
+    let pipeline = match &cli.pipeline {
+        Some(name) => config.pipeline(name).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("invalid pipeline: {}", e))
+        })?,
+        None => Vec::new(),
+    };
+
+    let glyph_set = match (&cli.glyph_weights, &cli.glyph_file, cli.glyph_range.as_slice()) {
+        (Some(name), _, _) => Some(config.glyph_weights(name).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("invalid glyph weights: {}", e))
+        })?),
+        (None, Some(path), _) => {
+            let invalid = |e: String| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid glyph file {}: {}", path.display(), e),
+                )
+            };
+            let text = std::fs::read_to_string(path).map_err(|e| {
+                let message = format!("failed to read glyph file {}: {}", path.display(), e);
+                io::Error::new(e.kind(), message)
+            })?;
+            Some(GlyphSet::from_text(&text).map_err(invalid)?)
+        }
+        (None, None, []) => None,
+        (None, None, ranges) => Some(GlyphSet::from_ranges(ranges).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no glyphs in the glyph ranges")
+        })?),
+    };
+
+    // Presets saved at runtime go into the same config file, even if it doesn't exist yet
+    let mut presets = config
+        .presets()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("invalid preset: {}", e)))?;
+    let config_path = cli.config.clone().or_else(Config::default_path);
+    let mut config_watcher = config_path.clone().map(ConfigWatcher::new);
+    let mut preset_status = messages.get("preset-none");
+
+    let notifier = config
+        .notifier()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("invalid notification: {}", e)))?;
+
+    let git_watcher = match &cli.git_watch {
+        Some(repo) => Some(gitwatch::GitWatcher::spawn(repo.clone()).map_err(|e| {
+            let message = format!("failed to watch {}: {}", repo.display(), e);
+            io::Error::new(e.kind(), message)
+        })?),
+        None => None,
+    };
+
+    #[cfg(feature = "journal")]
+    let journal = match cli.journal {
+        true => Some(journal::JournalFollower::spawn().map_err(|e| {
+            io::Error::new(e.kind(), format!("failed to run journalctl: {}", e))
+        })?),
+        false => None,
+    };
+
+    // The settings the last run left off with take the place of the defaults,
+    // unless the run is to be replayed or is asked to start fresh
+    let session_path = match scripted || cli.seed.is_some() || cli.fresh {
+        true => None,
+        false => session::default_path(),
+    };
+    let defaults = match &session_path {
+        Some(path) => session::load(path).unwrap_or_else(|e| {
+            eprintln!("warning: {}; starting from the defaults", e);
+            None
+        }),
+        None => None,
+    }
+    .unwrap_or_default();
+
+    let mut terminal_env = TerminalEnv::detect();
+    if let Some(colors) = cli.colors {
+        terminal_env.colors = colors;
+    }
+    if cli.no_sync {
+        terminal_env.synchronized_updates = false;
+    }
+
+    // Running from the background, the terminal's modes belong to the shell
+    let activity = match &cli.until_activity {
+        Some(tty) => Some(ActivityMonitor::new(tty).map_err(|e| {
+            io::Error::new(e.kind(), format!("failed to watch {}: {}", tty.display(), e))
+        })?),
+        None => None,
+    };
+
+    terminal_env::enable_escape_codes()?;
     let mut stdout = stdout();
-    stdout.execute(EnterAlternateScreen)?;
+    if !cli.inline {
+        stdout.execute(EnterAlternateScreen)?;
+    }
     stdout.execute(Hide)?;
-    terminal::enable_raw_mode()?;
-    stdout.execute(Clear(ClearType::All))?;
-
+    if activity.is_none() {
+        terminal::enable_raw_mode()?;
+    }
     let (width, height) = terminal::size()?;
-    let mut game = Game::new(width, height);
-    let mut last_debug_state = game.debug;
-    let mut last_debug_lines = 0;
+    // Inline, the rows above the rain are left as they are
+    let inline_top = match cli.inline {
+        true => make_room_inline(&mut stdout, height)?,
+        false => {
+            stdout.execute(Clear(ClearType::All))?;
+            0
+        }
+    };
+
+    let settings = Settings {
+        density: cli.density.unwrap_or(defaults.density),
+        speed: cli.speed.unwrap_or(defaults.speed),
+        max_stack_height: cli.max_stack_height.unwrap_or(defaults.max_stack_height),
+        debug: cli.debug,
+    };
+    let mut game = Game::new(width, height, settings);
+    if let Some(weather) = cli.weather {
+        game.apply_weather(&weather.preset());
+
+        // Settings given explicitly take precedence over the preset's
+        if let Some(density) = cli.density {
+            game.set_density(density);
+        }
+        if let Some(speed) = cli.speed {
+            game.set_speed(speed);
+        }
+        if let Some(max_stack_height) = cli.max_stack_height {
+            game.set_max_stack_height(max_stack_height);
+        }
+    }
+    if let Some(chance) = cli.lightning {
+        game.set_lightning_chance(chance);
+    }
+    game.set_lightning_style(cli.lightning_style);
+    game.set_lightning_region(cli.lightning_region);
+    game.set_mirror(cli.mirror);
+    game.set_mask(mask);
+    game.set_pages(cli.pages);
+    game.set_zoom(cli.zoom);
+    game.set_jitter(cli.jitter);
+    game.set_wind(cli.wind);
+    if let Some(aspect) = cli.cell_aspect {
+        game.set_cell_aspect(aspect);
+    }
+    game.set_normalized_speed(cli.normalize_speed);
+    game.set_dissolve(cli.dissolve);
+    game.set_glow(cli.glow);
+    game.set_head_flash(cli.head_flash.unwrap_or(0), cli.flash_background);
+    game.set_embers(cli.embers);
+    let head_attributes = if cli.high_contrast {
+        cli.head_attributes | Attributes::BOLD
+    } else {
+        cli.head_attributes
+    };
+    game.set_attributes(head_attributes, cli.tail_attributes);
+    game.set_target_coverage(cli.coverage);
+    let mut reserved = cli.reserve.unwrap_or_default();
+    reserved.top = reserved.top.max(inline_top);
+    game.set_reserved(reserved);
+    game.set_trail_colors(cli.trail_colors.clone());
+    game.set_theme(cli.theme);
+    game.set_color_mode(cli.color_mode);
+    game.set_light(cli.light || (terminal_env::light_background() && !cli.no_light));
+    game.set_brightness(cli.brightness);
+    game.set_hue_cycle(cli.hue_cycle);
+    game.set_high_contrast(cli.high_contrast);
+    game.set_spawn_pattern(cli.spawn_pattern.clone());
+    game.set_verify_frames(cli.verify_frames.is_some());
+    let reduce_colors = cli.reduce_colors || (terminal_env::recording() && !cli.no_reduce_colors);
+    game.set_color_threshold(reduce_colors.then_some(cli.color_threshold));
+    game.set_interlace(cli.interlace);
+    game.set_scroll_rendering(cli.backend == Backend::Scroll && terminal_env.scroll_regions);
+    game.set_origin(cli.origin);
+    if let Some(seed) = cli.seed {
+        game.set_seed(seed);
+    }
+    game.set_effect(cli.effect);
+    game.set_charset(cli.charset);
+    if let Some(glyph_set) = glyph_set {
+        game.set_glyph_set(glyph_set);
+    }
+    game.set_mirrored_glyphs(cli.mirrored_glyphs);
+    if !pipeline.is_empty() {
+        game.set_pipeline(&pipeline);
+    }
+    game.set_smooth(cli.smooth);
+    let tick_rate = tick_rate(&cli);
+
+    // Stressing the terminal draws frames as fast as it takes them, timing each part
+    let mut budget = stress.as_ref().map(|_| FrameBudget::default());
+    let mut budget_parts: Vec<String> = Vec::new();
+    let tick_rate = if budget.is_some() { Duration::ZERO } else { tick_rate };
+    if budget.is_some() {
+        stress::apply(&mut game);
+        game.set_debug(true);
+    }
+    game.set_title(cli.title.as_deref().map(TitleCard::new));
+    game.set_banner(cli.banner.as_deref().map(Banner::new));
+
+    let commands = if cli.progress {
+        game.set_progress(0.0);
+        Some(commands::spawn_stdin_reader())
+    } else {
+        None
+    };
+    game.set_paused(cli.paused);
+    if let Some(column) = cli.spotlight {
+        game.toggle_spotlight(Some(column));
+    }
+    let mut damage = DamageTracker::default();
+    let mut renderer = Renderer::spawn();
+
+    let started = Instant::now();
+    let mut last_frame = started;
+    let mut pacer = FramePacer::new(tick_rate);
+    pacer.set_input(activity.is_none());
+    if let Some(max_fps) = cli.max_fps {
+        pacer.set_max_fps(max_fps);
+    }
 
+    // A seeded run steps in whole ticks of the starting frame rate, so it
+    // replays the same way however the frames happen to be timed. In lockstep,
+    // it steps a tick per frame, so the frames drawn are the same too.
+    let fixed_step = (cli.seed.is_some() || cli.lockstep) && !tick_rate.is_zero();
+    let mut unsimulated = Duration::ZERO;
     let mut last_perf_update = Instant::now();
+    let mut last_full_repaint = Instant::now();
+    let mut repaint_requested = false;
+    let mut step_requested = false;
+    let mut resized: Option<(u16, u16)> = None; // the latest size, until resized to
+    let mut last_resize = Instant::now();
+
+    // Frame verification also repaints in full every so often
+    let repaint_every = match (cli.repaint_every, cli.verify_frames) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    let mut replay = cli.replay_buffer.map(ReplayBuffer::new);
+    let mut chime = cli.chime;
     let mut cached_perf_lines: Vec<String> = Vec::new();
+    let mut frames_drawn: u64 = 0;
 
-    loop {
-        if event::poll(Duration::from_millis(75))? {
-            if let Event::Key(key_event) = event::read()? {
-                match key_event.code {
-                    KeyCode::Char('q') | KeyCode::Esc => break,
-                    KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => break,
-                    KeyCode::Char('d') => game.increase_density(),
-                    KeyCode::Char('D') => game.decrease_density(),
-                    KeyCode::Char('h') => game.increase_max_stack_height(),
-                    KeyCode::Char('H') => game.decrease_max_stack_height(),
-                    KeyCode::Char('s') => game.increase_speed(),
-                    KeyCode::Char('S') => game.decrease_speed(),
-                    KeyCode::Char('?') => game.toggle_debug(),
-                    _ => {}
+    'main: loop {
+        let frame_start = Instant::now();
+        if duration.is_some_and(|duration| started.elapsed() >= duration)
+            || cli.frames.is_some_and(|frames| frames_drawn >= frames)
+        {
+            game.emit(SimEvent::TimerExpired);
+            let mut frame = Vec::new();
+            for event in game.take_events() {
+                notifier.dispatch(event, &terminal_env, &mut frame);
+            }
+            renderer.submit(frame)?;
+            break;
+        }
+        frames_drawn += 1;
+
+        // Any key pressed for the shell ends the rain
+        if activity.as_ref().is_some_and(|activity| activity.seen()) {
+            break;
+        }
+
+        // Exits once the job feeding progress on stdin finishes
+        if let Some(commands) = &commands {
+            loop {
+                match commands.try_recv() {
+                    Ok(Command::Progress(percent)) => game.set_progress(percent),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => break 'main,
                 }
             }
         }
 
-        let (current_width, current_height) = game.get_dimensions();
-        let (new_width, new_height) = terminal::size()?;
-        if new_width != current_width || new_height != current_height {
-            game.resize(new_width, new_height);
-            stdout.execute(Clear(ClearType::All))?;
+        // New commits rain down their subjects, with the latest one's hash in the corner
+        if let Some(watcher) = &git_watcher {
+            for commit in watcher.commits.try_iter() {
+                game.spawn_text(&commit.subject, gitwatch::COMMIT_COLOR);
+                game.set_caption(Some(Caption::new(&commit.short_hash, gitwatch::COMMIT_COLOR)));
+            }
+        }
+
+        // The time chimes on the minutes of the schedule, by the clock on the wall
+        let chimed = chime.as_mut().and_then(|chime| chime.due(SystemTime::now()));
+        if let Some((hour, minute)) = chimed {
+            game.chime(hour, minute);
+        }
+
+        // Changes to the config file apply as soon as they're saved
+        if config_watcher.as_mut().is_some_and(|watcher| watcher.changed()) {
+            let (text, color) = match reload_config(&cli, &env_args, defaults, &mut game) {
+                Ok((new_cli, new_keymap, new_presets)) => {
+                    (cli, keymap, presets) = (new_cli, new_keymap, new_presets);
+                    messages = Messages::for_language(cli.language.as_deref());
+                    (messages.get("config-reloaded"), reload::RELOADED_COLOR)
+                }
+                Err(e) => (
+                    messages.format("config-not-reloaded", &[("error", &e)]),
+                    reload::INVALID_COLOR,
+                ),
+            };
+            game.set_caption(Some(Caption::new(&text, color)));
         }
 
-        let changes = game.update_and_get_changes();
+        // Each new journal entry rains down in its severity's color
+        #[cfg(feature = "journal")]
+        if let Some(journal) = &journal {
+            for severity in journal.entries.try_iter() {
+                game.spawn_tinted(severity.color());
+            }
+        }
 
-        let mut y_offset = 0;
-        if game.debug {
+        // Input is handled as it arrives, until it's time for the next frame
+        while let Some(event) = pacer.next_event()? {
+            if let Event::Resize(width, height) = event {
+                resized = Some((width, height));
+                last_resize = Instant::now();
+            }
+            if let Event::Key(key_event) = event {
+                if let KeyCode::Char(c) = key_event.code {
+                    game.record_key(c);
+                }
+                match keymap.action(game.effect(), &key_event) {
+                    Some(Action::Quit) => break 'main,
+                    Some(Action::IncreaseDensity) => game.increase_density(),
+                    Some(Action::DecreaseDensity) => game.decrease_density(),
+                    Some(Action::IncreaseStackHeight) => game.increase_max_stack_height(),
+                    Some(Action::DecreaseStackHeight) => game.decrease_max_stack_height(),
+                    Some(Action::IncreaseSpeed) => game.increase_speed(),
+                    Some(Action::DecreaseSpeed) => game.decrease_speed(),
+                    Some(Action::IncreaseMutation) => game.increase_mutation_rate(),
+                    Some(Action::DecreaseMutation) => game.decrease_mutation_rate(),
+                    Some(Action::PanUp) => game.pan(-1),
+                    Some(Action::PanDown) => game.pan(1),
+                    Some(Action::PageUp) => game.pan(-(game.get_dimensions().1 as i32)),
+                    Some(Action::PageDown) => game.pan(game.get_dimensions().1 as i32),
+                    Some(Action::PanTop) => game.pan(i32::MIN / 2),
+                    Some(Action::PanBottom) => game.pan(i32::MAX / 2),
+                    Some(Action::ZoomIn) => game.zoom_in(),
+                    Some(Action::ZoomOut) => game.zoom_out(),
+                    Some(Action::ToggleHeatMap) => game.toggle_heat_map(),
+                    Some(Action::ToggleFastForward) => game.toggle_fast_forward(),
+                    Some(Action::TogglePause) => game.toggle_pause(),
+                    Some(Action::Step) => step_requested = game.paused(),
+                    Some(Action::IncreaseFps) => pacer.increase_fps(),
+                    Some(Action::DecreaseFps) => pacer.decrease_fps(),
+                    Some(Action::CycleMirror) => game.cycle_mirror(),
+                    Some(Action::CycleEffect) => game.cycle_effect(),
+                    Some(Action::CycleCharset) => game.cycle_charset(),
+                    Some(Action::CycleTheme) => game.cycle_theme(),
+                    Some(Action::IncreaseBrightness) => game.increase_brightness(),
+                    Some(Action::DecreaseBrightness) => game.decrease_brightness(),
+                    Some(Action::StrikeLightning) => game.strike_lightning(),
+                    Some(Action::ToggleSpotlight) => game.toggle_spotlight(cli.spotlight),
+                    Some(Action::SpotlightLeft) => game.move_spotlight(-1),
+                    Some(Action::SpotlightRight) => game.move_spotlight(1),
+                    Some(Action::ToggleDebug) => game.toggle_debug(),
+                    Some(Action::Repaint) => repaint_requested = true,
+                    // The time stopped isn't simulated
+                    Some(Action::Suspend) => {
+                        #[cfg(unix)]
+                        {
+                            renderer.suspend(!cli.inline)?;
+                            damage.reset();
+                            repaint_requested = true;
+                            // Resizes while stopped aren't heard of
+                            resized = Some(terminal::size()?);
+                            last_frame = Instant::now();
+                        }
+                    }
+                    Some(Action::SaveReplay) => {
+                        let text = match &replay {
+                            Some(replay) => save_replay(replay, game.get_dimensions(), &messages),
+                            None => messages.get("no-replay-buffer"),
+                        };
+                        game.set_caption(Some(Caption::new(&text, replay::CAPTION_COLOR)));
+                    }
+                    Some(Action::SavePreset(slot)) => {
+                        let name = presets.get(&slot).and_then(|preset| preset.name.clone());
+                        let preset = Preset::capture(&game, name);
+                        let saved = match &config_path {
+                            Some(path) => config::save_preset(path, slot, &preset),
+                            None => Err(io::Error::other("no config directory")),
+                        };
+                        if let Some(watcher) = &mut config_watcher {
+                            watcher.skip();
+                        }
+                        let label = preset.label(slot);
+                        preset_status = match saved {
+                            Ok(()) => messages.format("preset-saved", &[("preset", &label)]),
+                            Err(e) => messages
+                                .format("preset-not-saved", &[("preset", &label), ("error", &e)]),
+                        };
+                        presets.insert(slot, preset);
+                    }
+                    Some(Action::RecallPreset(slot)) => {
+                        preset_status = match presets.get(&slot) {
+                            Some(preset) => {
+                                preset.apply(&mut game);
+                                preset.label(slot)
+                            }
+                            None => messages.format("preset-empty", &[("slot", &slot)]),
+                        };
+                    }
+                    Some(Action::None) | None => {}
+                }
+            }
+        }
+
+        // Give up on a terminal that has stopped reading, rather than hang in raw mode
+        let stalled = renderer.stalled_for();
+        if !cli.watchdog.is_zero() && stalled >= cli.watchdog {
+            renderer::abandon_terminal(stalled);
+        }
+
+        // Terminals that support it draw each frame all at once, without tearing
+        let mut frame = Vec::new();
+        if terminal_env.synchronized_updates {
+            frame.queue(BeginSynchronizedUpdate)?;
+        }
+        // Without reading input, there are no resize events to wait for, so
+        // the size is checked every frame
+        if activity.is_some() {
+            resized = Some(terminal::size()?);
+        }
+        let settled = activity.is_some() || last_resize.elapsed() >= RESIZE_SETTLE;
+        let new_size = resized.filter(|_| settled);
+        if new_size.is_some() {
+            resized = None;
+        }
+        if let Some((width, height)) = new_size.filter(|size| *size != game.get_dimensions()) {
+            game.resize(width, height);
+            frame
+                .queue(MoveTo(0, inline_top))?
+                .queue(Clear(ClearType::FromCursorDown))?;
+            damage.reset();
+            if let Some(replay) = &mut replay {
+                replay.clear();
+            }
+            // Nothing changes while paused, so the frozen frame is drawn again
+            repaint_requested |= game.paused();
+        }
+        let (screen_width, _) = game.get_dimensions();
+
+        let now = Instant::now();
+        // Time paused isn't caught up with afterwards, and steps taken while
+        // paused are a tick long, however long they were waited for
+        let mut changes = if game.paused() {
+            unsimulated = Duration::ZERO;
+            if std::mem::take(&mut step_requested) {
+                let tick = if tick_rate.is_zero() { TICK_RATE } else { tick_rate };
+                game.step_and_get_changes(tick)
+            } else {
+                game.update_and_get_changes(now - last_frame)
+            }
+        } else if fixed_step {
+            unsimulated = if cli.lockstep {
+                tick_rate
+            } else {
+                (unsimulated + (now - last_frame)).min(MAX_CATCH_UP)
+            };
+            let mut changes = Vec::new();
+            while unsimulated >= tick_rate {
+                unsimulated -= tick_rate;
+                changes.extend(game.update_and_get_changes(tick_rate));
+            }
+            changes
+        } else {
+            game.update_and_get_changes(now - last_frame)
+        };
+        last_frame = now;
+        let keyframe = replay.as_ref().is_some_and(|replay| replay.wants_keyframe(now));
+        if repaint_requested
+            || keyframe
+            || repaint_every.is_some_and(|interval| last_full_repaint.elapsed() >= interval)
+        {
+            changes = game.full_repaint();
+            last_full_repaint = Instant::now();
+            repaint_requested = false;
+        }
+        let compose_start = Instant::now();
+        if let Some(budget) = &mut budget {
+            budget.record(Phase::Input, now - frame_start);
+            budget.record(Phase::Simulate, compose_start - now);
+        }
+        if !metrics.is_empty() {
+            metrics.record(game.telemetry())?;
+        }
+
+        // Overlays cover the rain; anything they stop covering is repainted
+        damage.begin_frame();
+        damage.cover(Rect::new(0, 0, screen_width, inline_top));
+        let mut overlay_rows = 0;
+        if game.debug() {
             // Update performance metrics only once a second
             if last_perf_update.elapsed() >= Duration::from_secs(1) {
-                let perf_metrics = vec![
-                    format!("Updates/sec: {:.2}", game.debug_info.updates_per_sec),
-                    format!("Glyphs/sec: {:.2}", game.debug_info.glyphs_per_sec),
-                    format!("Glyphs/update: {}", game.debug_info.glyphs_per_update),
-                    format!("Stacks/update: {}", game.debug_info.stacks_per_update),
-                    format!(
-                        "Min/Max stack update delay (ms): {}/{}",
-                        game.debug_info.min_glyph_delay, game.debug_info.max_glyph_delay
+                let telemetry = game.telemetry();
+                let second = telemetry.rates(Window::Second);
+                let minute = telemetry.rates(Window::Minute);
+                let (min_delay, max_delay) = telemetry.glyph_delay_ms();
+                let pool = telemetry.stack_pool();
+                let on_off = |on: bool| messages.get(if on { "on" } else { "off" });
+                let mut perf_metrics = vec![
+                    messages.format(
+                        "overlay-update-delay",
+                        &[("delay", &pacer.interval().as_millis())],
+                    ),
+                    messages.format(
+                        "overlay-target-fps",
+                        &[(
+                            "fps",
+                            &match pacer.fps() {
+                                Some(fps) => format!("{:.0}", fps),
+                                None => messages.get("overlay-unlimited"),
+                            },
+                        )],
+                    ),
+                    messages.format(
+                        "overlay-updates",
+                        &[
+                            ("rate", &format!("{:.2}", second.updates_per_sec)),
+                            ("minute", &format!("{:.2}", minute.updates_per_sec)),
+                        ],
+                    ),
+                    messages.format(
+                        "overlay-glyphs",
+                        &[
+                            ("rate", &format!("{:.2}", second.glyphs_per_sec)),
+                            ("minute", &format!("{:.2}", minute.glyphs_per_sec)),
+                        ],
+                    ),
+                    messages.format(
+                        "overlay-glyphs-per-update",
+                        &[("glyphs", &telemetry.glyphs_per_update())],
+                    ),
+                    messages.format(
+                        "overlay-stacks-per-update",
+                        &[("stacks", &telemetry.stacks_per_update())],
+                    ),
+                    messages.format(
+                        "overlay-stack-delay",
+                        &[("min", &min_delay), ("max", &max_delay)],
+                    ),
+                    messages.format(
+                        "overlay-stack-pool",
+                        &[
+                            ("pooled", &pool.pooled),
+                            ("reused", &format!("{:.0}", pool.reuse_rate() * 100.0)),
+                        ],
+                    ),
+                    messages.format(
+                        "overlay-key-heat",
+                        &[(
+                            "heat",
+                            &match game.heat_map_enabled() {
+                                true => format!("{:.2}", telemetry.heat()),
+                                false => on_off(false),
+                            },
+                        )],
+                    ),
+                    messages.format(
+                        "overlay-progress",
+                        &[(
+                            "progress",
+                            &match game.progress() {
+                                Some(percent) => format!("{:.0}%", percent),
+                                None => on_off(false),
+                            },
+                        )],
+                    ),
+                    messages.format(
+                        "overlay-coverage",
+                        &[
+                            ("coverage", &format!("{:.0}", telemetry.coverage() * 100.0)),
+                            ("trend", &format!("{:+.1}", second.coverage_trend * 100.0)),
+                        ],
+                    ),
+                    messages.format(
+                        "overlay-target-coverage",
+                        &[(
+                            "target",
+                            &match game.target_coverage() {
+                                Some(target) => messages.format(
+                                    "overlay-spawn-rate",
+                                    &[
+                                        ("target", &format!("{:.0}", target)),
+                                        ("scale", &format!("{:.2}", game.spawn_scale())),
+                                    ],
+                                ),
+                                None => on_off(false),
+                            },
+                        )],
                     ),
+                    messages.format(
+                        "overlay-fast-forward",
+                        &[("state", &on_off(game.fast_forward()))],
+                    ),
+                    match game.pipeline().as_slice() {
+                        [] => messages.format(
+                            "overlay-effect",
+                            &[("effect", &format!("{:?}", game.effect()))],
+                        ),
+                        stages => {
+                            let stages: Vec<String> = stages
+                                .iter()
+                                .map(|s| format!("{:?} {:.0}%", s.effect, s.opacity * 100.0))
+                                .collect();
+                            messages.format("overlay-pipeline", &[("stages", &stages.join(" < "))])
+                        }
+                    },
+                    messages.format("overlay-preset", &[("preset", &preset_status)]),
+                    messages.format(
+                        "overlay-mutation-rate",
+                        &[("rate", &format!("{:.2}", game.mutation_rate()))],
+                    ),
+                    messages.format("overlay-zoom", &[("zoom", &format!("{:?}", game.zoom()))]),
+                    {
+                        let (camera_y, canvas_height) = game.camera();
+                        messages.format(
+                            "overlay-camera",
+                            &[("row", &camera_y), ("rows", &canvas_height)],
+                        )
+                    },
+                    messages.format(
+                        "overlay-spotlight",
+                        &[(
+                            "spotlight",
+                            &match game.spotlight() {
+                                Some(x) => {
+                                    messages.format("overlay-spotlight-column", &[("column", &x)])
+                                }
+                                None => on_off(false),
+                            },
+                        )],
+                    ),
+                    messages.format("overlay-terminal", &[("terminal", &terminal_env)]),
+                    messages.format("overlay-seed", &[("seed", &game.seed())]),
                 ];
+                if cli.verify_frames.is_some() {
+                    let hash = format!("{:016x}", telemetry.frame_hash());
+                    perf_metrics.push(messages.format("overlay-frame-hash", &[("hash", &hash)]));
+                    let repaints = telemetry.full_repaints();
+                    perf_metrics
+                        .push(messages.format("overlay-full-repaints", &[("repaints", &repaints)]));
+                }
+                if budget.is_some() {
+                    perf_metrics.extend(budget_parts.iter().cloned());
+                }
 
                 let mut perf_line = String::new();
                 cached_perf_lines.clear();
                 for metric in perf_metrics {
                     if perf_line.is_empty() {
                         perf_line.push_str(&metric);
-                    } else if perf_line.len() + 3 + metric.len() <= new_width as usize {
+                    } else if perf_line.len() + 3 + metric.len() <= screen_width as usize {
                         perf_line.push_str(" | ");
                         perf_line.push_str(&metric);
                     } else {
@@ -180,41 +1219,119 @@ fn main() -> io::Result<()> {
                 last_perf_update = Instant::now();
             }
 
-            y_offset = render_debug_info(&mut stdout, &game.debug_info, new_width, &cached_perf_lines)?;
-        } else if last_debug_state {
-            for i in 0..last_debug_lines {
-                stdout
-                    .execute(MoveTo(0, i))?
-                    .execute(Print(" ".repeat(new_width as usize)))?;
+            let lines = render_debug_info(
+                &mut frame,
+                game.telemetry(),
+                screen_width,
+                &cached_perf_lines,
+                terminal_env.colors,
+                &messages,
+            )?;
+            damage.cover(Rect::new(0, 0, screen_width, lines));
+            overlay_rows = lines;
+        }
+
+        // The terminal scrolls the rain itself, below the overlay. Rows
+        // scrolled out from under the overlay were never drawn, so are drawn now.
+        if let Some(scroll) = game.scroll() {
+            let top = scroll.top.max(overlay_rows);
+            if top < scroll.bottom {
+                queue_scroll(&mut frame, top, scroll.bottom, scroll.rows)?;
+                if top > scroll.top {
+                    let rows = scroll.rows.min(scroll.bottom - top);
+                    changes.extend(game.repaint_region(Rect::new(0, top, screen_width, rows)));
+                }
             }
         }
-        last_debug_state = game.debug;
-        last_debug_lines = y_offset;
+        for region in damage.exposed() {
+            changes.extend(game.repaint_region(region));
+        }
 
-        for change in changes {
+        let colors = terminal_env.colors;
+        for change in damage.filter(changes) {
             match change {
-                Change::Update(x, y, ch, color) => {
-                    if y + y_offset < new_height {
-                        stdout
-                            .execute(MoveTo(x, y + y_offset))?
-                            .execute(SetForegroundColor(convert_color(color)))?
-                            .execute(Print(ch))?;
+                Change::Update(x, y, ch, color, background, attributes, _) => {
+                    let quantize = |color| palette::quantize_at(color, colors, cli.dither, x, y);
+                    frame
+                        .queue(MoveTo(x, y))?
+                        .queue(SetForeground(quantize(color), colors))?;
+                    for (attribute, set) in TEXT_ATTRIBUTES {
+                        if attributes.contains(attribute) {
+                            frame.queue(SetAttribute(set))?;
+                        }
+                    }
+                    match background {
+                        Some(background) => {
+                            let background = quantize(background);
+                            frame
+                                .queue(SetBackground(background, colors))?
+                                .queue(Print(ch))?
+                                .queue(SetBackground(Color::Reset, colors))?;
+                        }
+                        None => {
+                            frame.queue(Print(ch))?;
+                        }
+                    }
+                    if attributes.intersects(Attributes::BOLD | Attributes::DIM) {
+                        frame.queue(SetAttribute(Attribute::NormalIntensity))?;
+                    }
+                    if attributes.contains(Attributes::ITALIC) {
+                        frame.queue(SetAttribute(Attribute::NoItalic))?;
                     }
                 }
                 Change::Remove(x, y) => {
-                    if y + y_offset < new_height {
-                        stdout.execute(MoveTo(x, y + y_offset))?.execute(Print(' '))?;
-                    }
+                    frame.queue(MoveTo(x, y))?.queue(Print(' '))?;
                 }
             }
         }
 
-        stdout.flush()?;
+        if terminal_env.synchronized_updates {
+            frame.queue(EndSynchronizedUpdate)?;
+        }
+        for event in game.take_events() {
+            notifier.dispatch(event, &terminal_env, &mut frame);
+        }
+        if let Some(replay) = &mut replay {
+            replay.push(now, keyframe, &frame);
+        }
+        let Some(budget) = &mut budget else {
+            renderer.submit(frame)?;
+            continue;
+        };
+
+        // Waiting for the terminal to take each frame times how fast it draws
+        let write_start = budget.lap(Phase::Compose, compose_start);
+        let bytes = frame.len();
+        renderer.submit(frame)?;
+        renderer.wait_idle(cli.watchdog);
+        budget.lap(Phase::Write, write_start);
+        budget.end_frame(bytes);
+        if let Some(summary) = budget.summary(&messages) {
+            budget_parts = summary;
+        }
     }
 
+    renderer.finish()?;
     stdout.execute(Show)?;
-    stdout.execute(LeaveAlternateScreen)?;
-    stdout.execute(Clear(ClearType::All))?;
+    if cli.inline {
+        stdout.execute(MoveTo(0, inline_top))?;
+        stdout.execute(Clear(ClearType::FromCursorDown))?;
+    } else {
+        stdout.execute(LeaveAlternateScreen)?;
+        if activity.is_none() {
+            stdout.execute(Clear(ClearType::All))?;
+        }
+    }
     terminal::disable_raw_mode()?;
+
+    if let Some(path) = &session_path {
+        let settings = Settings {
+            debug: false,
+            ..game.settings()
+        };
+        if let Err(e) = session::save(path, &settings) {
+            eprintln!("warning: failed to save the session to {}: {}", path.display(), e);
+        }
+    }
     Ok(())
 }
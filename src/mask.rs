@@ -0,0 +1,76 @@
+use clap::ValueEnum;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::game::Viewport;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MaskPattern {
+    // Alternating horizontal bands of hidden and visible rows
+    Stripes,
+    // Alternating squares of hidden and visible cells
+    Checkerboard,
+}
+
+// Cells where rain is hidden, letting it form shapes in negative space
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mask {
+    Pattern { pattern: MaskPattern, size: u16 },
+    // Lines of text centered on the screen, hiding the rain under every
+    // non-whitespace character
+    Stencil(Vec<Vec<bool>>),
+}
+
+impl Mask {
+    pub fn load_stencil(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Ok(Self::stencil_from_str(&text))
+    }
+
+    pub fn stencil_from_str(text: &str) -> Self {
+        let rows = text
+            .lines()
+            .map(|line| line.chars().map(|c| !c.is_whitespace()).collect())
+            .collect();
+        Mask::Stencil(rows)
+    }
+
+    pub fn is_masked(&self, x: u16, y: u16, width: u16, height: u16) -> bool {
+        match self {
+            Mask::Pattern { pattern, size } => {
+                // Wide enough not to overflow doubling the biggest size
+                let (x, y, size) = (x as u32, y as u32, (*size).max(1) as u32);
+                match pattern {
+                    MaskPattern::Stripes => (y / size) % 2 == 1,
+                    MaskPattern::Checkerboard => ((x / (size * 2)) + (y / size)) % 2 == 1,
+                }
+            }
+            Mask::Stencil(rows) => {
+                let stencil_height = rows.len() as i32;
+                let stencil_width = rows.iter().map(|r| r.len()).max().unwrap_or(0) as i32;
+                let sx = x as i32 - (width as i32 - stencil_width) / 2;
+                let sy = y as i32 - (height as i32 - stencil_height) / 2;
+                if sx < 0 || sy < 0 || sy >= stencil_height {
+                    return false;
+                }
+                rows[sy as usize].get(sx as usize).copied().unwrap_or(false)
+            }
+        }
+    }
+
+    pub fn apply(&self, view: &Viewport) -> Viewport {
+        let (width, height) = (view.width(), view.height());
+        let mut result = Viewport::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                if let Some(cell) = view.get(x, y) {
+                    if !self.is_masked(x, y, width, height) {
+                        result.set(x, y, cell);
+                    }
+                }
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,191 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+#[cfg(feature = "stats")]
+use std::{fs, net::ToSocketAddrs, net::UdpSocket, path::PathBuf};
+
+use falling_glyphs::telemetry::{Telemetry, Window};
+
+const COLUMNS: &str = "elapsed_secs,updates_per_sec,glyphs_per_sec,stacks_per_update,\
+                       density,max_stack_height,speed,coverage,coverage_trend";
+
+// Somewhere a snapshot of the simulation's metrics is written, given the
+// time since the run started
+trait Sink {
+    fn write(&mut self, elapsed: Duration, telemetry: &Telemetry) -> io::Result<()>;
+}
+
+// The metrics as named gauges, with what each measures, for sinks that
+// label their values rather than ordering them
+#[cfg(feature = "stats")]
+const GAUGES: [(&str, &str); 9] = [
+    ("uptime_seconds", "Seconds since the run started"),
+    ("updates_per_second", "Simulation updates per second"),
+    ("glyphs_per_second", "Glyphs pushed per second"),
+    ("stacks_per_update", "Stacks spawned last update"),
+    ("density", "Spawn density setting"),
+    ("max_stack_height", "Longest stack, over the screen"),
+    ("speed", "Speed setting"),
+    ("coverage", "Fraction of the screen's cells lit"),
+    ("coverage_trend", "Change in coverage per second"),
+];
+
+// The values of the gauges, in order
+#[cfg(feature = "stats")]
+fn gauge_values(elapsed: Duration, telemetry: &Telemetry) -> [f64; 9] {
+    let rates = telemetry.rates(Window::Second);
+    [
+        elapsed.as_secs_f64(),
+        rates.updates_per_sec,
+        rates.glyphs_per_sec,
+        telemetry.stacks_per_update() as f64,
+        telemetry.density(),
+        telemetry.max_stack_height(),
+        telemetry.speed() as f64,
+        telemetry.coverage(),
+        rates.coverage_trend,
+    ]
+}
+
+// Appends a CSV row per snapshot, for tuning settings or graphing a run
+// after the fact
+struct CsvSink {
+    out: BufWriter<File>,
+}
+
+impl Sink for CsvSink {
+    fn write(&mut self, elapsed: Duration, telemetry: &Telemetry) -> io::Result<()> {
+        let rates = telemetry.rates(Window::Second);
+        writeln!(
+            self.out,
+            "{:.1},{:.2},{:.2},{},{:.2},{:.2},{},{:.4},{:.4}",
+            elapsed.as_secs_f64(),
+            rates.updates_per_sec,
+            rates.glyphs_per_sec,
+            telemetry.stacks_per_update(),
+            telemetry.density(),
+            telemetry.max_stack_height(),
+            telemetry.speed(),
+            telemetry.coverage(),
+            rates.coverage_trend,
+        )?;
+        self.out.flush()
+    }
+}
+
+// Sends the gauges to a statsd server in one packet. Sending is fire and
+// forget, so a server that's down or restarting loses the snapshot rather
+// than stopping the rain.
+#[cfg(feature = "stats")]
+struct StatsdSink {
+    socket: UdpSocket,
+}
+
+#[cfg(feature = "stats")]
+impl Sink for StatsdSink {
+    fn write(&mut self, elapsed: Duration, telemetry: &Telemetry) -> io::Result<()> {
+        let packet: Vec<String> = GAUGES
+            .iter()
+            .zip(gauge_values(elapsed, telemetry))
+            .map(|((name, _), value)| format!("falling_glyphs.{}:{}|g", name, value))
+            .collect();
+        let _ = self.socket.send(packet.join("\n").as_bytes());
+        Ok(())
+    }
+}
+
+// Replaces a file with the gauges in the Prometheus text format, for
+// node_exporter's textfile collector. The file is written alongside and
+// renamed over it, so the collector never reads half a snapshot.
+#[cfg(feature = "stats")]
+struct PrometheusSink {
+    path: PathBuf,
+}
+
+#[cfg(feature = "stats")]
+impl Sink for PrometheusSink {
+    fn write(&mut self, elapsed: Duration, telemetry: &Telemetry) -> io::Result<()> {
+        let mut text = String::new();
+        for ((name, help), value) in GAUGES.iter().zip(gauge_values(elapsed, telemetry)) {
+            text += &format!("# HELP falling_glyphs_{} {}\n", name, help);
+            text += &format!("# TYPE falling_glyphs_{} gauge\n", name);
+            text += &format!("falling_glyphs_{} {}\n", name, value);
+        }
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        fs::write(&temp, text)?;
+        fs::rename(&temp, &self.path)
+    }
+}
+
+// Writes a snapshot of the simulation's metrics to each of its sinks on an
+// interval
+pub struct MetricsSink {
+    sinks: Vec<Box<dyn Sink>>,
+    interval: Duration,
+    started: Instant,
+    last_write: Instant,
+}
+
+impl MetricsSink {
+    pub fn new(interval: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            sinks: Vec::new(),
+            interval,
+            started: now,
+            last_write: now,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    pub fn add_csv(&mut self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{}", COLUMNS)?;
+        self.sinks.push(Box::new(CsvSink { out }));
+        Ok(())
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn add_statsd(&mut self, address: &str) -> io::Result<()> {
+        let address = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the address didn't resolve"))?;
+        let local = if address.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(address)?;
+        self.sinks.push(Box::new(StatsdSink { socket }));
+        Ok(())
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn add_prometheus(&mut self, path: &Path) -> io::Result<()> {
+        // Fail now, rather than a second in, if the file can't be written
+        File::create(path)?;
+        self.sinks.push(Box::new(PrometheusSink {
+            path: path.to_path_buf(),
+        }));
+        Ok(())
+    }
+
+    pub fn record(&mut self, telemetry: &Telemetry) -> io::Result<()> {
+        if self.last_write.elapsed() < self.interval {
+            return Ok(());
+        }
+        self.last_write = Instant::now();
+        let elapsed = self.started.elapsed();
+        for sink in &mut self.sinks {
+            sink.write(elapsed, telemetry)?;
+        }
+        Ok(())
+    }
+}
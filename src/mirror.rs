@@ -0,0 +1,90 @@
+use clap::ValueEnum;
+
+use crate::game::{display_width, Viewport};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Mirror {
+    #[default]
+    Off,
+    // The left half of the screen is reflected onto the right half
+    Horizontal,
+    // The top half of the screen is reflected onto the bottom half
+    Vertical,
+}
+
+impl Mirror {
+    pub fn next(self) -> Self {
+        match self {
+            Mirror::Off => Mirror::Horizontal,
+            Mirror::Horizontal => Mirror::Vertical,
+            Mirror::Vertical => Mirror::Off,
+        }
+    }
+
+    // The number of columns stacks may spawn in, so that a (double-width)
+    // glyph never straddles the middle of the screen
+    pub fn source_width(self, width: u16) -> u16 {
+        match self {
+            Mirror::Horizontal => (width / 2).saturating_sub(1),
+            _ => width,
+        }
+    }
+
+    // Replaces the mirrored half of the view with a reflection of the source half
+    pub fn apply(self, view: &Viewport) -> Viewport {
+        let (width, height) = (view.width(), view.height());
+        let mut result = Viewport::new(width, height);
+        match self {
+            Mirror::Off => return view.clone(),
+            Mirror::Horizontal => {
+                for y in 0..height {
+                    for x in 0..width / 2 {
+                        if let Some(cell) = view.get(x, y) {
+                            result.set(x, y, cell);
+                            // Wide glyphs cover x and x + 1, so they land one column further left
+                            if let Some(mirror_x) = width.checked_sub(x + display_width(cell.ch)) {
+                                result.set(mirror_x, y, cell);
+                            }
+                        }
+                    }
+                }
+            }
+            Mirror::Vertical => {
+                for y in 0..height.div_ceil(2) {
+                    for x in 0..width {
+                        if let Some(cell) = view.get(x, y) {
+                            result.set(x, y, cell);
+                            result.set(x, height - 1 - y, cell);
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{AnsiColor, Attributes, Cell};
+
+    fn glyph(ch: char) -> Cell {
+        Cell {
+            ch,
+            color: AnsiColor::Green,
+            background: None,
+            attributes: Attributes::default(),
+        }
+    }
+
+    #[test]
+    fn horizontal_mirrors_reflect_narrow_and_wide_glyphs_onto_the_same_columns() {
+        let mut view = Viewport::new(10, 1);
+        view.set(0, 0, glyph('a'));
+        view.set(2, 0, glyph('日'));
+        let mirrored = Mirror::Horizontal.apply(&view);
+        assert_eq!(mirrored.get(9, 0).map(|cell| cell.ch), Some('a'));
+        assert_eq!(mirrored.get(6, 0).map(|cell| cell.ch), Some('日'));
+    }
+}
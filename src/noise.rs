@@ -0,0 +1,45 @@
+// Smooth, seeded value noise over a plane: random values at the points of a
+// unit grid, blended smoothly in between, so nearby points get similar values
+// and the field drifts rather than flickers as either coordinate moves
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ValueNoise {
+    seed: u64,
+}
+
+impl ValueNoise {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    // The noise at a point, from -1.0 to 1.0
+    pub fn at(&self, x: f64, y: f64) -> f64 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (smoothstep(x - x0), smoothstep(y - y0));
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = lerp(self.lattice(x0, y0), self.lattice(x0 + 1, y0), fx);
+        let bottom = lerp(self.lattice(x0, y0 + 1), self.lattice(x0 + 1, y0 + 1), fx);
+        lerp(top, bottom, fy)
+    }
+
+    // The random value at a point of the grid, from -1.0 to 1.0 (SplitMix64
+    // of the seed mixed with the point)
+    fn lattice(&self, x: i64, y: i64) -> f64 {
+        let mut z = self
+            .seed
+            .wrapping_add((x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .wrapping_add((y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+    }
+}
+
+// Eases a fraction in and out, so the blend has no kinks at grid lines
+fn smoothstep(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
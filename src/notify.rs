@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use falling_glyphs::events::SimEvent;
+use serde::Deserialize;
+
+use crate::terminal_env::TerminalEnv;
+
+const BELL: &str = "\x07";
+
+// How to get the user's attention when an event happens
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Notification {
+    Off,
+    Bell,
+    // An OSC 9 desktop notification, where the terminal supports them
+    Desktop,
+    Both,
+}
+
+// Turns the game's events into terminal bells and desktop notifications
+#[derive(Clone, Debug, Default)]
+pub struct Notifier {
+    notifications: HashMap<SimEvent, Notification>,
+}
+
+impl Notifier {
+    pub fn new(notifications: HashMap<SimEvent, Notification>) -> Self {
+        Self { notifications }
+    }
+
+    // Appends the escape codes for the event's notification to a frame
+    pub fn dispatch(&self, event: SimEvent, env: &TerminalEnv, frame: &mut Vec<u8>) {
+        let notification = self.notifications.get(&event).copied().unwrap_or(Notification::Off);
+        if matches!(notification, Notification::Bell | Notification::Both) {
+            frame.extend_from_slice(BELL.as_bytes());
+        }
+        if matches!(notification, Notification::Desktop | Notification::Both) {
+            let message = format!("falling_glyphs: {}", event.description());
+            let osc = format!("\x1b]9;{}{}", message, BELL);
+            frame.extend_from_slice(env.passthrough(&osc).as_bytes());
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use clap::ValueEnum;
+use rand::Rng;
+
+// The edge (or edges) of the screen stacks spawn from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Origin {
+    #[default]
+    Top,
+    Bottom,
+    Left,
+    Right,
+    // Every edge, with stacks converging on the center of the screen
+    All,
+}
+
+// The edge a single stack spawned from, which sets its direction of travel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Edge {
+    #[default]
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Origin {
+    pub fn pick_edge<R: Rng>(self, rng: &mut R) -> Edge {
+        match self {
+            Origin::Top => Edge::Top,
+            Origin::Bottom => Edge::Bottom,
+            Origin::Left => Edge::Left,
+            Origin::Right => Edge::Right,
+            Origin::All => {
+                [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right][rng.random_range(0..4)]
+            }
+        }
+    }
+
+    // Whether stacks stop at the center of the canvas rather than crossing it
+    pub fn converges(self) -> bool {
+        self == Origin::All
+    }
+}
+
+impl Edge {
+    pub fn is_horizontal(self) -> bool {
+        matches!(self, Edge::Left | Edge::Right)
+    }
+
+    // Number of steps a stack from this edge takes to cross a canvas of the
+    // given size. Glyphs are two columns wide, so horizontal steps move two columns.
+    pub fn travel(self, width: u16, height: u16) -> u16 {
+        if self.is_horizontal() {
+            width / 2
+        } else {
+            height
+        }
+    }
+
+    // Number of lanes (columns or rows) stacks from this edge can travel along
+    pub fn lanes(self, width: u16, height: u16) -> u16 {
+        if self.is_horizontal() {
+            height
+        } else {
+            width
+        }
+    }
+
+    // Maps a lane and the distance travelled from this edge onto a cell of the canvas
+    pub fn to_cell(self, lane: u16, distance: i16, width: u16, height: u16) -> Option<(u16, u16)> {
+        if distance < 0 {
+            return None;
+        }
+        let (lane, distance) = (lane as i32, distance as i32);
+        let (x, y) = match self {
+            Edge::Top => (lane, distance),
+            Edge::Bottom => (lane, height as i32 - 1 - distance),
+            Edge::Left => (distance * 2, lane),
+            Edge::Right => (width as i32 - 2 - distance * 2, lane),
+        };
+        (x >= 0 && y >= 0 && x < width as i32 && y < height as i32).then_some((x as u16, y as u16))
+    }
+}
@@ -0,0 +1,131 @@
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event};
+
+// Frame rates stepped through at runtime
+const FPS_STEPS: [f64; 14] = [
+    1.0, 2.0, 5.0, 10.0, 15.0, 20.0, 24.0, 30.0, 45.0, 60.0, 90.0, 120.0, 144.0, 240.0,
+];
+
+// Waiting for input wakes up to a millisecond late, so the last stretch
+// before a frame is slept precisely instead
+const SLEEP_SLACK: Duration = Duration::from_millis(2);
+
+// Paces frames to a target rate, handing out input as it arrives in between,
+// without spinning. A zero interval draws frames as fast as possible.
+pub struct FramePacer {
+    interval: Duration,
+    next_frame: Instant,
+    input: bool,
+    min_interval: Duration, // from the highest frame rate allowed
+}
+
+impl FramePacer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_frame: Instant::now() + interval,
+            input: true,
+            min_interval: Duration::ZERO,
+        }
+    }
+
+    // Caps the frame rate, slowing to the cap if it's already faster
+    pub fn set_max_fps(&mut self, max_fps: f64) {
+        self.min_interval = Duration::from_secs_f64(1.0 / max_fps);
+        if let Some(fps) = self.fps() {
+            self.set_fps(fps);
+        }
+    }
+
+    // Whether to wait for input between frames at all. A process in the
+    // background of its terminal is stopped if it tries to read it.
+    pub fn set_input(&mut self, input: bool) {
+        self.input = input;
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn fps(&self) -> Option<f64> {
+        (!self.interval.is_zero()).then(|| 1.0 / self.interval.as_secs_f64())
+    }
+
+    pub fn set_fps(&mut self, fps: f64) {
+        let next = self.next_frame - self.interval;
+        self.interval = Duration::from_secs_f64(1.0 / fps).max(self.min_interval);
+        self.next_frame = next + self.interval;
+    }
+
+    pub fn increase_fps(&mut self) {
+        if let Some(fps) = self.fps() {
+            let step = FPS_STEPS.iter().find(|step| **step > fps + 0.5);
+            self.set_fps(*step.unwrap_or(&fps));
+        }
+    }
+
+    pub fn decrease_fps(&mut self) {
+        if let Some(fps) = self.fps() {
+            let step = FPS_STEPS.iter().rev().find(|step| **step < fps - 0.5);
+            self.set_fps(*step.unwrap_or(&fps));
+        }
+    }
+
+    // Waits for the next input event, or returns None once it's time to
+    // draw the next frame. Input that's already waiting is handed out first.
+    pub fn next_event(&mut self) -> io::Result<Option<Event>> {
+        loop {
+            let now = Instant::now();
+            let remaining = self.next_frame.saturating_duration_since(now);
+            if self.input {
+                if remaining > SLEEP_SLACK {
+                    if event::poll(remaining - SLEEP_SLACK)? {
+                        return event::read().map(Some);
+                    }
+                    continue;
+                }
+                if event::poll(Duration::ZERO)? {
+                    return event::read().map(Some);
+                }
+            }
+            thread::sleep(remaining);
+
+            // Frames that fall behind start the cadence over, rather than
+            // being rushed out to catch up
+            let now = Instant::now();
+            self.next_frame += self.interval;
+            if self.next_frame < now {
+                self.next_frame = now + self.interval;
+            }
+            return Ok(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_rates_step_through_the_list_and_stop_at_the_cap() {
+        let mut pacer = FramePacer::new(Duration::from_secs_f64(1.0 / 30.0));
+        pacer.increase_fps();
+        assert_eq!(pacer.fps().map(f64::round), Some(45.0));
+        pacer.decrease_fps();
+        pacer.decrease_fps();
+        assert_eq!(pacer.fps().map(f64::round), Some(24.0));
+
+        pacer.set_max_fps(60.0);
+        for _ in 0..FPS_STEPS.len() {
+            pacer.increase_fps();
+        }
+        assert_eq!(pacer.fps().map(f64::round), Some(60.0));
+
+        let mut unpaced = FramePacer::new(Duration::ZERO);
+        unpaced.increase_fps();
+        assert_eq!(unpaced.fps(), None);
+    }
+}
@@ -0,0 +1,289 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use crossterm::style::{Color, SetBackgroundColor, SetForegroundColor};
+use crossterm::Command;
+
+use falling_glyphs::game::AnsiColor;
+
+// The basic 16 colors as xterm draws them by default, which most terminals
+// come close to
+const BASIC: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+// Levels of each channel in the 256-color palette's 6x6x6 cube
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// Ordered dithering patterns, the order cells of a 2x2 or 4x4 block take
+// the second of two colors in as a mix moves from the first toward it
+const BAYER_2: [[u8; 2]; 2] = [[0, 2], [3, 1]];
+const BAYER_4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// How many colors the terminal can show
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorDepth {
+    #[value(name = "16")]
+    Ansi16,
+    #[value(name = "256")]
+    Ansi256,
+    Truecolor,
+}
+
+impl fmt::Display for ColorDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColorDepth::Ansi16 => "16",
+            ColorDepth::Ansi256 => "256",
+            ColorDepth::Truecolor => "truecolor",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// How colors between the basic ones are drawn at 16 colors: in the nearest
+// (off), or as a pattern of the two they lie between, the share of each
+// cell of a 2x2 (low) or 4x4 (high) block, so fades still read as fades
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Dither {
+    #[default]
+    Off,
+    Low,
+    High,
+}
+
+impl Dither {
+    // How far toward the second of two colors a mix has to be for a cell
+    // to be drawn in it, from 0 to 1
+    fn threshold(self, x: u16, y: u16) -> Option<f64> {
+        let (x, y) = (x as usize, y as usize);
+        match self {
+            Dither::Off => None,
+            Dither::Low => Some((BAYER_2[y % 2][x % 2] as f64 + 0.5) / 4.0),
+            Dither::High => Some((BAYER_4[y % 4][x % 4] as f64 + 0.5) / 16.0),
+        }
+    }
+}
+
+// Squared distance between two colors, weighted toward green as eyes are
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    2 * d(r1, r2) + 4 * d(g1, g2) + 3 * d(b1, b2)
+}
+
+// The nearest of the basic colors. A glyph drawn in black would vanish into
+// the background rather than look dim, so colors nearest black take the
+// dark basic color of their hue instead (dark grey, for grays).
+fn nearest_basic((r, g, b): (u8, u8, u8)) -> u8 {
+    let nearest = |rgb, indices: std::ops::Range<u8>| {
+        indices
+            .min_by_key(|index| distance(rgb, BASIC[*index as usize]))
+            .unwrap_or(0)
+    };
+    let index = nearest((r, g, b), 0..16);
+    if index != 0 {
+        return index;
+    }
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    if max - min < 16 {
+        return 8;
+    }
+    let scale = |channel: u8| (channel as u32 * 205 / max as u32) as u8;
+    nearest((scale(r), scale(g), scale(b)), 1..7)
+}
+
+// The nearest color of the 256-color palette's cube or gray ramp
+fn nearest_256((r, g, b): (u8, u8, u8)) -> u8 {
+    let level = |channel: u8| {
+        (0..6)
+            .min_by_key(|i| (CUBE_LEVELS[*i] as i32 - channel as i32).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+
+    // The ramp runs from 8 to 238 in steps of 10, skipping black and white
+    let mean = (r as u32 + g as u32 + b as u32) / 3;
+    let step = (mean.saturating_sub(3) / 10).min(23) as u8;
+    let gray = 8 + step * 10;
+
+    if distance((gray, gray, gray), (r, g, b)) < distance(cube, (r, g, b)) {
+        232 + step
+    } else {
+        16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8
+    }
+}
+
+// The basic colors a color lies between, the nearest and the one that mixed
+// with it comes closest, and how far the color is from the first to the
+// second (0 to 1). Black is left out, as nearest_basic leaves it out.
+fn basic_mix(rgb: (u8, u8, u8)) -> (u8, u8, f64) {
+    let first = nearest_basic(rgb);
+    let from = BASIC[first as usize];
+    let channels = |(r, g, b): (u8, u8, u8)| [r as f64, g as f64, b as f64];
+    let (a, c) = (channels(from), channels(rgb));
+    let weights = [2.0, 4.0, 3.0];
+    let mixes = (1..16).filter(|second| *second != first).map(|second| {
+        let b = channels(BASIC[second as usize]);
+        let dot = |u: [f64; 3], v: [f64; 3]| (0..3).map(|i| weights[i] * u[i] * v[i]).sum::<f64>();
+        let towards = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+        let offset = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+        let mix = (dot(offset, towards) / dot(towards, towards)).clamp(0.0, 1.0);
+        let mixed = |i: usize| (a[i] + towards[i] * mix).round() as u8;
+        let error = distance((mixed(0), mixed(1), mixed(2)), rgb);
+        (second, mix, error)
+    });
+    match mixes.min_by_key(|(_, _, error)| *error) {
+        Some((second, mix, _)) => (first, second, mix),
+        None => (first, first, 0.0),
+    }
+}
+
+// The index of the basic color a color is drawn in at 16 colors
+fn basic_index(color: AnsiColor) -> u8 {
+    match color {
+        AnsiColor::White => 15,
+        AnsiColor::Green => 10,
+        AnsiColor::DarkGreen => 2,
+        AnsiColor::Rgb(r, g, b) => nearest_basic((r, g, b)),
+    }
+}
+
+// The nearest color to a game color the terminal can show
+pub fn quantize(color: AnsiColor, depth: ColorDepth) -> Color {
+    match (color, depth) {
+        (color, ColorDepth::Ansi16) => Color::AnsiValue(basic_index(color)),
+        (AnsiColor::White, _) => Color::White,
+        (AnsiColor::Green, _) => Color::Green,
+        (AnsiColor::DarkGreen, _) => Color::DarkGreen,
+        (AnsiColor::Rgb(r, g, b), ColorDepth::Truecolor) => Color::Rgb { r, g, b },
+        (AnsiColor::Rgb(r, g, b), ColorDepth::Ansi256) => Color::AnsiValue(nearest_256((r, g, b))),
+    }
+}
+
+// The color a cell is drawn in, as quantize draws it but for colors
+// between the basic ones, which are dithered at 16 colors
+pub fn quantize_at(color: AnsiColor, depth: ColorDepth, dither: Dither, x: u16, y: u16) -> Color {
+    match (color, depth, dither.threshold(x, y)) {
+        (AnsiColor::Rgb(r, g, b), ColorDepth::Ansi16, Some(threshold)) => {
+            let (first, second, mix) = basic_mix((r, g, b));
+            Color::AnsiValue(if mix > threshold { second } else { first })
+        }
+        _ => quantize(color, depth),
+    }
+}
+
+// The index of a basic color, which crossterm names or numbers
+fn basic_color_index(color: Color) -> Option<u8> {
+    let index = match color {
+        Color::Black => 0,
+        Color::DarkRed => 1,
+        Color::DarkGreen => 2,
+        Color::DarkYellow => 3,
+        Color::DarkBlue => 4,
+        Color::DarkMagenta => 5,
+        Color::DarkCyan => 6,
+        Color::Grey => 7,
+        Color::DarkGrey => 8,
+        Color::Red => 9,
+        Color::Green => 10,
+        Color::Yellow => 11,
+        Color::Blue => 12,
+        Color::Magenta => 13,
+        Color::Cyan => 14,
+        Color::White => 15,
+        Color::AnsiValue(index) if index < 16 => index,
+        _ => return None,
+    };
+    Some(index)
+}
+
+// Sets the foreground color, for a terminal with the given depth. At 16
+// colors, basic colors are written with the original SGR codes, as 16-color
+// terminals needn't understand the 256-color ones crossterm writes for them.
+pub struct SetForeground(pub Color, pub ColorDepth);
+
+impl Command for SetForeground {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        match basic_color_index(self.0) {
+            Some(index) if self.1 == ColorDepth::Ansi16 => {
+                let code = if index < 8 { 30 + index } else { 82 + index };
+                write!(f, "\x1b[{}m", code)
+            }
+            _ => SetForegroundColor(self.0).write_ansi(f),
+        }
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        SetForegroundColor(self.0).execute_winapi()
+    }
+}
+
+// Sets the background color, for a terminal with the given depth, as
+// SetForeground sets the foreground
+pub struct SetBackground(pub Color, pub ColorDepth);
+
+impl Command for SetBackground {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        match basic_color_index(self.0) {
+            Some(index) if self.1 == ColorDepth::Ansi16 => {
+                let code = if index < 8 { 40 + index } else { 92 + index };
+                write!(f, "\x1b[{}m", code)
+            }
+            _ => SetBackgroundColor(self.0).write_ansi(f),
+        }
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        SetBackgroundColor(self.0).execute_winapi()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The colors a 4x4 block of cells is drawn in
+    fn block(color: AnsiColor, dither: Dither) -> Vec<Color> {
+        (0..4)
+            .flat_map(|y| (0..4).map(move |x| (x, y)))
+            .map(|(x, y)| quantize_at(color, ColorDepth::Ansi16, dither, x, y))
+            .collect()
+    }
+
+    #[test]
+    fn colors_between_basic_ones_are_dithered_in_proportion() {
+        // Halfway between the green (10) and dark green (2) of a fading trail
+        let halfway = AnsiColor::Rgb(0, 230, 0);
+        assert!(block(halfway, Dither::Off).iter().all(|c| *c == block(halfway, Dither::Off)[0]));
+        for dither in [Dither::Low, Dither::High] {
+            let cells = block(halfway, dither);
+            let green = cells.iter().filter(|c| **c == Color::AnsiValue(10)).count();
+            let dark = cells.iter().filter(|c| **c == Color::AnsiValue(2)).count();
+            assert_eq!((green, dark), (8, 8), "{:?}", dither);
+        }
+        // Basic colors are drawn as they are
+        let green = AnsiColor::Rgb(0, 255, 0);
+        assert!(block(green, Dither::High).iter().all(|c| *c == Color::AnsiValue(10)));
+        // Other depths aren't dithered
+        let color = quantize_at(halfway, ColorDepth::Truecolor, Dither::High, 1, 0);
+        assert_eq!(color, Color::Rgb { r: 0, g: 230, b: 0 });
+    }
+}
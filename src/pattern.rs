@@ -0,0 +1,241 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::str::FromStr;
+use std::time::Duration;
+
+// What a rule of a spawn pattern spawns
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpawnAction {
+    // A stack in a random column, or in the given one
+    Stack(Option<u16>),
+    // A stack in every column at once
+    Wave,
+    // A stack raining the text's characters
+    Message(String),
+}
+
+// When a rule spawns, in simulated time since the pattern started
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum When {
+    Every(Duration),
+    At(Duration),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub when: When,
+    pub action: SpawnAction,
+}
+
+// A choreography of spawns, written as rules separated by semicolons, like
+// `every 0.5s at random; every 5s wave; at 30s message "HELLO"`. Each rule is
+// `every <time>` or `at <time>` followed by an action: `at random`,
+// `at column <n>`, `wave` or `message "<text>"`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpawnPattern {
+    rules: Vec<Rule>,
+}
+
+impl SpawnPattern {
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+}
+
+impl FromStr for SpawnPattern {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut rules = Vec::new();
+        for rule in split_rules(text)? {
+            let (keyword, rest) = next_word(&rule);
+            let (time, action) = next_word(rest);
+            let time = parse_duration(time)?;
+            let when = match keyword {
+                "every" if time.is_zero() => {
+                    return Err(format!("`{}` repeats without any time between", rule))
+                }
+                "every" => When::Every(time),
+                "at" => When::At(time),
+                _ => return Err(format!("`{}` doesn't start with `every` or `at`", rule)),
+            };
+            rules.push(Rule {
+                when,
+                action: parse_action(action)?,
+            });
+        }
+        Ok(Self { rules })
+    }
+}
+
+// Splits the rules apart at the semicolons outside quotes
+fn split_rules(text: &str) -> Result<Vec<String>, String> {
+    let mut rules = vec![String::new()];
+    let mut quoted = false;
+    for ch in text.chars() {
+        match ch {
+            ';' if !quoted => rules.push(String::new()),
+            _ => {
+                quoted ^= ch == '"';
+                rules.last_mut().unwrap().push(ch);
+            }
+        }
+    }
+    if quoted {
+        return Err("a message's quotes aren't closed".to_string());
+    }
+    Ok(rules
+        .into_iter()
+        .map(|rule| rule.trim().to_string())
+        .filter(|rule| !rule.is_empty())
+        .collect())
+}
+
+fn next_word(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    (&text[..end], text[end..].trim_start())
+}
+
+fn parse_action(text: &str) -> Result<SpawnAction, String> {
+    let (word, rest) = next_word(text);
+    match (word, next_word(rest)) {
+        ("at", ("random", "")) => Ok(SpawnAction::Stack(None)),
+        ("at", ("column", column)) => column
+            .parse()
+            .map(|column| SpawnAction::Stack(Some(column)))
+            .map_err(|_| format!("`{}` isn't a column", column)),
+        ("wave", ("", "")) => Ok(SpawnAction::Wave),
+        ("message", _) => rest
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+            .map(|message| SpawnAction::Message(message.to_string()))
+            .ok_or_else(|| format!("`{}` isn't a message in quotes", rest)),
+        _ => Err(format!(
+            "`{}` isn't an action (use `at random`, `at column <n>`, `wave` or `message \"<text>\"`)",
+            text
+        )),
+    }
+}
+
+// Parses a time like `1500ms`, `30s`, `2m` or `1h`; bare numbers are seconds
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("`{value}` isn't a duration"))?;
+    let seconds = match unit {
+        "" | "s" => number,
+        "ms" => number / 1000.0,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return Err(format!("`{unit}` isn't a unit of time (use ms, s, m or h)")),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("`{value}` is too long"))
+}
+
+// The spawns of a pattern still to come, in the order they're due
+pub struct SpawnQueue {
+    pattern: SpawnPattern,
+    elapsed: Duration,
+    due: BinaryHeap<Reverse<(Duration, usize)>>, // when, and the rule
+}
+
+impl SpawnQueue {
+    pub fn new(pattern: SpawnPattern) -> Self {
+        let due = pattern
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(rule, Rule { when, .. })| match when {
+                When::Every(period) => Reverse((*period, rule)),
+                When::At(at) => Reverse((*at, rule)),
+            })
+            .collect();
+        Self {
+            pattern,
+            elapsed: Duration::ZERO,
+            due,
+        }
+    }
+
+    pub fn pattern(&self) -> &SpawnPattern {
+        &self.pattern
+    }
+
+    // Advances the pattern by dt, returning the actions that came due, in
+    // order. Repeating rules come due as many times as they fit in dt.
+    pub fn update(&mut self, dt: Duration) -> Vec<SpawnAction> {
+        self.elapsed += dt;
+        let mut actions = Vec::new();
+        while let Some(Reverse((at, rule))) = self.due.peek().copied() {
+            if at > self.elapsed {
+                break;
+            }
+            self.due.pop();
+            let Rule { when, action } = &self.pattern.rules[rule];
+            if let When::Every(period) = when {
+                self.due.push(Reverse((at + *period, rule)));
+            }
+            actions.push(action.clone());
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_parse_into_rules() {
+        let pattern: SpawnPattern =
+            "every 0.5s at random; every 5s wave; at 30s message \"HI; there\""
+                .parse()
+                .unwrap();
+        assert_eq!(
+            pattern.rules(),
+            [
+                Rule {
+                    when: When::Every(Duration::from_millis(500)),
+                    action: SpawnAction::Stack(None),
+                },
+                Rule {
+                    when: When::Every(Duration::from_secs(5)),
+                    action: SpawnAction::Wave,
+                },
+                Rule {
+                    when: When::At(Duration::from_secs(30)),
+                    action: SpawnAction::Message("HI; there".to_string()),
+                },
+            ]
+        );
+        assert!("every 0s wave".parse::<SpawnPattern>().is_err());
+        assert!("sometimes wave".parse::<SpawnPattern>().is_err());
+        assert!("at 1s message \"HI".parse::<SpawnPattern>().is_err());
+        assert!("at 1s explode".parse::<SpawnPattern>().is_err());
+    }
+
+    #[test]
+    fn queued_spawns_come_due_in_order_as_time_passes() {
+        let pattern = "every 1s at column 4; at 2500ms wave".parse().unwrap();
+        let mut queue = SpawnQueue::new(pattern);
+        assert!(queue.update(Duration::from_millis(900)).is_empty());
+        assert_eq!(
+            queue.update(Duration::from_millis(100)),
+            [SpawnAction::Stack(Some(4))]
+        );
+        assert_eq!(
+            queue.update(Duration::from_secs(2)),
+            [
+                SpawnAction::Stack(Some(4)),
+                SpawnAction::Wave,
+                SpawnAction::Stack(Some(4))
+            ]
+        );
+    }
+}
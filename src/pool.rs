@@ -0,0 +1,46 @@
+use std::collections::VecDeque;
+
+use crate::game::Glyph;
+use crate::telemetry::PoolStats;
+
+// Most buffers kept for reuse, past which despawned stacks' are freed
+const MAX_POOLED: usize = 1024;
+
+// The glyph buffers of despawned stacks, handed to stacks spawned later so
+// dense rain churning through stacks doesn't churn through the allocator.
+// Buffers keep their capacity, so once the pool has warmed up most stacks
+// spawn without allocating at all.
+#[derive(Default)]
+pub(crate) struct StackPool {
+    buffers: Vec<VecDeque<Glyph>>,
+    stats: PoolStats,
+}
+
+impl StackPool {
+    // An empty buffer with room for the given number of glyphs
+    pub(crate) fn take(&mut self, capacity: usize) -> VecDeque<Glyph> {
+        let mut buffer = self.buffers.pop().unwrap_or_default();
+        if buffer.capacity() >= capacity {
+            self.stats.reused += 1;
+        } else {
+            self.stats.allocated += 1;
+            buffer.reserve_exact(capacity);
+        }
+        buffer
+    }
+
+    // Keeps a despawned stack's buffer for reuse, while there's room
+    pub(crate) fn give(&mut self, mut buffer: VecDeque<Glyph>) {
+        if self.buffers.len() < MAX_POOLED && buffer.capacity() > 0 {
+            buffer.clear();
+            self.buffers.push(buffer);
+        }
+    }
+
+    pub(crate) fn stats(&self) -> PoolStats {
+        PoolStats {
+            pooled: self.buffers.len(),
+            ..self.stats
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use falling_glyphs::game::AnsiColor;
+
+// How often the config file is checked for changes
+const CHECK_EVERY: Duration = Duration::from_secs(1);
+
+// Colors of the captions saying the config was reloaded, or why it wasn't
+pub const RELOADED_COLOR: AnsiColor = AnsiColor::Rgb(0, 200, 255);
+pub const INVALID_COLOR: AnsiColor = AnsiColor::Rgb(255, 80, 80);
+
+// Watches the config file for changes while the rain runs, by checking when
+// it was last modified every so often
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+fn modified(path: &PathBuf) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            modified: modified(&path),
+            path,
+            last_check: Instant::now(),
+        }
+    }
+
+    // Whether the file has changed (or appeared) since it was last seen
+    pub fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < CHECK_EVERY {
+            return false;
+        }
+        self.last_check = Instant::now();
+        let modified = modified(&self.path);
+        let changed = modified.is_some() && modified != self.modified;
+        self.modified = modified;
+        changed
+    }
+
+    // Takes the file as it is now as seen, like after saving to it
+    pub fn skip(&mut self) {
+        self.modified = modified(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_are_seen_once_and_saves_can_be_skipped() {
+        let path = std::env::temp_dir().join(format!("falling_glyphs-{}.toml", std::process::id()));
+        let mut watcher = ConfigWatcher::new(path.clone());
+        let check = |watcher: &mut ConfigWatcher| {
+            watcher.last_check -= CHECK_EVERY;
+            watcher.changed()
+        };
+        assert!(!check(&mut watcher));
+        fs::write(&path, "[keys]\n").unwrap();
+        assert!(check(&mut watcher));
+        assert!(!check(&mut watcher));
+
+        let later = SystemTime::now() + Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        watcher.skip();
+        assert!(!check(&mut watcher));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Sender};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crossterm::cursor::{Hide, Show};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::QueueableCommand;
+
+// How long to wait for the terminal to take the restoring escape codes
+// before giving up on a stuck terminal
+const RESTORE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// How often to check whether the writer has caught up
+const IDLE_POLL: Duration = Duration::from_micros(50);
+
+// Writes frames to stdout on a thread of its own, so a terminal that stops
+// reading (a dead SSH connection, a stopped pager) can't hang the animation.
+// The main loop watches for writes that never finish.
+pub struct Renderer {
+    frames: Option<Sender<Vec<u8>>>,
+    writing_since: Arc<Mutex<Option<Instant>>>,
+    queued: Arc<AtomicUsize>, // frames submitted but not yet written
+    writer: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl Renderer {
+    pub fn spawn() -> Self {
+        let (frames, rx) = mpsc::channel::<Vec<u8>>();
+        let writing_since = Arc::new(Mutex::new(None));
+        let since = writing_since.clone();
+        let queued = Arc::new(AtomicUsize::new(0));
+        let written = queued.clone();
+        let writer = thread::spawn(move || {
+            let mut stdout = io::stdout();
+            for frame in rx {
+                *since.lock().unwrap() = Some(Instant::now());
+                stdout.write_all(&frame)?;
+                stdout.flush()?;
+                *since.lock().unwrap() = None;
+                written.fetch_sub(1, Ordering::SeqCst);
+            }
+            Ok(())
+        });
+        Self {
+            frames: Some(frames),
+            writing_since,
+            queued,
+            writer: Some(writer),
+        }
+    }
+
+    // Queues a frame of escape codes and text for the writer
+    pub fn submit(&mut self, frame: Vec<u8>) -> io::Result<()> {
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        let sent = self
+            .frames
+            .as_ref()
+            .is_some_and(|frames| frames.send(frame).is_ok());
+        if sent {
+            Ok(())
+        } else {
+            // The writer only stops early when a write fails
+            self.finish()?;
+            Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the terminal writer stopped",
+            ))
+        }
+    }
+
+    // How long the writer has been stuck in its current write, if it is writing
+    pub fn stalled_for(&self) -> Duration {
+        self.writing_since
+            .lock()
+            .unwrap()
+            .map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    // Waits for the terminal to take every queued frame, giving up if a
+    // write stalls for the timeout (zero waits indefinitely)
+    pub fn wait_idle(&self, timeout: Duration) {
+        let writing = || self.writer.as_ref().is_some_and(|writer| !writer.is_finished());
+        while self.queued.load(Ordering::SeqCst) > 0 && writing() {
+            if !timeout.is_zero() && self.stalled_for() >= timeout {
+                return;
+            }
+            thread::sleep(IDLE_POLL);
+        }
+    }
+
+    // Hands the terminal back to the shell and stops, as Ctrl-Z does outside
+    // raw mode, then takes it over again once resumed, for the caller to
+    // repaint (on the alternate screen, cleared, if it was drawing on one)
+    #[cfg(unix)]
+    pub fn suspend(&self, alternate_screen: bool) -> io::Result<()> {
+        self.wait_idle(RESTORE_TIMEOUT);
+        let mut stdout = io::stdout();
+        stdout.queue(Show)?;
+        if alternate_screen {
+            stdout.queue(LeaveAlternateScreen)?;
+        }
+        stdout.flush()?;
+        terminal::disable_raw_mode()?;
+        // SAFETY: raising a signal has no preconditions; SIGTSTP stops the
+        // process until the shell continues it
+        unsafe { libc::raise(libc::SIGTSTP) };
+        terminal::enable_raw_mode()?;
+        if alternate_screen {
+            stdout
+                .queue(EnterAlternateScreen)?
+                .queue(Clear(ClearType::All))?;
+        }
+        stdout.queue(Hide)?.flush()
+    }
+
+    // Waits for every queued frame to be written
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.frames = None;
+        match self.writer.take().map(|writer| writer.join()) {
+            Some(Ok(result)) => result,
+            Some(Err(_)) => Err(io::Error::other("the terminal writer panicked")),
+            None => Ok(()),
+        }
+    }
+}
+
+// Gives up on a terminal that has stopped reading: leaves raw mode (which
+// doesn't need the terminal to read anything), tries briefly to restore the
+// screen, and exits.
+pub fn abandon_terminal(stalled: Duration) -> ! {
+    let _ = terminal::disable_raw_mode();
+    let (done, restored) = mpsc::channel();
+    thread::spawn(move || {
+        eprintln!(
+            "falling_glyphs: the terminal stopped reading for {:.1}s, exiting",
+            stalled.as_secs_f64()
+        );
+        let mut stdout = io::stdout();
+        let _ = stdout.queue(Show).and_then(|s| s.queue(LeaveAlternateScreen));
+        let _ = stdout.flush();
+        let _ = done.send(());
+    });
+    let _ = restored.recv_timeout(RESTORE_TIMEOUT);
+    std::process::exit(1);
+}
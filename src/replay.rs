@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use falling_glyphs::game::AnsiColor;
+
+// How often a frame repaints the whole screen, for a replay to start from
+const KEYFRAME_EVERY: Duration = Duration::from_secs(2);
+
+// Color of the caption saying where a replay was saved
+pub const CAPTION_COLOR: AnsiColor = AnsiColor::Rgb(255, 200, 0);
+
+// A frame as written to the terminal
+struct Frame {
+    at: Instant,
+    keyframe: bool,
+    bytes: Vec<u8>,
+}
+
+// The frames drawn over the last stretch of a run, to save as an asciinema
+// recording after something worth keeping happens, like a DVR. Frames only
+// draw what changed, so every so often one repaints the screen in full, and
+// a replay plays from the newest of those before the stretch it covers.
+pub struct ReplayBuffer {
+    length: Duration,
+    frames: VecDeque<Frame>,
+    last_keyframe: Option<Instant>,
+}
+
+impl ReplayBuffer {
+    pub fn new(length: Duration) -> Self {
+        Self {
+            length,
+            frames: VecDeque::new(),
+            last_keyframe: None,
+        }
+    }
+
+    // Whether the next frame should repaint the whole screen
+    pub fn wants_keyframe(&self, now: Instant) -> bool {
+        self.last_keyframe
+            .is_none_or(|keyframe| now - keyframe >= KEYFRAME_EVERY)
+    }
+
+    // Keeps a frame, dropping those too old to be replayed. Until the first
+    // keyframe, there's nothing to replay from.
+    pub fn push(&mut self, at: Instant, keyframe: bool, bytes: &[u8]) {
+        if keyframe {
+            self.last_keyframe = Some(at);
+        } else if self.last_keyframe.is_none() {
+            return;
+        }
+        self.frames.push_back(Frame {
+            at,
+            keyframe,
+            bytes: bytes.to_vec(),
+        });
+        let Some(cutoff) = at.checked_sub(self.length) else {
+            return;
+        };
+        let start = self
+            .frames
+            .iter()
+            .rposition(|frame| frame.keyframe && frame.at <= cutoff);
+        if let Some(start) = start {
+            self.frames.drain(..start);
+        }
+    }
+
+    // Forgets every frame, like when the screen is resized and they no
+    // longer fit it
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.last_keyframe = None;
+    }
+
+    // Writes the frames as an asciicast of the given screen size, leaving
+    // out the time before the stretch kept, and returns how long it plays for
+    pub fn save(&self, path: &Path, width: u16, height: u16) -> io::Result<Duration> {
+        let Some(last) = self.frames.back() else {
+            return Err(io::Error::other("no frames drawn yet"));
+        };
+        let start = last
+            .at
+            .checked_sub(self.length)
+            .unwrap_or(self.frames[0].at);
+        let frames = self.frames.iter().map(|frame| {
+            let time = frame.at.saturating_duration_since(start);
+            (time, String::from_utf8_lossy(&frame.bytes))
+        });
+        write_asciicast(path, (width, height), frames)?;
+        Ok(last.at.saturating_duration_since(start))
+    }
+}
+
+// Writes frames, each with the time into the recording it's drawn at, as an
+// asciicast (v2) of the given screen size, on a clear screen with the cursor
+// hidden as the run hides it
+pub fn write_asciicast<S: AsRef<str>>(
+    path: &Path,
+    (width, height): (u16, u16),
+    frames: impl IntoIterator<Item = (Duration, S)>,
+) -> io::Result<()> {
+    let mut text = format!(
+        "{{\"version\": 2, \"width\": {}, \"height\": {}}}\n",
+        width, height
+    );
+    text.push_str(&format!(
+        "{}\n",
+        serde_json::json!([0.0, "o", "\x1b[?25l\x1b[2J"])
+    ));
+    for (time, output) in frames {
+        let event = serde_json::json!([time.as_secs_f64(), "o", output.as_ref()]);
+        text.push_str(&format!("{}\n", event));
+    }
+    fs::write(path, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_start_from_the_last_keyframe_before_the_stretch_kept() {
+        let start = Instant::now();
+        let mut replay = ReplayBuffer::new(Duration::from_secs(5));
+        assert!(replay.wants_keyframe(start));
+        replay.push(start, false, b"lost");
+        for second in 0..12 {
+            let at = start + Duration::from_secs(second);
+            let keyframe = replay.wants_keyframe(at);
+            replay.push(at, keyframe, second.to_string().as_bytes());
+        }
+        // Keyframes at 0, 2, ..., 10 s: 6 s is the last before 11 - 5 s
+        let kept: Vec<&[u8]> = replay.frames.iter().map(|f| f.bytes.as_slice()).collect();
+        assert_eq!(kept.first(), Some(&&b"6"[..]));
+        assert_eq!(kept.len(), 6);
+
+        let path = std::env::temp_dir().join(format!("falling_glyphs-{}.cast", std::process::id()));
+        assert_eq!(replay.save(&path, 80, 24).unwrap(), Duration::from_secs(5));
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], r#"{"version": 2, "width": 80, "height": 24}"#);
+        assert_eq!(lines[2], r#"[0.0,"o","6"]"#);
+        assert_eq!(lines[7], r#"[5.0,"o","11"]"#);
+    }
+}
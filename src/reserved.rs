@@ -0,0 +1,96 @@
+use std::str::FromStr;
+
+use crate::game::{display_width, Viewport};
+
+// Rows and columns along the edges of the screen that the rain never draws
+// in, left free for status bars and other widgets drawn by another program
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reserved {
+    pub top: u16,
+    pub bottom: u16,
+    pub left: u16,
+    pub right: u16,
+}
+
+impl Reserved {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn contains(&self, x: u16, y: u16, width: u16, height: u16) -> bool {
+        x < self.left
+            || x >= width.saturating_sub(self.right)
+            || y < self.top
+            || y >= height.saturating_sub(self.bottom)
+    }
+
+    // Clears the reserved cells, along with any wide glyph spilling into them
+    // from the column beside them
+    pub fn apply(&self, view: &mut Viewport) {
+        let (width, height) = (view.width(), view.height());
+        for y in 0..height {
+            for x in 0..width {
+                let spills = view
+                    .get(x, y)
+                    .is_some_and(|cell| display_width(cell.ch) > 1)
+                    && self.contains(x + 1, y, width, height);
+                if spills || self.contains(x, y, width, height) {
+                    view.clear(x, y);
+                }
+            }
+        }
+    }
+}
+
+// Parses a list of edges and sizes, like `top=1,right=2`
+impl FromStr for Reserved {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut reserved = Self::default();
+        for part in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (edge, size) = part
+                .split_once('=')
+                .ok_or_else(|| format!("`{part}` isn't an edge and size, like `top=1`"))?;
+            let size: u16 = size
+                .trim()
+                .parse()
+                .map_err(|_| format!("`{size}` isn't a number of rows or columns"))?;
+            match edge.trim() {
+                "top" => reserved.top = size,
+                "bottom" => reserved.bottom = size,
+                "left" => reserved.left = size,
+                "right" => reserved.right = size,
+                edge => {
+                    return Err(format!(
+                        "`{edge}` isn't an edge (use top, bottom, left or right)"
+                    ))
+                }
+            }
+        }
+        Ok(reserved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserved_edges_parse_from_a_list_of_sizes() {
+        let reserved: Reserved = "top=1, right=2,".parse().unwrap();
+        assert_eq!(
+            reserved,
+            Reserved {
+                top: 1,
+                right: 2,
+                ..Reserved::default()
+            }
+        );
+        assert!(reserved.contains(79, 5, 80, 24) && !reserved.contains(77, 5, 80, 24));
+        assert_eq!("".parse::<Reserved>(), Ok(Reserved::default()));
+        assert!("top".parse::<Reserved>().is_err());
+        assert!("top=-1".parse::<Reserved>().is_err());
+        assert!("middle=1".parse::<Reserved>().is_err());
+    }
+}
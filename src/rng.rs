@@ -0,0 +1,47 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Independent random streams seeded from one master seed, so drawing more or
+// fewer numbers from one (e.g. picking glyphs from another charset) doesn't
+// change what the others produce
+pub struct RngStreams {
+    seed: u64,
+    // Where and when stacks spawn, and their lengths and timing
+    pub spawn: StdRng,
+    // Which glyphs are drawn
+    pub glyphs: StdRng,
+    // When glyphs mutate, and which ones
+    pub mutation: StdRng,
+    // Lightning, and the flicker of the overlays
+    pub effects: StdRng,
+}
+
+impl RngStreams {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            spawn: stream(seed, 1),
+            glyphs: stream(seed, 2),
+            mutation: stream(seed, 3),
+            effects: stream(seed, 4),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // How far each stream has got, told by the next number it would draw
+    // (drawn from a copy, leaving the stream where it is)
+    pub fn positions(&self) -> [u64; 4] {
+        [&self.spawn, &self.glyphs, &self.mutation, &self.effects].map(|rng| rng.clone().random())
+    }
+}
+
+// Seeds a stream from the master seed mixed with the stream's id (SplitMix64)
+fn stream(seed: u64, id: u64) -> StdRng {
+    let mut z = seed.wrapping_add(id.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    StdRng::seed_from_u64(z ^ (z >> 31))
+}
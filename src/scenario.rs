@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use crate::game::{Game, Rect, Settings, Viewport};
+
+// Ticks taken by scenarios are at most this long (in units of 4ms)
+const MAX_TICK: u64 = 255 * 4;
+
+// Something done to a running game, as a key press or a resize would
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Tick(Duration),
+    Step(Duration),
+    Resize(u16, u16),
+    IncreaseDensity,
+    DecreaseDensity,
+    IncreaseStackHeight,
+    DecreaseStackHeight,
+    IncreaseSpeed,
+    DecreaseSpeed,
+    Pan(i32),
+    ZoomIn,
+    ZoomOut,
+    CycleEffect,
+    CycleCharset,
+    CycleMirror,
+    ToggleFastForward,
+    TogglePause,
+    ToggleHeatMap,
+    Type(char),
+    StrikeLightning,
+    ToggleSpotlight,
+    MoveSpotlight(i16),
+    Repaint,
+}
+
+// A run of the game from a given size and seed through a sequence of ops,
+// decoded from arbitrary bytes so fuzzers can drive it. Every sequence of
+// bytes is a scenario, if mostly one of ticks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scenario {
+    pub width: u16,
+    pub height: u16,
+    pub pages: u16,
+    pub seed: u64,
+    pub scroll_rendering: bool,
+    pub ops: Vec<Op>,
+}
+
+impl Scenario {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut bytes = bytes.iter().copied();
+        let mut next = move || bytes.next();
+        let width = next().unwrap_or(80) as u16;
+        let height = next().unwrap_or(24) as u16 % 100;
+        let flags = next().unwrap_or(0);
+        let seed = (0..8).fold(0, |seed, _| seed << 8 | next().unwrap_or(0) as u64);
+        let mut ops = Vec::new();
+        while let Some(op) = next() {
+            let mut arg = || next().unwrap_or(0);
+            ops.push(match op % 24 {
+                0..=3 => Op::Tick(Duration::from_millis(arg() as u64 * 4)),
+                4 => Op::Step(Duration::from_millis(arg() as u64 * 4)),
+                5 => Op::Resize(arg() as u16, arg() as u16 % 100),
+                6 => Op::IncreaseDensity,
+                7 => Op::DecreaseDensity,
+                8 => Op::IncreaseStackHeight,
+                9 => Op::DecreaseStackHeight,
+                10 => Op::IncreaseSpeed,
+                11 => Op::DecreaseSpeed,
+                12 => Op::Pan(arg() as i8 as i32),
+                13 => Op::ZoomIn,
+                14 => Op::ZoomOut,
+                15 => Op::CycleEffect,
+                16 => Op::CycleCharset,
+                17 => Op::CycleMirror,
+                18 => Op::ToggleFastForward,
+                19 => Op::TogglePause,
+                20 => Op::ToggleHeatMap,
+                21 => Op::Type(char::from(arg())),
+                22 => match arg() % 4 {
+                    0 => Op::StrikeLightning,
+                    1 => Op::ToggleSpotlight,
+                    _ => Op::MoveSpotlight(arg() as i8 as i16),
+                },
+                _ => Op::Repaint,
+            });
+        }
+        Self {
+            width,
+            height,
+            pages: 1 + (flags & 3) as u16,
+            seed,
+            scroll_rendering: flags & 4 != 0,
+            ops,
+        }
+    }
+
+    // Runs the scenario, drawing the changes each update hands out onto a
+    // screen of its own as a terminal would, and checks that no change lands
+    // off the screen and that the screen drawn always matches the view
+    pub fn run(&self) -> Result<(), String> {
+        let mut game = Game::new(self.width, self.height, Settings::default());
+        game.set_seed(self.seed);
+        game.set_pages(self.pages);
+        game.set_scroll_rendering(self.scroll_rendering);
+        let mut screen = Viewport::new(self.width, self.height);
+        for (at, op) in self.ops.iter().enumerate() {
+            let changes = match *op {
+                Op::Tick(dt) => {
+                    game.update_and_get_changes(dt.min(Duration::from_millis(MAX_TICK)))
+                }
+                Op::Step(dt) => game.step_and_get_changes(dt.min(Duration::from_millis(MAX_TICK))),
+                Op::Resize(width, height) => {
+                    game.resize(width, height);
+                    screen = Viewport::new(width, height);
+                    game.full_repaint()
+                }
+                Op::Repaint => game.full_repaint(),
+                op => {
+                    apply(&mut game, op);
+                    continue;
+                }
+            };
+            if let Some(scroll) = game.scroll() {
+                screen.scroll(scroll);
+            }
+            let (width, height) = game.get_dimensions();
+            for change in &changes {
+                let (x, y) = change.position();
+                if x >= width || y >= height {
+                    return Err(format!(
+                        "op {} ({:?}) changed ({}, {}) off a {}x{} screen",
+                        at, op, x, y, width, height
+                    ));
+                }
+                screen.apply(change);
+            }
+            let mut expected = Viewport::new(width, height);
+            for change in game.repaint_region(Rect::new(0, 0, width, height)) {
+                expected.apply(&change);
+            }
+            if screen.as_drawn().fingerprint() != expected.fingerprint() {
+                return Err(format!(
+                    "op {} ({:?}) left the screen drawn unlike the view",
+                    at, op
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Does what a key bound to the op would
+fn apply(game: &mut Game, op: Op) {
+    match op {
+        Op::IncreaseDensity => game.increase_density(),
+        Op::DecreaseDensity => game.decrease_density(),
+        Op::IncreaseStackHeight => game.increase_max_stack_height(),
+        Op::DecreaseStackHeight => game.decrease_max_stack_height(),
+        Op::IncreaseSpeed => game.increase_speed(),
+        Op::DecreaseSpeed => game.decrease_speed(),
+        Op::Pan(rows) => game.pan(rows),
+        Op::ZoomIn => game.zoom_in(),
+        Op::ZoomOut => game.zoom_out(),
+        Op::CycleEffect => game.cycle_effect(),
+        Op::CycleCharset => game.cycle_charset(),
+        Op::CycleMirror => game.cycle_mirror(),
+        Op::ToggleFastForward => game.toggle_fast_forward(),
+        Op::TogglePause => game.toggle_pause(),
+        Op::ToggleHeatMap => game.toggle_heat_map(),
+        Op::Type(key) => game.record_key(key),
+        Op::StrikeLightning => game.strike_lightning(),
+        Op::ToggleSpotlight => game.toggle_spotlight(None),
+        Op::MoveSpotlight(delta) => game.move_spotlight(delta),
+        Op::Tick(_) | Op::Step(_) | Op::Resize(..) | Op::Repaint => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn random_scenarios_draw_the_view_and_stay_on_screen() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let len = rng.random_range(0..200);
+            let bytes: Vec<u8> = (0..len).map(|_| rng.random()).collect();
+            let scenario = Scenario::from_bytes(&bytes);
+            if let Err(e) = scenario.run() {
+                panic!("{}: {:?}", e, bytes);
+            }
+        }
+    }
+}
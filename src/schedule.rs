@@ -0,0 +1,104 @@
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Minutes past the hour something happens at, like the minute field of a
+// crontab: `0` for on the hour, `0,30`, or `*/15` for every quarter hour
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,              // a bit for each minute of the hour
+    last_checked: Option<u64>, // the minute since the epoch last checked
+}
+
+impl Schedule {
+    // The local time, as an hour and minute, if it's one the schedule is due
+    // at and the schedule hasn't been checked already this minute. The
+    // minute the schedule's first checked in is passed over, so starting
+    // partway through a minute on the schedule doesn't count as it coming up.
+    pub fn due(&mut self, now: SystemTime) -> Option<(u8, u8)> {
+        let secs = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let minute = secs / 60;
+        let first = self.last_checked.is_none();
+        if self.last_checked.replace(minute) == Some(minute) || first {
+            return None;
+        }
+        let (hour, minute) = local_time(secs);
+        (self.minutes & 1 << minute != 0).then_some((hour, minute))
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut minutes = 0;
+        for part in text.split(',').map(str::trim) {
+            let minute = |text: &str| {
+                text.parse::<u64>()
+                    .ok()
+                    .filter(|minute| *minute < 60)
+                    .ok_or_else(|| format!("`{text}` isn't a minute past the hour (0 to 59)"))
+            };
+            match part.strip_prefix("*/") {
+                Some(step) => {
+                    let step = minute(step)?.max(1);
+                    minutes |= (0..60)
+                        .step_by(step as usize)
+                        .fold(0, |bits, m| bits | 1 << m);
+                }
+                None => minutes |= 1 << minute(part)?,
+            }
+        }
+        Ok(Self {
+            minutes,
+            last_checked: None,
+        })
+    }
+}
+
+// The hour and minute of the local time at the seconds since the epoch
+#[cfg(unix)]
+fn local_time(secs: u64) -> (u8, u8) {
+    let time = secs as libc::time_t;
+    // SAFETY: localtime_r only writes the tm it's given, which is plain data
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return utc_time(secs);
+    }
+    (tm.tm_hour as u8, tm.tm_min as u8)
+}
+
+// Without the time zone to hand, the time is kept in UTC
+#[cfg(not(unix))]
+fn local_time(secs: u64) -> (u8, u8) {
+    utc_time(secs)
+}
+
+fn utc_time(secs: u64) -> (u8, u8) {
+    ((secs / 3600 % 24) as u8, (secs / 60 % 60) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn schedules_parse_like_a_crontab_minute_field() {
+        let every_quarter: Schedule = "*/15".parse().unwrap();
+        assert_eq!(every_quarter.minutes, 1 | 1 << 15 | 1 << 30 | 1 << 45);
+        let twice: Schedule = "0, 30".parse().unwrap();
+        assert_eq!(twice.minutes, 1 | 1 << 30);
+        assert!("60".parse::<Schedule>().is_err());
+        assert!("*/x".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn schedules_are_due_once_in_each_minute_on_them() {
+        let mut schedule: Schedule = "*/1".parse().unwrap();
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(schedule.due(start), None);
+        let next = start + Duration::from_secs(60);
+        assert!(schedule.due(next).is_some());
+        assert_eq!(schedule.due(next + Duration::from_secs(1)), None);
+    }
+}
@@ -0,0 +1,77 @@
+use std::env;
+use std::ffi::OsString;
+use std::io;
+use std::time::Duration;
+
+use falling_glyphs::vt::{self, Screen};
+
+use crate::cli::SelftestArgs;
+
+// Size of the pseudo terminal the rain runs in
+pub const COLUMNS: u16 = 80;
+pub const ROWS: u16 = 24;
+
+// How long past its duration the run may take to exit before it's killed
+const EXIT_GRACE: Duration = Duration::from_secs(10);
+
+// Private modes that have to be off again when the run exits: the cursor
+// hidden (25 on means shown), the alternate screen and synchronized output
+const RESTORED_MODES: [(u16, bool); 3] = [(25, true), (1049, false), (2026, false)];
+
+// The options given before a subcommand, for the runs it makes
+pub fn run_args(subcommand: &str) -> Vec<OsString> {
+    env::args_os()
+        .skip(1)
+        .take_while(|arg| arg != subcommand)
+        .collect()
+}
+
+// Modes left on (or off) that a terminal would be stuck with
+fn unrestored(screen: &Screen) -> Vec<String> {
+    RESTORED_MODES
+        .iter()
+        .filter(|(mode, restored)| screen.mode(*mode).is_some_and(|on| on != *restored))
+        .map(|(mode, _)| format!("?{}", mode))
+        .collect()
+}
+
+// Runs the rain in a pseudo terminal for a few seconds, as a terminal would
+// see it, and checks what it wrote: only escape codes the renderer is known
+// to write, nothing drawn off the screen, and the cursor and screen restored
+// on exit. Options given before the subcommand apply to the run.
+pub fn run(args: &SelftestArgs) -> io::Result<()> {
+    let failed = |message: String| io::Error::other(format!("selftest failed: {}", message));
+
+    let mut run_args = run_args("selftest");
+    run_args.push("--duration".into());
+    run_args.push(format!("{}ms", args.duration.as_millis()).into());
+    println!(
+        "Running for {:?} in a {}x{} pty...",
+        args.duration, COLUMNS, ROWS
+    );
+    let output = vt::capture(
+        &env::current_exe()?,
+        run_args,
+        (COLUMNS, ROWS),
+        args.duration + EXIT_GRACE,
+    )
+    .map_err(|e| failed(e.to_string()))?;
+
+    let mut screen = Screen::new(COLUMNS, ROWS);
+    screen.feed(&output).map_err(failed)?;
+    let unrestored = unrestored(&screen);
+    if !unrestored.is_empty() {
+        return Err(failed(format!(
+            "modes left unrestored: {}",
+            unrestored.join(", ")
+        )));
+    }
+
+    println!(
+        "Passed: {} bytes, {} escape codes and {} glyphs, all on screen, with the terminal restored",
+        output.len(),
+        screen.sequences(),
+        screen.glyphs()
+    );
+    Ok(())
+}
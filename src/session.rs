@@ -0,0 +1,60 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use falling_glyphs::game::Settings;
+
+// Where the settings a run leaves off with are kept for the next one, e.g.
+// ~/.local/state/falling_glyphs/session.toml on Linux
+pub fn default_path() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("falling_glyphs").join("session.toml"))
+}
+
+// The settings saved by the last run, or None if there aren't any yet
+pub fn load(path: &Path) -> io::Result<Option<Settings>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    toml::from_str(&text).map(Some).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid session {}: {}", path.display(), e),
+        )
+    })
+}
+
+pub fn save(path: &Path, settings: &Settings) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text = toml::to_string(settings)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    fs::write(path, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_saved_by_one_run_are_loaded_by_the_next() {
+        let dir =
+            std::env::temp_dir().join(format!("falling_glyphs-session-{}", std::process::id()));
+        let path = dir.join("session.toml");
+        assert_eq!(load(&path).unwrap(), None);
+
+        let settings = Settings {
+            density: 0.8,
+            speed: 25,
+            ..Settings::default()
+        };
+        save(&path, &settings).unwrap();
+        let loaded = load(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded, Some(settings));
+    }
+}
@@ -0,0 +1,227 @@
+#[cfg(feature = "count-allocations")]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io;
+#[cfg(feature = "count-allocations")]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use falling_glyphs::game::{Game, Settings};
+use falling_glyphs::weather::WeatherPreset;
+
+use crate::cli::StressArgs;
+use crate::i18n::Messages;
+
+// How far past the top of the density range the stress test pushes spawning
+pub const SPAWN_SCALE: f64 = 8.0;
+
+// Screen size simulated when not stressing a real terminal
+const HEADLESS_SIZE: (u16, u16) = (240, 70);
+const HEADLESS_DT: Duration = Duration::from_millis(16);
+const HEADLESS_DURATION: Duration = Duration::from_secs(10);
+
+// Heap allocations (and reallocations) made so far, counted for the stress
+// test's report of the simulation's allocator pressure. Counting replaces
+// the global allocator, so only builds that ask for it do.
+#[cfg(feature = "count-allocations")]
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+// The system allocator, counting allocations
+#[cfg(feature = "count-allocations")]
+struct CountingAllocator;
+
+#[cfg(feature = "count-allocations")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Heap allocations made so far, if they're counted
+fn allocations() -> Option<u64> {
+    #[cfg(feature = "count-allocations")]
+    return Some(ALLOCATIONS.load(Ordering::Relaxed));
+    #[cfg(not(feature = "count-allocations"))]
+    None
+}
+
+// Settings that keep as much rain on screen, changing as often, as possible
+pub fn preset() -> WeatherPreset {
+    WeatherPreset {
+        density: 1.0,
+        speed: 50,
+        max_stack_height: 1.0,
+        update_interval_ms: 20..=60,
+        mutation_rate: 0.3,
+        lightning_chance: 0.0,
+    }
+}
+
+pub fn apply(game: &mut Game) {
+    game.apply_weather(&preset());
+    game.set_spawn_scale(SPAWN_SCALE);
+}
+
+// Parts of a frame that time is spent in
+#[derive(Clone, Copy, Debug)]
+pub enum Phase {
+    Input,    // polling for key presses
+    Simulate, // updating the game and diffing the view
+    Compose,  // encoding the changes as escape codes
+    Write,    // the terminal taking the frame
+}
+
+// Each phase and the message naming it
+const PHASES: [(Phase, &str); 4] = [
+    (Phase::Input, "phase-input"),
+    (Phase::Simulate, "phase-simulate"),
+    (Phase::Compose, "phase-compose"),
+    (Phase::Write, "phase-write"),
+];
+
+// Totals the time spent in each phase of a frame, summarized once a second
+pub struct FrameBudget {
+    totals: [Duration; 4],
+    frames: u32,
+    bytes: usize,
+    since: Instant,
+}
+
+impl Default for FrameBudget {
+    fn default() -> Self {
+        Self {
+            totals: [Duration::ZERO; 4],
+            frames: 0,
+            bytes: 0,
+            since: Instant::now(),
+        }
+    }
+}
+
+impl FrameBudget {
+    pub fn record(&mut self, phase: Phase, time: Duration) {
+        self.totals[phase as usize] += time;
+    }
+
+    // Times a phase from the given start until now, returning now for timing the next
+    pub fn lap(&mut self, phase: Phase, start: Instant) -> Instant {
+        let now = Instant::now();
+        self.record(phase, now - start);
+        now
+    }
+
+    pub fn end_frame(&mut self, bytes: usize) {
+        self.frames += 1;
+        self.bytes += bytes;
+    }
+
+    // Once a second, the average time per frame in each phase timed (and its share
+    // of the frame), frames per second and bytes written per second (if any)
+    pub fn summary(&mut self, messages: &Messages) -> Option<Vec<String>> {
+        let elapsed = self.since.elapsed();
+        if elapsed < Duration::from_secs(1) || self.frames == 0 {
+            return None;
+        }
+        let total: Duration = self.totals.iter().sum();
+        let mut parts = Vec::new();
+        for (phase, name) in PHASES {
+            let time = self.totals[phase as usize];
+            if time.is_zero() {
+                continue;
+            }
+            let share = time.as_secs_f64() / total.as_secs_f64().max(f64::EPSILON) * 100.0;
+            let per_frame = time.as_secs_f64() * 1000.0 / self.frames as f64;
+            parts.push(messages.format(
+                "budget-phase",
+                &[
+                    ("phase", &messages.get(name)),
+                    ("time", &format!("{:.2}", per_frame)),
+                    ("share", &format!("{:.0}", share)),
+                ],
+            ));
+        }
+        let seconds = elapsed.as_secs_f64();
+        let fps = format!("{:.0}", self.frames as f64 / seconds);
+        parts.push(messages.format("budget-fps", &[("fps", &fps)]));
+        if self.bytes > 0 {
+            let rate = format!("{:.0}", self.bytes as f64 / 1024.0 / seconds);
+            parts.push(messages.format("budget-written", &[("rate", &rate)]));
+        }
+        *self = Self::default();
+        Some(parts)
+    }
+}
+
+// Runs the stress preset without a terminal, printing a second-by-second
+// breakdown of the time spent simulating. Seeding it runs the same rain
+// each time, for comparing builds.
+pub fn run_headless(args: &StressArgs, seed: Option<u64>, messages: &Messages) -> io::Result<()> {
+    let (width, height) = HEADLESS_SIZE;
+    let duration = args.duration.unwrap_or(HEADLESS_DURATION);
+    let mut game = Game::new(width, height, Settings::default());
+    if let Some(seed) = seed {
+        game.set_seed(seed);
+    }
+    apply(&mut game);
+
+    println!(
+        "{}",
+        messages.format(
+            "stress-start",
+            &[
+                ("width", &width),
+                ("height", &height),
+                ("seconds", &format!("{:.1}", duration.as_secs_f64())),
+                ("seed", &game.seed()),
+            ],
+        )
+    );
+    let started = Instant::now();
+    let mut budget = FrameBudget::default();
+    let mut changes = 0;
+    let mut allocated = allocations();
+    while started.elapsed() < duration {
+        let start = Instant::now();
+        changes += game.update_and_get_changes(HEADLESS_DT).len();
+        budget.lap(Phase::Simulate, start);
+        budget.end_frame(0);
+        if let Some(summary) = budget.summary(messages) {
+            let pool = game.telemetry().stack_pool();
+            let allocations_per_second = match (allocations(), allocated) {
+                (Some(now), Some(before)) => {
+                    messages.format("stress-allocations", &[("allocations", &(now - before))])
+                }
+                _ => String::new(),
+            };
+            println!(
+                "{}",
+                messages.format(
+                    "stress-second",
+                    &[
+                        ("budget", &summary.join(", ")),
+                        ("changes", &changes),
+                        ("allocations", &allocations_per_second),
+                        ("coverage", &format!("{:.0}", game.coverage() * 100.0)),
+                        ("reused", &format!("{:.0}", pool.reuse_rate() * 100.0)),
+                    ],
+                )
+            );
+            changes = 0;
+            allocated = allocations();
+        }
+    }
+    Ok(())
+}
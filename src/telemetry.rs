@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+// Length of each sample the rates are averaged from
+const SAMPLE_LENGTH: Duration = Duration::from_secs(1);
+
+/// A span of recent simulated time that rates are averaged over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Window {
+    /// The last second
+    Second,
+    /// The last ten seconds
+    TenSeconds,
+    /// The last minute
+    Minute,
+}
+
+impl Window {
+    fn seconds(self) -> usize {
+        match self {
+            Window::Second => 1,
+            Window::TenSeconds => 10,
+            Window::Minute => 60,
+        }
+    }
+}
+
+/// How fast the simulation ran over a window of simulated time. Rates are
+/// per simulated second, and zero until the first second has passed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rates {
+    /// Simulation updates per second
+    pub updates_per_sec: f64,
+    /// Glyphs pushed onto stacks per second
+    pub glyphs_per_sec: f64,
+    /// Stacks spawned per second
+    pub stacks_per_sec: f64,
+    /// Change in the fraction of cells lit, per second
+    pub coverage_trend: f64,
+}
+
+/// How the glyph buffers of despawned stacks have been reused by stacks
+/// spawned since, rather than allocated afresh.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolStats {
+    /// Stacks spawned with a buffer from the pool, without allocating
+    pub reused: u64,
+    /// Stacks spawned with a new buffer, or one grown to fit them
+    pub allocated: u64,
+    /// Buffers waiting in the pool
+    pub pooled: usize,
+}
+
+impl PoolStats {
+    /// Fraction (0.0 - 1.0) of the stacks spawned that reused a buffer.
+    pub fn reuse_rate(&self) -> f64 {
+        let spawned = self.reused + self.allocated;
+        if spawned == 0 {
+            return 0.0;
+        }
+        self.reused as f64 / spawned as f64
+    }
+}
+
+/// A snapshot of the simulation: its settings, what the last update did,
+/// and rates over the last second, ten seconds and minute. Updated by each
+/// [`crate::game::Game::update_and_get_changes`], and read with
+/// [`crate::game::Game::telemetry`], it's what the debug overlay and the
+/// metrics sinks show, and it serializes for embedders shipping it elsewhere.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Telemetry {
+    pub(crate) density: f64,
+    pub(crate) max_stack_height: f64,
+    pub(crate) speed: u8,
+    pub(crate) heat: f64,
+    pub(crate) coverage: f64,
+    pub(crate) glyphs_per_update: usize,
+    pub(crate) stacks_per_update: usize,
+    pub(crate) min_glyph_delay_ms: u64,
+    pub(crate) max_glyph_delay_ms: u64,
+    pub(crate) frame_hash: u64,
+    pub(crate) full_repaints: usize,
+    pub(crate) stack_pool: PoolStats,
+    pub(crate) last_second: Rates,
+    pub(crate) last_ten_seconds: Rates,
+    pub(crate) last_minute: Rates,
+}
+
+impl Telemetry {
+    /// Spawn density setting, 0.1 - 1.0.
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+
+    /// Longest a stack may grow, as a fraction of the screen's height.
+    pub fn max_stack_height(&self) -> f64 {
+        self.max_stack_height
+    }
+
+    /// Speed setting, 1 - 50.
+    pub fn speed(&self) -> u8 {
+        self.speed
+    }
+
+    /// Total heat of the key heat map (zero while it's off).
+    pub fn heat(&self) -> f64 {
+        self.heat
+    }
+
+    /// Fraction (0.0 - 1.0) of the screen's cells lit.
+    pub fn coverage(&self) -> f64 {
+        self.coverage
+    }
+
+    /// Glyphs pushed onto stacks by the last update.
+    pub fn glyphs_per_update(&self) -> usize {
+        self.glyphs_per_update
+    }
+
+    /// Stacks spawned by the last update.
+    pub fn stacks_per_update(&self) -> usize {
+        self.stacks_per_update
+    }
+
+    /// Shortest and longest time, in milliseconds, between steps of the
+    /// stacks on screen (zero for both with none).
+    pub fn glyph_delay_ms(&self) -> (u64, u64) {
+        (self.min_glyph_delay_ms, self.max_glyph_delay_ms)
+    }
+
+    /// Fingerprint of the view the changes handed out should have drawn,
+    /// while verifying frames (see [`crate::game::Game::set_verify_frames`]).
+    pub fn frame_hash(&self) -> u64 {
+        self.frame_hash
+    }
+
+    /// Full repaints handed out so far.
+    pub fn full_repaints(&self) -> usize {
+        self.full_repaints
+    }
+
+    /// How stacks' glyph buffers have been recycled so far.
+    pub fn stack_pool(&self) -> PoolStats {
+        self.stack_pool
+    }
+
+    /// Rates over a window of recent simulated time.
+    pub fn rates(&self, window: Window) -> Rates {
+        match window {
+            Window::Second => self.last_second,
+            Window::TenSeconds => self.last_ten_seconds,
+            Window::Minute => self.last_minute,
+        }
+    }
+}
+
+// What the simulation did over a second or so of simulated time
+#[derive(Clone, Copy, Debug, Default)]
+struct Sample {
+    elapsed: Duration,
+    updates: u32,
+    glyphs: usize,
+    spawned: usize,
+    coverage_start: f64,
+    coverage_end: f64,
+}
+
+// Counts what each update does in samples of a second, keeping a minute of
+// them to average rates over each window from
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RateSampler {
+    current: Sample,
+    samples: VecDeque<Sample>, // oldest first
+}
+
+impl RateSampler {
+    // Counts an update of dt, returning true once it completes a sample
+    pub(crate) fn record(
+        &mut self,
+        dt: Duration,
+        glyphs: usize,
+        spawned: usize,
+        coverage: f64,
+    ) -> bool {
+        self.current.elapsed += dt;
+        self.current.updates += 1;
+        self.current.glyphs += glyphs;
+        self.current.spawned += spawned;
+        self.current.coverage_end = coverage;
+        if self.current.elapsed < SAMPLE_LENGTH {
+            return false;
+        }
+        if self.samples.len() == Window::Minute.seconds() {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(self.current);
+        self.current = Sample {
+            coverage_start: coverage,
+            ..Sample::default()
+        };
+        true
+    }
+
+    // Rates over the latest samples covering the window
+    pub(crate) fn rates(&self, window: Window) -> Rates {
+        let start = self.samples.len().saturating_sub(window.seconds());
+        let samples = self.samples.range(start..);
+        let (Some(first), Some(last)) = (samples.clone().next(), samples.clone().last()) else {
+            return Rates::default();
+        };
+        let seconds: f64 = samples.clone().map(|s| s.elapsed.as_secs_f64()).sum();
+        let per_sec = |total: f64| total / seconds;
+        Rates {
+            updates_per_sec: per_sec(samples.clone().map(|s| s.updates as f64).sum()),
+            glyphs_per_sec: per_sec(samples.clone().map(|s| s.glyphs as f64).sum()),
+            stacks_per_sec: per_sec(samples.map(|s| s.spawned as f64).sum()),
+            coverage_trend: per_sec(last.coverage_end - first.coverage_start),
+        }
+    }
+}
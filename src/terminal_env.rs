@@ -0,0 +1,245 @@
+use std::env;
+use std::fmt;
+use std::io;
+use std::process::Command;
+
+use crate::palette::ColorDepth;
+
+// A terminal multiplexer sitting between us and the real terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Multiplexer {
+    Tmux,
+    Screen,
+}
+
+// What the terminal we're running in can do, worked out at startup
+#[derive(Clone, Debug)]
+pub struct TerminalEnv {
+    pub multiplexer: Option<Multiplexer>,
+    pub colors: ColorDepth,
+    pub synchronized_updates: bool,
+    // Whether the terminal can scroll a region of the screen (DECSTBM)
+    pub scroll_regions: bool,
+    // Whether escape sequences can be passed through the multiplexer to the
+    // real terminal (wrapped with `passthrough`)
+    pub passthrough: bool,
+}
+
+// Variables set by emulators that don't set TERM_PROGRAM, and the names
+// they're known by in tuned settings
+const EMULATOR_HINTS: [(&str, &str); 3] = [
+    ("KONSOLE_VERSION", "konsole"),
+    ("VTE_VERSION", "vte"),
+    ("WT_SESSION", "windows-terminal"),
+];
+
+fn var(name: &str) -> String {
+    env::var(name).unwrap_or_default()
+}
+
+// The terminal settings are tuned for (see `tune`): its TERM, and the
+// emulator (or multiplexer) it names itself as, if any, e.g.
+// `xterm-256color/WezTerm`
+pub fn profile_key() -> String {
+    let term = match var("TERM") {
+        term if term.is_empty() => "unknown".to_string(),
+        term => term,
+    };
+    let emulator = match var("TERM_PROGRAM") {
+        program if !program.is_empty() => Some(program),
+        _ => EMULATOR_HINTS
+            .iter()
+            .find(|(name, _)| env::var_os(name).is_some())
+            .map(|(_, emulator)| emulator.to_string()),
+    };
+    match emulator {
+        Some(emulator) => format!("{}/{}", term, emulator),
+        None => term,
+    }
+}
+
+// Whether this is the legacy Windows console (conhost) rather than Windows
+// Terminal or another emulator, which all set variables of their own
+#[cfg(windows)]
+pub fn legacy_console() -> bool {
+    var("TERM").is_empty()
+        && var("TERM_PROGRAM").is_empty()
+        && env::var_os("WT_SESSION").is_none()
+        && env::var_os("ConEmuANSI").is_none()
+}
+
+#[cfg(not(windows))]
+pub fn legacy_console() -> bool {
+    false
+}
+
+// Settings for terminals that need them whatever they're tuned to, as
+// command line arguments for the tuned settings to be parsed over. The
+// legacy Windows console's fonts have no katakana, and it draws 16 colors
+// at a time and tears synchronized updates.
+pub fn default_args() -> Vec<String> {
+    match legacy_console() {
+        true => ["--charset=latin", "--colors=16", "--no-sync"]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        false => Vec::new(),
+    }
+}
+
+// Turns on the Windows console's handling of escape codes, which frames are
+// drawn with, failing on consoles too old to have it
+#[cfg(windows)]
+pub fn enable_escape_codes() -> io::Result<()> {
+    match crossterm::ansi_support::supports_ansi() {
+        true => Ok(()),
+        false => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the console can't draw escape codes (it needs Windows 10 or later)",
+        )),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn enable_escape_codes() -> io::Result<()> {
+    Ok(())
+}
+
+// Whether the session is being recorded, by asciinema (which sets
+// ASCIINEMA_REC in the shell it records)
+pub fn recording() -> bool {
+    env::var_os("ASCIINEMA_REC").is_some()
+}
+
+// Whether the terminal's background is light, by the colors some terminals
+// (like rxvt and Konsole) give in COLORFGBG, e.g. `0;15` for black on white
+pub fn light_background() -> bool {
+    light_colorfgbg(&var("COLORFGBG"))
+}
+
+fn light_colorfgbg(value: &str) -> bool {
+    let background = value
+        .rsplit(';')
+        .next()
+        .and_then(|bg| bg.parse::<u8>().ok());
+    matches!(background, Some(7 | 9..=15))
+}
+
+// Asks the tmux server for one of its format variables or options
+fn tmux(args: &[&str]) -> Option<String> {
+    let output = Command::new("tmux").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Colors the terminal's terminfo entry says it has, short of truecolor,
+// which terminfo rarely advertises
+fn terminfo_colors(term: &str) -> ColorDepth {
+    if term.contains("256color") {
+        return ColorDepth::Ansi256;
+    }
+    let colors = Command::new("tput")
+        .arg("colors")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse::<u32>().ok());
+    match colors {
+        Some(colors) if colors >= 256 => ColorDepth::Ansi256,
+        _ => ColorDepth::Ansi16,
+    }
+}
+
+impl TerminalEnv {
+    pub fn detect() -> Self {
+        let term = var("TERM");
+        let multiplexer = if !var("TMUX").is_empty() || term.starts_with("tmux") {
+            Some(Multiplexer::Tmux)
+        } else if !var("STY").is_empty() || term.starts_with("screen") {
+            Some(Multiplexer::Screen)
+        } else {
+            None
+        };
+        let scroll_regions = !matches!(term.as_str(), "" | "dumb");
+        let colorterm = var("COLORTERM");
+        let advertises_truecolor =
+            matches!(colorterm.as_str(), "truecolor" | "24bit") || term.ends_with("-direct");
+
+        match multiplexer {
+            // tmux knows which features the terminal it's attached to supports
+            Some(Multiplexer::Tmux) => {
+                let features =
+                    tmux(&["display-message", "-p", "#{client_termfeatures}"]).unwrap_or_default();
+                let features: Vec<&str> = features.split(',').collect();
+                let passthrough = tmux(&["show-options", "-gv", "allow-passthrough"])
+                    .is_some_and(|value| value == "on" || value == "all");
+                let colors = if features.contains(&"RGB") {
+                    ColorDepth::Truecolor
+                } else if features.contains(&"256") {
+                    ColorDepth::Ansi256
+                } else {
+                    terminfo_colors(&term)
+                };
+                Self {
+                    multiplexer,
+                    colors,
+                    synchronized_updates: features.contains(&"sync"),
+                    scroll_regions,
+                    passthrough,
+                }
+            }
+            // screen drops 24-bit colors and synchronized updates, but always
+            // passes DCS strings through
+            Some(Multiplexer::Screen) => Self {
+                multiplexer,
+                colors: terminfo_colors(&term),
+                synchronized_updates: false,
+                scroll_regions,
+                passthrough: true,
+            },
+            // Terminals ignore synchronized update modes they don't know
+            None => Self {
+                multiplexer,
+                colors: match advertises_truecolor {
+                    true => ColorDepth::Truecolor,
+                    false => terminfo_colors(&term),
+                },
+                synchronized_updates: true,
+                scroll_regions,
+                passthrough: false,
+            },
+        }
+    }
+
+    // Wraps an escape sequence so the multiplexer hands it to the real terminal
+    pub fn passthrough(&self, sequence: &str) -> String {
+        match self.multiplexer {
+            Some(Multiplexer::Tmux) if self.passthrough => {
+                format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+            }
+            Some(Multiplexer::Screen) => format!("\x1bP{}\x1b\\", sequence),
+            _ => sequence.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for TerminalEnv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.multiplexer {
+            Some(Multiplexer::Tmux) => "tmux",
+            Some(Multiplexer::Screen) => "screen",
+            None => "direct",
+        };
+        let yes_no = |on: bool| if on { "yes" } else { "no" };
+        write!(
+            f,
+            "{} (colors: {}, sync: {}, scroll: {}, passthrough: {})",
+            name,
+            self.colors,
+            yes_no(self.synchronized_updates),
+            yes_no(self.scroll_regions),
+            yes_no(self.passthrough)
+        )
+    }
+}
@@ -0,0 +1,402 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::game::AnsiColor;
+
+// Where the body of a stack starts, past its head, in high contrast
+const HIGH_CONTRAST_BODY: f64 = 0.1;
+
+// Blending steps taken to find a color of a given lightness
+const LIGHTNESS_STEPS: u32 = 12;
+
+// Brightness of the heads on a light background, relative to the body
+const LIGHT_HEAD: f64 = 0.3;
+
+// How far the tails pale toward a light background from the body
+const LIGHT_TAIL: f64 = 0.45;
+
+// Span of hues the rainbow runs through from the head of a stack to its
+// tail, in degrees, stopping short of coming back round to red
+const RAINBOW_HUES: f64 = 300.0;
+
+// Brightness of the rainbow's tail, relative to its body
+const RAINBOW_TAIL: f64 = 0.55;
+
+// Colors the rain is drawn in, from its head down its trail
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    // White heads fading through green
+    #[default]
+    #[value(alias = "matrix")]
+    #[serde(alias = "matrix")]
+    Classic,
+    // Oranges, like an old amber monitor
+    Amber,
+    // Pale blues
+    Ice,
+    // Reds
+    Blood,
+    // White heads over trails running through the spectrum
+    Rainbow,
+    // Grays
+    Mono,
+    // Blues, for red-green color blindness with weak greens
+    Deuteranopia,
+    // Yellows, for red-green color blindness with weak reds
+    Protanopia,
+    // Reds, for blue-yellow color blindness
+    Tritanopia,
+}
+
+// Where the rain's colors come from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorMode {
+    // The theme's colors
+    #[default]
+    Theme,
+    // A random hue for each stack, fading along its trail as the rainbow does
+    PerStack,
+}
+
+// The colors of a theme: the head of each stack, its body and the end of its
+// tail, told apart by brightness as well as hue, and the background the rain
+// is drawn over (which dissolving glyphs fade into)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    pub head: AnsiColor,
+    pub body: AnsiColor,
+    pub tail: AnsiColor,
+    pub background: AnsiColor,
+}
+
+impl Palette {
+    const fn on_black(head: AnsiColor, body: AnsiColor, tail: AnsiColor) -> Self {
+        Self {
+            head,
+            body,
+            tail,
+            background: AnsiColor::Rgb(0, 0, 0),
+        }
+    }
+
+    // White heads over a body in the hue (in degrees), darkening down the tail
+    pub fn of_hue(hue: f64) -> Self {
+        Self::on_black(
+            AnsiColor::White,
+            hsv_to_rgb(hue, 1.0, 1.0),
+            hsv_to_rgb(hue, 1.0, RAINBOW_TAIL),
+        )
+    }
+
+    // The palette for a light background, the other way up: the head darkest,
+    // in the body's hue, the body halfway between the head and the
+    // background in lightness, and the tail paler still
+    pub fn on_light(self) -> Self {
+        let background = AnsiColor::Rgb(255, 255, 255);
+        let head = self.body.scaled(LIGHT_HEAD);
+        let body = halfway(self.body, head, background);
+        Self {
+            head,
+            body,
+            tail: body.blend(background, LIGHT_TAIL),
+            background,
+        }
+    }
+
+    // The color of a glyph the rain colors white, green or dark green (for
+    // its head, body and tail), at a position (0.0 - 1.0) along its stack. In
+    // high contrast, the body keeps one color (see `high_contrast`) rather
+    // than fading into the background.
+    pub fn color(self, role: AnsiColor, position: f64, high_contrast: bool) -> AnsiColor {
+        if high_contrast {
+            let palette = self.high_contrast();
+            return if position <= 0.0 {
+                palette.head
+            } else if position >= HIGH_CONTRAST_BODY {
+                palette.body
+            } else {
+                palette
+                    .head
+                    .blend(palette.body, position / HIGH_CONTRAST_BODY)
+            };
+        }
+        match role {
+            AnsiColor::White => self.head,
+            AnsiColor::Green => self.body,
+            AnsiColor::DarkGreen => self.tail,
+            _ => role,
+        }
+    }
+
+    // The palette with its colors as far apart in lightness as they go, for
+    // high contrast: the head white over a dark background (or black over a
+    // light one), and the body keeping its hue at a lightness halfway between
+    // the head's and the background's
+    pub fn high_contrast(self) -> Self {
+        let head = if lightness(self.background) < 50.0 {
+            AnsiColor::White
+        } else {
+            AnsiColor::Rgb(0, 0, 0)
+        };
+        let body = halfway(self.body, head, self.background);
+        Self {
+            head,
+            body,
+            tail: body,
+            background: self.background,
+        }
+    }
+}
+
+// The color's hue, blended toward the head or the background until its
+// lightness is halfway between theirs
+fn halfway(color: AnsiColor, head: AnsiColor, background: AnsiColor) -> AnsiColor {
+    let target = (lightness(head) + lightness(background)) / 2.0;
+    let (r, g, b) = color.to_rgb();
+    let brightest = r.max(g).max(b).max(1) as f64;
+    let scale = |c: u8| (c as f64 * 255.0 / brightest).round() as u8;
+    let color = AnsiColor::Rgb(scale(r), scale(g), scale(b));
+    // Toward whichever of the two is on the far side of the target
+    let toward = if (lightness(color) > target) == (lightness(head) > target) {
+        background
+    } else {
+        head
+    };
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..LIGHTNESS_STEPS {
+        let amount = (low + high) / 2.0;
+        let blended = color.blend(toward, amount);
+        if (lightness(blended) > target) == (lightness(color) > target) {
+            low = amount;
+        } else {
+            high = amount;
+        }
+    }
+    color.blend(toward, (low + high) / 2.0)
+}
+
+// The color's CIELAB lightness, from 0 (black) to 100 (white)
+fn lightness(color: AnsiColor) -> f64 {
+    let (r, g, b) = color.to_rgb();
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let y = 0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b);
+    if y > 216.0 / 24389.0 {
+        116.0 * y.cbrt() - 16.0
+    } else {
+        24389.0 / 27.0 * y
+    }
+}
+
+impl Theme {
+    // The built-in themes' colors
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Classic => {
+                Palette::on_black(AnsiColor::White, AnsiColor::Green, AnsiColor::DarkGreen)
+            }
+            Theme::Amber => Palette::on_black(
+                AnsiColor::Rgb(255, 230, 170),
+                AnsiColor::Rgb(255, 176, 0),
+                AnsiColor::Rgb(130, 80, 0),
+            ),
+            Theme::Ice => Palette::on_black(
+                AnsiColor::White,
+                AnsiColor::Rgb(150, 220, 255),
+                AnsiColor::Rgb(40, 90, 140),
+            ),
+            Theme::Blood => Palette::on_black(
+                AnsiColor::Rgb(255, 200, 200),
+                AnsiColor::Rgb(200, 0, 0),
+                AnsiColor::Rgb(90, 0, 0),
+            ),
+            Theme::Rainbow => Palette::on_black(
+                AnsiColor::White,
+                hsv_to_rgb(0.0, 1.0, 1.0),
+                hsv_to_rgb(RAINBOW_HUES, 1.0, RAINBOW_TAIL),
+            ),
+            Theme::Mono => Palette::on_black(
+                AnsiColor::White,
+                AnsiColor::Rgb(170, 170, 170),
+                AnsiColor::Rgb(85, 85, 85),
+            ),
+            Theme::Deuteranopia => Palette::on_black(
+                AnsiColor::White,
+                AnsiColor::Rgb(86, 180, 233),
+                AnsiColor::Rgb(0, 80, 140),
+            ),
+            Theme::Protanopia => Palette::on_black(
+                AnsiColor::White,
+                AnsiColor::Rgb(240, 228, 66),
+                AnsiColor::Rgb(120, 110, 20),
+            ),
+            Theme::Tritanopia => Palette::on_black(
+                AnsiColor::White,
+                AnsiColor::Rgb(230, 120, 110),
+                AnsiColor::Rgb(130, 30, 30),
+            ),
+        }
+    }
+
+    // The theme after this one, wrapping around
+    pub fn next(self) -> Self {
+        let themes = Self::value_variants();
+        let at = themes.iter().position(|theme| *theme == self).unwrap_or(0);
+        themes[(at + 1) % themes.len()]
+    }
+
+    // The color of a glyph in the theme (see `Palette::color`), over a dark
+    // background or a light one. The rainbow runs through the spectrum along
+    // the stack.
+    pub fn color(
+        self,
+        role: AnsiColor,
+        position: f64,
+        high_contrast: bool,
+        light: bool,
+    ) -> AnsiColor {
+        let palette = match self {
+            Theme::Classic if !high_contrast && !light => return role,
+            Theme::Rainbow => Palette::of_hue(position * RAINBOW_HUES),
+            _ => self.palette(),
+        };
+        let palette = if light { palette.on_light() } else { palette };
+        palette.color(role, position, high_contrast)
+    }
+}
+
+// The color of a hue (in degrees), saturation and value (0.0 - 1.0)
+pub fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> AnsiColor {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    let channel = |c: f64| ((c + m) * 255.0).round() as u8;
+    AnsiColor::Rgb(channel(r), channel(g), channel(b))
+}
+
+// The color with its hue turned the given number of degrees round the
+// spectrum, keeping its saturation and value (so grays stay gray)
+pub fn rotate_hue(color: AnsiColor, degrees: f64) -> AnsiColor {
+    let (r, g, b) = color.to_rgb();
+    let (r, g, b) = (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+    let value = r.max(g).max(b);
+    let chroma = value - r.min(g).min(b);
+    if chroma == 0.0 {
+        return color;
+    }
+    let hue = if value == r {
+        (g - b) / chroma
+    } else if value == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+    hsv_to_rgb(hue * 60.0 + degrees, chroma / value, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hues_go_and_turn_round_the_spectrum() {
+        assert_eq!(hsv_to_rgb(0.0, 1.0, 1.0), AnsiColor::Rgb(255, 0, 0));
+        assert_eq!(hsv_to_rgb(120.0, 1.0, 1.0), AnsiColor::Rgb(0, 255, 0));
+        assert_eq!(hsv_to_rgb(240.0, 1.0, 0.5), AnsiColor::Rgb(0, 0, 128));
+        assert_eq!(hsv_to_rgb(360.0, 0.0, 1.0), AnsiColor::Rgb(255, 255, 255));
+
+        assert_eq!(
+            rotate_hue(AnsiColor::Rgb(255, 0, 0), 120.0),
+            AnsiColor::Rgb(0, 255, 0)
+        );
+        assert_eq!(
+            rotate_hue(AnsiColor::Rgb(0, 128, 0), -120.0),
+            AnsiColor::Rgb(128, 0, 0)
+        );
+        assert_eq!(rotate_hue(AnsiColor::White, 90.0), AnsiColor::White);
+    }
+
+    #[test]
+    fn themes_color_the_head_body_and_tail_and_cycle_back_round() {
+        let ice = Theme::Ice.palette();
+        assert_eq!(
+            Theme::Ice.color(AnsiColor::White, 0.0, false, false),
+            ice.head
+        );
+        assert_eq!(
+            Theme::Ice.color(AnsiColor::Green, 0.2, false, false),
+            ice.body
+        );
+        assert_eq!(
+            Theme::Ice.color(AnsiColor::DarkGreen, 0.8, false, false),
+            ice.tail
+        );
+        assert_eq!(
+            Theme::Classic.color(AnsiColor::DarkGreen, 0.8, false, false),
+            AnsiColor::DarkGreen
+        );
+        assert_eq!(
+            Theme::Rainbow.color(AnsiColor::Green, 0.4, false, false),
+            Palette::of_hue(0.4 * RAINBOW_HUES).body
+        );
+
+        let mut theme = Theme::Classic;
+        for _ in Theme::value_variants() {
+            theme = theme.next();
+        }
+        assert_eq!(theme, Theme::Classic);
+        assert_eq!(Theme::from_str("matrix", true), Ok(Theme::Classic));
+    }
+
+    #[test]
+    fn high_contrast_bodies_are_halfway_between_head_and_background_in_lightness() {
+        for theme in Theme::value_variants() {
+            let palette = theme.palette().high_contrast();
+            assert_eq!(palette.head, AnsiColor::White);
+            let body = theme.color(AnsiColor::DarkGreen, 0.8, true, false);
+            assert!(
+                (lightness(body) - 50.0).abs() < 1.5,
+                "{:?}: {:?}",
+                theme,
+                body
+            );
+        }
+        let (head, body) = (AnsiColor::Rgb(0, 0, 0), AnsiColor::Rgb(0, 0, 255));
+        let on_white = halfway(body, head, AnsiColor::White);
+        assert!((lightness(on_white) - 50.0).abs() < 1.5, "{:?}", on_white);
+    }
+
+    #[test]
+    fn light_palettes_darken_from_the_tail_to_the_head() {
+        for theme in Theme::value_variants() {
+            let palette = theme.palette().on_light();
+            let [head, body, tail, background] =
+                [palette.head, palette.body, palette.tail, palette.background].map(lightness);
+            assert!(
+                head < body && body < tail && tail < background,
+                "{:?}: {:?}",
+                theme,
+                palette
+            );
+        }
+        let light = Theme::Classic.color(AnsiColor::White, 0.0, false, true);
+        assert!(lightness(light) < 50.0, "{:?}", light);
+    }
+}
@@ -0,0 +1,83 @@
+use rand::Rng;
+use std::time::Duration;
+
+use crate::compositor::{Layer, LayerCell};
+use crate::game::AnsiColor;
+
+// How long the rain runs before typing starts, and the delay between characters
+const TITLE_DELAY: Duration = Duration::from_secs(2);
+const TYPE_INTERVAL: Duration = Duration::from_millis(90);
+
+// A centered line of text typed out glyph-by-glyph over the rain. The
+// cell being typed flickers through random (single-width) characters
+// before settling.
+pub struct TitleCard {
+    text: Vec<char>,
+    elapsed: Duration,
+    revealed: usize,
+}
+
+impl TitleCard {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.chars().collect(),
+            elapsed: Duration::ZERO,
+            revealed: 0,
+        }
+    }
+
+    // Advances the typing by dt, returning true on the update the last
+    // character lands
+    pub fn update(&mut self, dt: Duration) -> bool {
+        let was_revealed = self.revealed == self.text.len();
+        self.elapsed += dt;
+        let elapsed = self.elapsed;
+        self.revealed = if elapsed < TITLE_DELAY {
+            0
+        } else {
+            let typed = (elapsed - TITLE_DELAY).as_millis() / TYPE_INTERVAL.as_millis();
+            (typed as usize).min(self.text.len())
+        };
+        !was_revealed && self.revealed == self.text.len()
+    }
+
+    // A layer hiding the rain behind the text's row, with the typed text
+    // and the typewriter head drawn over it
+    pub fn layer<R: Rng>(&self, width: u16, height: u16, rng: &mut R) -> Layer {
+        let mut layer = Layer::new(width, height);
+        if self.revealed == 0 && self.elapsed < TITLE_DELAY {
+            return layer;
+        }
+
+        let y = height / 2;
+        let len = self.text.len().min(width as usize) as u16;
+        let start = (width - len) / 2;
+
+        // Rain glyphs are two columns wide, so clear a column either side of the text
+        for x in start.saturating_sub(1)..(start + len + 1).min(width) {
+            layer.set(x, y, LayerCell::Clear);
+        }
+
+        for (i, ch) in self.text.iter().take(len as usize).enumerate() {
+            let x = start + i as u16;
+            if i < self.revealed {
+                let cell = LayerCell::Glyph {
+                    ch: *ch,
+                    color: AnsiColor::White,
+                    alpha: 1.0,
+                };
+                layer.set(x, y, cell);
+            } else if i == self.revealed {
+                // The typewriter head flickers until its character lands
+                let ch = rng.random_range('!'..='~');
+                let cell = LayerCell::Glyph {
+                    ch,
+                    color: AnsiColor::Green,
+                    alpha: 1.0,
+                };
+                layer.set(x, y, cell);
+            }
+        }
+        layer
+    }
+}
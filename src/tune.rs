@@ -0,0 +1,274 @@
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    style::Print,
+    terminal::{
+        self, BeginSynchronizedUpdate, Clear, ClearType, EndSynchronizedUpdate,
+        EnterAlternateScreen, LeaveAlternateScreen,
+    },
+    QueueableCommand,
+};
+use falling_glyphs::game::{Change, Game, Settings};
+
+use crate::config;
+use crate::i18n::Messages;
+use crate::palette::{self, ColorDepth, SetForeground};
+use crate::terminal_env::{self, TerminalEnv};
+
+// Asks for the terminal's primary device attributes, which every terminal
+// answers, and answers in order: once it has, any query before it has been
+// answered (or was ignored), and everything written before it drawn
+const DEVICE_ATTRIBUTES: &str = "\x1b[c";
+
+// Sets a 24-bit background, then asks which colors are set (DECRQSS), and
+// asks whether synchronized updates are supported (DECRQM)
+const CAPABILITY_QUERIES: &str = "\x1b[48;2;1;2;3m\x1bP$qm\x1b\\\x1b[0m\x1b[?2026$p";
+
+// How long a terminal has to answer the capability queries
+const QUERY_TIMEOUT: Duration = Duration::from_secs(1);
+
+// How long rain is drawn for, as fast as the terminal takes it, and how long
+// the terminal then has to catch up with it
+const CALIBRATION_TIME: Duration = Duration::from_millis(1500);
+const CATCH_UP_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Time simulated per frame drawn, as when drawing at 60 frames per second
+const FRAME_DT: Duration = Duration::from_millis(16);
+
+// Share of the frames per second the terminal took that the cap allows,
+// leaving headroom for heavier rain and a busy machine
+const FPS_HEADROOM: f64 = 0.75;
+
+// Frame rates the cap is rounded down to
+const FPS_CAPS: [u32; 9] = [10, 15, 24, 30, 45, 60, 90, 120, 240];
+
+// What the terminal answered and how fast it drew
+struct Calibration {
+    truecolor: Option<bool>,
+    synchronized_updates: Option<bool>,
+    frames: u32,
+    bytes: usize,
+    elapsed: Duration,
+}
+
+impl Calibration {
+    fn fps(&self) -> f64 {
+        self.frames as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    // The highest cap the terminal keeps up with, with headroom
+    fn max_fps(&self) -> u32 {
+        let fps = self.fps() * FPS_HEADROOM;
+        let fits = FPS_CAPS.iter().rev().find(|cap| **cap as f64 <= fps);
+        *fits.unwrap_or(&FPS_CAPS[0])
+    }
+
+    // The settings for the terminal, named after the long options. Anything
+    // it didn't answer is left for detection at startup.
+    fn settings(&self) -> toml::Table {
+        let mut settings = toml::Table::new();
+        settings.insert("max-fps".into(), (self.max_fps() as i64).into());
+        match self.truecolor {
+            Some(true) => settings.insert("colors".into(), "truecolor".into()),
+            Some(false) => settings.insert("colors".into(), "256".into()),
+            None => None,
+        };
+        if self.synchronized_updates == Some(false) {
+            settings.insert("no-sync".into(), true.into());
+        }
+        settings
+    }
+}
+
+// Writes queries followed by a device attributes query, and returns what the
+// terminal answered before its device attributes, or None if it didn't
+// answer in time. Reading stops once it has, so nothing typed afterwards is
+// taken from the rain.
+fn query(queries: &str, timeout: Duration) -> io::Result<Option<String>> {
+    let (answers, answered) = mpsc::channel();
+    thread::spawn(move || {
+        let mut answer = Vec::new();
+        for byte in io::stdin().lock().bytes() {
+            let Ok(byte) = byte else {
+                return;
+            };
+            answer.push(byte);
+            if byte != b'c' {
+                continue;
+            }
+            // The device attributes are a list of numbers, like `\x1b[?62;22c`
+            let text = String::from_utf8_lossy(&answer);
+            let Some(start) = text.rfind("\x1b[?") else {
+                continue;
+            };
+            let attributes = &text[start + 3..text.len() - 1];
+            if attributes.bytes().all(|b| b.is_ascii_digit() || b == b';') {
+                let _ = answers.send(text[..start].to_string());
+                return;
+            }
+        }
+    });
+    let mut stdout = io::stdout();
+    stdout.write_all(queries.as_bytes())?;
+    stdout.write_all(DEVICE_ATTRIBUTES.as_bytes())?;
+    stdout.flush()?;
+    Ok(answered.recv_timeout(timeout).ok())
+}
+
+// Whether the colors reported set (by DECRQSS) kept the 24-bit background,
+// if the terminal reported them
+fn kept_truecolor(answer: &str) -> Option<bool> {
+    let (_, reported) = answer.split_once("\x1bP1$r")?;
+    let reported = reported.split('\x1b').next()?;
+    Some(reported.contains("1;2;3") || reported.contains("1:2:3"))
+}
+
+// Whether synchronized updates can be used, if the terminal reported the
+// mode (by DECRQM): set, reset or permanently set, rather than permanently
+// reset (4) or not recognized (0)
+fn supports_sync(answer: &str) -> Option<bool> {
+    let (_, reported) = answer.split_once("\x1b[?2026;")?;
+    let state = reported.split('$').next()?;
+    Some(matches!(state, "1" | "2" | "3"))
+}
+
+// Draws heavy rain as fast as the terminal takes it, then waits for the
+// terminal to catch up, counting the frames and bytes it drew
+fn draw_rain(env: &TerminalEnv, sync: bool) -> io::Result<(u32, usize, Duration)> {
+    let (width, height) = terminal::size()?;
+    let settings = Settings {
+        density: 1.0,
+        ..Settings::default()
+    };
+    let mut game = Game::new(width, height, settings);
+    let mut stdout = io::stdout();
+    let (mut frames, mut bytes) = (0, 0);
+    let started = Instant::now();
+    while started.elapsed() < CALIBRATION_TIME {
+        let mut frame = Vec::new();
+        if sync {
+            frame.queue(BeginSynchronizedUpdate)?;
+        }
+        for change in game.update_and_get_changes(FRAME_DT) {
+            match change {
+                Change::Update(x, y, ch, color, _, _, _) => {
+                    let color = palette::quantize(color, env.colors);
+                    frame
+                        .queue(MoveTo(x, y))?
+                        .queue(SetForeground(color, env.colors))?
+                        .queue(Print(ch))?;
+                }
+                Change::Remove(x, y) => {
+                    frame.queue(MoveTo(x, y))?.queue(Print(' '))?;
+                }
+            }
+        }
+        if sync {
+            frame.queue(EndSynchronizedUpdate)?;
+        }
+        stdout.write_all(&frame)?;
+        stdout.flush()?;
+        frames += 1;
+        bytes += frame.len();
+    }
+    if query("", CATCH_UP_TIMEOUT)?.is_none() {
+        return Err(io::Error::other(
+            "the terminal didn't catch up with the rain",
+        ));
+    }
+    Ok((frames, bytes, started.elapsed()))
+}
+
+fn calibrate() -> io::Result<Calibration> {
+    let env = TerminalEnv::detect();
+    let answer = query(CAPABILITY_QUERIES, QUERY_TIMEOUT)?.unwrap_or_default();
+    let truecolor = kept_truecolor(&answer);
+    let synchronized_updates = supports_sync(&answer);
+    let env = TerminalEnv {
+        colors: match truecolor {
+            Some(true) => ColorDepth::Truecolor,
+            Some(false) => ColorDepth::Ansi256,
+            None => env.colors,
+        },
+        ..env
+    };
+    let sync = synchronized_updates.unwrap_or(env.synchronized_updates);
+    io::stdout().queue(Clear(ClearType::All))?.flush()?;
+    let (frames, bytes, elapsed) = draw_rain(&env, sync)?;
+    Ok(Calibration {
+        truecolor,
+        synchronized_updates,
+        frames,
+        bytes,
+        elapsed,
+    })
+}
+
+// Measures what the terminal can do: whether it keeps 24-bit colors and
+// knows synchronized updates (by asking it), and how many frames of heavy
+// rain it draws a second. The settings for it (a frame rate cap it keeps up
+// with, and colors and synchronized updates where it answered) are saved in
+// the config file under its TERM and emulator, for the rain to use whenever
+// it runs there again.
+pub fn run(path: &Path, dry_run: bool, messages: &Messages) -> io::Result<()> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(io::Error::other("tuning needs a terminal to measure"));
+    }
+    let mut stdout = io::stdout();
+    stdout.queue(EnterAlternateScreen)?.queue(Hide)?.flush()?;
+    terminal::enable_raw_mode()?;
+    let calibration = calibrate();
+    terminal::disable_raw_mode()?;
+    stdout
+        .queue(Print("\x1b[0m"))?
+        .queue(Show)?
+        .queue(LeaveAlternateScreen)?
+        .flush()?;
+    let calibration = calibration?;
+
+    let key = terminal_env::profile_key();
+    let yes_no = |answer: Option<bool>| match answer {
+        Some(true) => messages.get("tune-yes"),
+        Some(false) => messages.get("tune-no"),
+        None => messages.get("tune-unknown"),
+    };
+    let rate = calibration.bytes as f64 / 1024.0 / calibration.elapsed.as_secs_f64();
+    println!(
+        "{}",
+        messages.format(
+            "tune-result",
+            &[
+                ("terminal", &key),
+                ("fps", &format!("{:.0}", calibration.fps())),
+                ("rate", &format!("{:.0}", rate)),
+                ("truecolor", &yes_no(calibration.truecolor)),
+                ("sync", &yes_no(calibration.synchronized_updates)),
+            ],
+        )
+    );
+    let settings = calibration.settings();
+    let listed: Vec<String> = settings
+        .iter()
+        .map(|(k, v)| format!("{} = {}", k, v))
+        .collect();
+    let listed = listed.join(", ");
+    if dry_run {
+        println!(
+            "{}",
+            messages.format("tune-would-save", &[("settings", &listed)])
+        );
+        return Ok(());
+    }
+    config::save_terminal(path, &key, settings)?;
+    let saved = messages.format(
+        "tune-saved",
+        &[("path", &path.display()), ("settings", &listed)],
+    );
+    println!("{}", saved);
+    Ok(())
+}
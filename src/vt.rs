@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsStr;
+use std::io::{self, Read};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+
+use crate::game::display_width;
+
+// A cell of the screen: its glyph, and the colors and attributes it was
+// drawn in, as the parameters of the escape codes that set them (like
+// `38;5;46` or `97`, or `1;3` for bold italic, or empty for the terminal's
+// own). The right half of a wide glyph holds a NUL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub foreground: String,
+    pub background: String,
+    pub attributes: String,
+}
+
+impl Cell {
+    fn blank() -> Self {
+        Self {
+            ch: ' ',
+            foreground: String::new(),
+            background: String::new(),
+            attributes: String::new(),
+        }
+    }
+}
+
+// A terminal in memory, following the cursor and cells through the escape
+// codes the renderer writes. It fails on any code the renderer doesn't write
+// (so garbled output shows up) and on any glyph drawn past the edges of the
+// screen.
+pub struct Screen {
+    columns: u16,
+    rows: u16,
+    x: u16,
+    y: u16,
+    cells: Vec<Vec<Cell>>,
+    foreground: String,
+    background: String,
+    attributes: [bool; 3], // bold, dim and italic (SGR 1 - 3)
+    scroll_region: (u16, u16), // top and bottom rows, inclusive
+    modes: HashMap<u16, bool>,
+    sequences: usize,
+    glyphs: usize,
+}
+
+impl Screen {
+    pub fn new(columns: u16, rows: u16) -> Self {
+        Self {
+            columns,
+            rows,
+            x: 0,
+            y: 0,
+            cells: vec![vec![Cell::blank(); columns as usize]; rows as usize],
+            foreground: String::new(),
+            background: String::new(),
+            attributes: [false; 3],
+            scroll_region: (0, rows.saturating_sub(1)),
+            modes: HashMap::new(),
+            sequences: 0,
+            glyphs: 0,
+        }
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.columns, self.rows)
+    }
+
+    pub fn cursor(&self) -> (u16, u16) {
+        (self.x, self.y)
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> &Cell {
+        &self.cells[y as usize][x as usize]
+    }
+
+    // The glyphs of a row as text, as they'd be copied off the screen
+    pub fn row_text(&self, y: u16) -> String {
+        self.cells[y as usize]
+            .iter()
+            .map(|cell| cell.ch)
+            .filter(|ch| *ch != '\0')
+            .collect()
+    }
+
+    // Whether a private mode (like 1049, the alternate screen) was last set
+    // or reset, if it was ever either
+    pub fn mode(&self, mode: u16) -> Option<bool> {
+        self.modes.get(&mode).copied()
+    }
+
+    // How many escape codes and glyphs have been fed in
+    pub fn sequences(&self) -> usize {
+        self.sequences
+    }
+
+    pub fn glyphs(&self) -> usize {
+        self.glyphs
+    }
+
+    // Blanks a cell, along with the other half of a wide glyph it's part of
+    fn erase(&mut self, x: u16, y: u16) {
+        let row = &mut self.cells[y as usize];
+        let x = x as usize;
+        if row[x].ch == '\0' && x > 0 {
+            row[x - 1] = Cell::blank();
+        } else if x + 1 < row.len() && row[x + 1].ch == '\0' {
+            row[x + 1] = Cell::blank();
+        }
+        row[x] = Cell::blank();
+    }
+
+    fn erase_rows(&mut self, rows: std::ops::Range<u16>) {
+        for y in rows {
+            self.cells[y as usize].fill(Cell::blank());
+        }
+    }
+
+    fn erase_columns(&mut self, y: u16, columns: std::ops::Range<u16>) {
+        for x in columns {
+            self.erase(x, y);
+        }
+    }
+
+    // Scrolls the scroll region up a row (as a line feed at its bottom does),
+    // or down a row (as a reverse index at its top does)
+    fn scroll(&mut self, up: bool) {
+        let (top, bottom) = (self.scroll_region.0 as usize, self.scroll_region.1 as usize);
+        let region = &mut self.cells[top..=bottom];
+        if up {
+            region.rotate_left(1);
+            region[bottom - top].fill(Cell::blank());
+        } else {
+            region.rotate_right(1);
+            region[0].fill(Cell::blank());
+        }
+    }
+
+    // The glyph drawn at the cursor, which has to fit on its row. Text
+    // written once the alternate screen is left (like an error) may wrap.
+    fn print(&mut self, ch: char) -> Result<(), String> {
+        let width = display_width(ch);
+        if self.x + width > self.columns {
+            if self.modes.get(&1049) == Some(&true) {
+                return Err(format!(
+                    "{:?} drawn past the right edge at {},{}",
+                    ch, self.x, self.y
+                ));
+            }
+            self.x = 0;
+            self.line_feed();
+        }
+        for x in self.x..self.x + width {
+            self.erase(x, self.y);
+        }
+        // A space looks the same whatever color and attributes it's drawn in,
+        // though not whatever color it's drawn over
+        let (foreground, attributes) = if ch == ' ' {
+            (String::new(), String::new())
+        } else {
+            (self.foreground.clone(), self.attribute_codes())
+        };
+        let background = self.background.clone();
+        let row = &mut self.cells[self.y as usize];
+        if width == 2 {
+            row[self.x as usize + 1] = Cell {
+                ch: '\0',
+                background: background.clone(),
+                ..Cell::blank()
+            };
+        }
+        row[self.x as usize] = Cell {
+            ch,
+            foreground,
+            background,
+            attributes,
+        };
+        self.x += width;
+        self.glyphs += 1;
+        Ok(())
+    }
+
+    fn line_feed(&mut self) {
+        if self.y == self.scroll_region.1 {
+            self.scroll(true);
+        } else {
+            self.y = (self.y + 1).min(self.rows - 1);
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        if self.y == self.scroll_region.0 {
+            self.scroll(false);
+        } else {
+            self.y = self.y.saturating_sub(1);
+        }
+    }
+
+    // The attributes set, as the parameters of an SGR code setting them
+    fn attribute_codes(&self) -> String {
+        (1..=3)
+            .filter(|code| self.attributes[code - 1])
+            .map(|code| code.to_string())
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    // Sets the colors and attributes an SGR code's parameters select,
+    // returning None for any parameter that's neither
+    fn select_graphics(&mut self, numbers: &[u16]) -> Option<()> {
+        let mut rest = numbers;
+        while let Some((&number, after)) = rest.split_first() {
+            rest = after;
+            let (color, set) = match number {
+                0 => {
+                    self.foreground.clear();
+                    self.background.clear();
+                    self.attributes = [false; 3];
+                    continue;
+                }
+                1..=3 => {
+                    self.attributes[number as usize - 1] = true;
+                    continue;
+                }
+                // Normal intensity ends both bold and dim
+                22 => {
+                    self.attributes[..2].fill(false);
+                    continue;
+                }
+                23 => {
+                    self.attributes[2] = false;
+                    continue;
+                }
+                30..=37 | 90..=97 => (&mut self.foreground, vec![number]),
+                40..=47 | 100..=107 => (&mut self.background, vec![number]),
+                39 => (&mut self.foreground, vec![]),
+                49 => (&mut self.background, vec![]),
+                // Indexed (5;n) or 24-bit (2;r;g;b) colors
+                38 | 48 => {
+                    let length = match rest.first()? {
+                        5 => 2,
+                        2 => 4,
+                        _ => return None,
+                    };
+                    let (values, after) = (rest.get(..length)?, &rest[length..]);
+                    rest = after;
+                    let color = match number {
+                        38 => &mut self.foreground,
+                        _ => &mut self.background,
+                    };
+                    (color, [&[number], values].concat())
+                }
+                _ => return None,
+            };
+            *color = set
+                .iter()
+                .map(u16::to_string)
+                .collect::<Vec<_>>()
+                .join(";");
+        }
+        Some(())
+    }
+
+    fn control(&mut self, sequence: &str) -> Result<(), String> {
+        let unexpected = || format!("unexpected escape code {:?}", sequence);
+        self.sequences += 1;
+        if sequence == "\x1bM" {
+            self.reverse_index();
+            return Ok(());
+        }
+        let body = sequence.strip_prefix("\x1b[").ok_or_else(unexpected)?;
+        let (params, last) = body.split_at(body.len() - 1);
+        let (private, params) = match params.strip_prefix('?') {
+            Some(params) => (true, params),
+            None => (false, params),
+        };
+        let numbers = params
+            .split(';')
+            .map(|n| {
+                if n.is_empty() {
+                    Ok(0)
+                } else {
+                    n.parse::<u16>()
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| unexpected())?;
+        let first = numbers.first().copied().unwrap_or(0);
+
+        match (private, last) {
+            (true, "h" | "l") => {
+                for mode in numbers {
+                    // Entering or leaving the alternate screen starts it blank
+                    if mode == 1049 {
+                        self.erase_rows(0..self.rows);
+                    }
+                    self.modes.insert(mode, last == "h");
+                }
+            }
+            (false, "H") => {
+                let row = first.max(1);
+                let column = numbers.get(1).copied().unwrap_or(1).max(1);
+                if row > self.rows || column > self.columns {
+                    return Err(format!("cursor moved off the screen to {},{}", column, row));
+                }
+                (self.x, self.y) = (column - 1, row - 1);
+            }
+            (false, "m") => self.select_graphics(&numbers).ok_or_else(unexpected)?,
+            (false, "K") => match first {
+                0 => self.erase_columns(self.y, self.x.min(self.columns - 1)..self.columns),
+                1 => self.erase_columns(self.y, 0..(self.x + 1).min(self.columns)),
+                _ => self.erase_rows(self.y..self.y + 1),
+            },
+            (false, "J") => match first {
+                0 => {
+                    self.erase_columns(self.y, self.x.min(self.columns - 1)..self.columns);
+                    self.erase_rows(self.y + 1..self.rows);
+                }
+                1 => {
+                    self.erase_rows(0..self.y);
+                    self.erase_columns(self.y, 0..(self.x + 1).min(self.columns));
+                }
+                _ => self.erase_rows(0..self.rows),
+            },
+            (false, "r") => {
+                let top = first.max(1);
+                let bottom = numbers
+                    .get(1)
+                    .copied()
+                    .filter(|b| *b > 0)
+                    .unwrap_or(self.rows);
+                if top >= bottom || bottom > self.rows {
+                    return Err(format!(
+                        "scroll region set off the screen to {}-{}",
+                        top, bottom
+                    ));
+                }
+                self.scroll_region = (top - 1, bottom - 1);
+                (self.x, self.y) = (0, 0);
+            }
+            _ => return Err(unexpected()),
+        }
+        Ok(())
+    }
+
+    pub fn feed(&mut self, output: &str) -> Result<(), String> {
+        let mut chars = output.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\x1b' => {
+                    let mut sequence = String::from(ch);
+                    match chars.next() {
+                        Some('[') => {
+                            sequence.push('[');
+                            // Parameters and intermediates, up to the final byte
+                            for ch in chars.by_ref() {
+                                sequence.push(ch);
+                                if ('@'..='~').contains(&ch) {
+                                    break;
+                                }
+                            }
+                        }
+                        Some(ch) => sequence.push(ch),
+                        None => return Err("output ends in the middle of an escape code".into()),
+                    }
+                    self.control(&sequence)?;
+                }
+                '\r' => self.x = 0,
+                '\n' => self.line_feed(),
+                ch if ch.is_control() => {
+                    return Err(format!("unexpected control character {:?}", ch));
+                }
+                ch => self.print(ch)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Runs a program (a build of falling_glyphs, say) in a pseudo terminal of
+// the given size, returning everything it wrote once it exits successfully,
+// killing it if it runs past the time limit
+pub fn capture<I, S>(
+    program: &Path,
+    args: I,
+    (columns, rows): (u16, u16),
+    limit: Duration,
+) -> io::Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let pty_error = |e: String| io::Error::other(format!("failed to open a pty: {}", e));
+
+    let pair = native_pty_system()
+        .openpty(PtySize {
+            rows,
+            cols: columns,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| pty_error(e.to_string()))?;
+
+    let mut command = CommandBuilder::new(program);
+    command.args(args);
+    command.cwd(env::current_dir()?);
+    command.env("TERM", "xterm-256color");
+    // The pty is a terminal of its own, not a window of any multiplexer or
+    // emulator this runs in (whose tuned settings would apply to it), nor
+    // one being recorded
+    let outer = [
+        "TMUX",
+        "STY",
+        "TERM_PROGRAM",
+        "KONSOLE_VERSION",
+        "VTE_VERSION",
+        "WT_SESSION",
+        "ASCIINEMA_REC",
+    ];
+    for name in outer {
+        command.env_remove(name);
+    }
+    let mut child = pair
+        .slave
+        .spawn_command(command)
+        .map_err(|e| pty_error(e.to_string()))?;
+    drop(pair.slave);
+
+    // The pty has to be drained as the run writes, or it blocks
+    let mut reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| pty_error(e.to_string()))?;
+    let output = thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = reader.read_to_end(&mut output);
+        output
+    });
+
+    let deadline = Instant::now() + limit;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() > deadline {
+            let _ = child.kill();
+            return Err(io::Error::other("the run didn't exit when it was due to"));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    drop(pair.master);
+    let output = output.join().unwrap_or_default();
+
+    let output = String::from_utf8(output).map_err(|e| {
+        io::Error::other(format!(
+            "output isn't UTF-8 at byte {}",
+            e.utf8_error().valid_up_to()
+        ))
+    })?;
+    if !status.success() {
+        let tail: String = output
+            .lines()
+            .last()
+            .unwrap_or_default()
+            .chars()
+            .take(200)
+            .collect();
+        return Err(io::Error::other(format!(
+            "the run exited with {}: {}",
+            status, tail
+        )));
+    }
+    Ok(output)
+}
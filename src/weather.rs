@@ -0,0 +1,51 @@
+use clap::ValueEnum;
+use std::ops::RangeInclusive;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Weather {
+    Drizzle,
+    Downpour,
+    Storm,
+}
+
+// A named bundle of settings applied to the game at startup
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeatherPreset {
+    pub density: f64,
+    pub speed: u8,
+    pub max_stack_height: f64,
+    pub update_interval_ms: RangeInclusive<u64>, // per-stack glyph delay range
+    pub mutation_rate: f64,                      // chance of a glyph changing per update
+    pub lightning_chance: f64,                   // chance of a flash per 75ms tick
+}
+
+impl Weather {
+    pub fn preset(self) -> WeatherPreset {
+        match self {
+            Weather::Drizzle => WeatherPreset {
+                density: 0.2,
+                speed: 6,
+                max_stack_height: 0.3,
+                update_interval_ms: 150..=350,
+                mutation_rate: 0.02,
+                lightning_chance: 0.0,
+            },
+            Weather::Downpour => WeatherPreset {
+                density: 0.9,
+                speed: 18,
+                max_stack_height: 0.7,
+                update_interval_ms: 40..=160,
+                mutation_rate: 0.05,
+                lightning_chance: 0.0,
+            },
+            Weather::Storm => WeatherPreset {
+                density: 1.0,
+                speed: 30,
+                max_stack_height: 0.9,
+                update_interval_ms: 30..=120,
+                mutation_rate: 0.12,
+                lightning_chance: 0.01,
+            },
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use crate::noise::ValueNoise;
+
+// How many lanes across a gust spans, and how long one takes to rise and relax
+const GUST_WIDTH: f64 = 12.0;
+const GUST_PERIOD: Duration = Duration::from_secs(4);
+
+// Lanes gusts drift across, per gust period
+const GUST_DRIFT: f64 = 0.3;
+
+// Slowest a lull slows stacks down to, as a fraction of their speed
+const MIN_GUST: f64 = 0.1;
+
+// Gusts of wind that speed groups of neighbouring lanes up together and let
+// them relax, from a slowly drifting noise field over the lanes and time
+#[derive(Clone, Debug, Default)]
+pub struct Wind {
+    strength: f64, // 0.0 - 1.0, how far gusts vary the speed (0.0 is calm)
+    noise: ValueNoise,
+    elapsed: Duration,
+    gusts: Vec<f64>, // speed multiplier per lane
+}
+
+impl Wind {
+    pub fn strength(&self) -> f64 {
+        self.strength
+    }
+
+    pub fn set_strength(&mut self, strength: f64) {
+        self.strength = strength.clamp(0.0, 1.0);
+        self.gusts.clear();
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.noise = ValueNoise::new(seed);
+    }
+
+    // Moves the gusts on by dt, working out the speed multiplier of each lane
+    pub fn update(&mut self, dt: Duration, lanes: u16) {
+        if self.strength == 0.0 {
+            return;
+        }
+        self.elapsed += dt;
+        let t = self.elapsed.as_secs_f64() / GUST_PERIOD.as_secs_f64();
+        let (noise, strength) = (self.noise, self.strength);
+        self.gusts.clear();
+        self.gusts.extend((0..lanes).map(|lane| {
+            let x = lane as f64 / GUST_WIDTH + t * GUST_DRIFT;
+            (1.0 + strength * noise.at(x, t)).max(MIN_GUST)
+        }));
+    }
+
+    // The speed multiplier of a lane, 1.0 while calm
+    pub fn gust(&self, lane: u16) -> f64 {
+        self.gusts.get(lane as usize).copied().unwrap_or(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wind_gusts_neighbouring_columns_together() {
+        let mut wind = Wind::default();
+        wind.set_seed(3);
+        wind.set_strength(1.0);
+        wind.update(Duration::from_secs(1), 80);
+
+        let gusts: Vec<f64> = (0..80).map(|lane| wind.gust(lane)).collect();
+        assert!(gusts.iter().any(|g| *g > 1.2) && gusts.iter().any(|g| *g < 0.8));
+        for pair in gusts.windows(2) {
+            assert!((pair[0] - pair[1]).abs() < 0.3, "{:?}", pair);
+        }
+        assert_eq!(wind.gust(80), 1.0);
+    }
+}
@@ -0,0 +1,215 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode},
+    style::{Color, Print, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    QueueableCommand,
+};
+
+use crate::config::DEFAULT_PROFILE;
+use crate::i18n::Messages;
+
+const HEADER: &str =
+    "# Written by the falling_glyphs setup wizard; run with --setup to redo it.\n\n";
+
+// What picking a choice does to its setting
+#[derive(Clone, Copy)]
+enum Choice {
+    Default,
+    Text(&'static str),
+    Flag(bool),
+}
+
+// A question, and the choices for its answer, as the ids of their messages
+struct Step {
+    prompt: &'static str,
+    setting: &'static str,
+    choices: &'static [(&'static str, Choice)],
+}
+
+const STEPS: [Step; 5] = [
+    Step {
+        prompt: "wizard-weather",
+        setting: "weather",
+        choices: &[
+            ("wizard-drizzle", Choice::Text("drizzle")),
+            ("wizard-steady", Choice::Default),
+            ("wizard-downpour", Choice::Text("downpour")),
+            ("wizard-storm", Choice::Text("storm")),
+        ],
+    },
+    Step {
+        prompt: "wizard-effect",
+        setting: "effect",
+        choices: &[
+            ("wizard-rain", Choice::Default),
+            ("wizard-vortex", Choice::Text("vortex")),
+            ("wizard-helix", Choice::Text("helix")),
+            ("wizard-waterfall", Choice::Text("waterfall")),
+            ("wizard-sheet", Choice::Text("sheet")),
+        ],
+    },
+    Step {
+        prompt: "wizard-theme",
+        setting: "theme",
+        choices: &[
+            ("wizard-classic", Choice::Default),
+            ("wizard-deuteranopia", Choice::Text("deuteranopia")),
+            ("wizard-protanopia", Choice::Text("protanopia")),
+            ("wizard-tritanopia", Choice::Text("tritanopia")),
+        ],
+    },
+    Step {
+        prompt: "wizard-high-contrast",
+        setting: "high-contrast",
+        choices: &[
+            ("wizard-no", Choice::Flag(false)),
+            ("wizard-yes", Choice::Flag(true)),
+        ],
+    },
+    Step {
+        prompt: "wizard-smooth",
+        setting: "smooth",
+        choices: &[
+            ("wizard-no", Choice::Flag(false)),
+            ("wizard-yes", Choice::Flag(true)),
+        ],
+    },
+];
+
+// Draws a prompt and its choices, returning the picked choice's index, or
+// None if the wizard was skipped
+fn ask(title: &str, prompt: &str, choices: &[String], hint: &str) -> io::Result<Option<usize>> {
+    let mut selected = 0;
+    loop {
+        let mut frame = Vec::new();
+        frame
+            .queue(Clear(ClearType::All))?
+            .queue(MoveTo(2, 1))?
+            .queue(SetForegroundColor(Color::DarkGreen))?
+            .queue(Print(title))?
+            .queue(MoveTo(2, 3))?
+            .queue(SetForegroundColor(Color::White))?
+            .queue(Print(prompt))?;
+        for (i, choice) in choices.iter().enumerate() {
+            let (marker, color) = if i == selected {
+                ("> ", Color::White)
+            } else {
+                ("  ", Color::Green)
+            };
+            frame
+                .queue(MoveTo(2, 5 + i as u16))?
+                .queue(SetForegroundColor(color))?
+                .queue(Print(format!("{}{}", marker, choice)))?;
+        }
+        frame
+            .queue(MoveTo(2, 6 + choices.len() as u16))?
+            .queue(SetForegroundColor(Color::DarkGreen))?
+            .queue(Print(hint))?;
+        io::stdout().write_all(&frame)?;
+        io::stdout().flush()?;
+
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Up => selected = selected.saturating_sub(1),
+                KeyCode::Down => selected = (selected + 1).min(choices.len() - 1),
+                KeyCode::Enter => return Ok(Some(selected)),
+                KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+// Walks through the steps, returning the settings picked, or None if skipped
+fn ask_all(messages: &Messages) -> io::Result<Option<toml::Table>> {
+    let hint = messages.get("wizard-hint");
+    let welcome = ask(
+        &messages.get("wizard-title"),
+        &messages.get("wizard-welcome"),
+        &[messages.get("wizard-set-up"), messages.get("wizard-skip")],
+        &hint,
+    )?;
+    if welcome != Some(0) {
+        return Ok(None);
+    }
+
+    let mut profile = toml::Table::new();
+    for (i, step) in STEPS.iter().enumerate() {
+        let title = messages.format(
+            "wizard-step-title",
+            &[("step", &(i + 1)), ("steps", &STEPS.len())],
+        );
+        let labels: Vec<String> = step
+            .choices
+            .iter()
+            .map(|(label, _)| messages.get(label))
+            .collect();
+        let Some(picked) = ask(&title, &messages.get(step.prompt), &labels, &hint)? else {
+            return Ok(None);
+        };
+        match step.choices[picked].1 {
+            Choice::Default => {}
+            Choice::Text(value) => {
+                profile.insert(step.setting.to_string(), value.into());
+            }
+            Choice::Flag(value) => {
+                profile.insert(step.setting.to_string(), value.into());
+            }
+        }
+    }
+    Ok(Some(profile))
+}
+
+// Runs the setup wizard and saves its choices as the default profile in the
+// config file, keeping anything else already in it. Skipping still writes the
+// file, so the wizard isn't offered again.
+pub fn run(path: &Path, messages: &Messages) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.queue(EnterAlternateScreen)?.queue(Hide)?;
+    terminal::enable_raw_mode()?;
+    let profile = ask_all(messages);
+    terminal::disable_raw_mode()?;
+    stdout.queue(Show)?.queue(LeaveAlternateScreen)?;
+    stdout.flush()?;
+
+    let invalid = |e: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid config {}: {}", path.display(), e),
+        )
+    };
+    let existing = match fs::read_to_string(path) {
+        Ok(text) => Some(
+            text.parse::<toml::Table>()
+                .map_err(|e| invalid(e.to_string()))?,
+        ),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+    let profile = profile?;
+    if existing.is_some() && profile.is_none() {
+        return Ok(());
+    }
+
+    let mut config = existing.unwrap_or_default();
+    if let Some(profile) = profile {
+        let profiles = config
+            .entry("profile")
+            .or_insert_with(|| toml::Table::new().into());
+        let Some(profiles) = profiles.as_table_mut() else {
+            return Err(invalid("`profile` isn't a table".to_string()));
+        };
+        profiles.insert(DEFAULT_PROFILE.to_string(), profile.into());
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text = toml::to_string(&config).map_err(|e| invalid(e.to_string()))?;
+    fs::write(path, format!("{}{}", HEADER, text))
+}
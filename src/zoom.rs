@@ -0,0 +1,142 @@
+use clap::ValueEnum;
+
+use crate::game::{Cell, Viewport};
+
+// Block characters for each combination of lit quadrants, indexed by
+// top-left (1), top-right (2), bottom-left (4) and bottom-right (8)
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+// Braille dot bits, indexed by [row][column] within a 2×4 cell
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+const BRAILLE_BASE: u32 = 0x2800;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Zoom {
+    // Each simulated cell is spaced out over 2×2 screen cells
+    In,
+    #[default]
+    Normal,
+    // Each screen cell summarizes 2×2 simulated cells with a quadrant block
+    Quadrants,
+    // Each screen cell summarizes 2×4 simulated cells with a braille pattern
+    Braille,
+}
+
+impl Zoom {
+    pub fn zoom_in(self) -> Self {
+        match self {
+            Zoom::Braille => Zoom::Quadrants,
+            Zoom::Quadrants => Zoom::Normal,
+            Zoom::Normal | Zoom::In => Zoom::In,
+        }
+    }
+
+    pub fn zoom_out(self) -> Self {
+        match self {
+            Zoom::In => Zoom::Normal,
+            Zoom::Normal => Zoom::Quadrants,
+            Zoom::Quadrants | Zoom::Braille => Zoom::Braille,
+        }
+    }
+
+    // Simulated columns and rows summarized by each screen cell when zoomed out
+    fn cell_size(self) -> (u16, u16) {
+        match self {
+            Zoom::In | Zoom::Normal => (1, 1),
+            Zoom::Quadrants => (2, 2),
+            Zoom::Braille => (2, 4),
+        }
+    }
+
+    // Size of the simulation needed to fill a screen of the given size
+    pub fn sim_size(self, width: u16, height: u16) -> (u16, u16) {
+        match self {
+            Zoom::In => (width / 2, height / 2),
+            _ => {
+                let (columns, rows) = self.cell_size();
+                (width.saturating_mul(columns), height.saturating_mul(rows))
+            }
+        }
+    }
+
+    // Converts a screen column to the simulated column drawn there
+    pub fn to_sim_x(self, x: u16) -> u16 {
+        match self {
+            Zoom::In => x / 2,
+            _ => x.saturating_mul(self.cell_size().0),
+        }
+    }
+
+    // Converts a number of screen rows to simulated rows, moving at least one
+    pub fn to_sim_rows(self, rows: i32) -> i32 {
+        match self {
+            Zoom::In => rows.signum() * (rows.abs() / 2).max(1),
+            _ => rows.saturating_mul(self.cell_size().1 as i32),
+        }
+    }
+
+    // Spacing between the columns stacks spawn in. Glyphs are two columns
+    // wide, but zoomed out views don't draw glyphs, so every column is used.
+    pub fn column_step(self) -> u16 {
+        match self {
+            Zoom::In | Zoom::Normal => 2,
+            Zoom::Quadrants | Zoom::Braille => 1,
+        }
+    }
+
+    // Projects the simulated view onto a screen of the given size
+    pub fn project(self, sim: &Viewport, width: u16, height: u16) -> Viewport {
+        let mut view = Viewport::new(width, height);
+        match self {
+            Zoom::Normal => return sim.clone(),
+            Zoom::In => {
+                for y in 0..sim.height() {
+                    for x in 0..sim.width() {
+                        if let Some(cell) = sim.get(x, y) {
+                            view.set(x * 2, y * 2, cell);
+                        }
+                    }
+                }
+            }
+            Zoom::Quadrants | Zoom::Braille => {
+                let (columns, rows) = self.cell_size();
+                for y in 0..height {
+                    for x in 0..width {
+                        let mut bits = 0;
+                        let mut brightest: Option<Cell> = None;
+                        for dy in 0..rows {
+                            for dx in 0..columns {
+                                let Some(cell) = sim.get(x * columns + dx, y * rows + dy) else {
+                                    continue;
+                                };
+                                bits |= match self {
+                                    Zoom::Quadrants => 1 << (dy * 2 + dx),
+                                    _ => BRAILLE_DOTS[dy as usize][dx as usize],
+                                };
+                                if brightest
+                                    .is_none_or(|b| cell.color.brightness() > b.color.brightness())
+                                {
+                                    brightest = Some(cell);
+                                }
+                            }
+                        }
+                        if let Some(cell) = brightest {
+                            let ch = match self {
+                                Zoom::Quadrants => QUADRANTS[bits as usize],
+                                _ => char::from_u32(BRAILLE_BASE + bits).unwrap_or(' '),
+                            };
+                            view.set(
+                                x,
+                                y,
+                                Cell { ch, ..cell },
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        view
+    }
+}
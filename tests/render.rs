@@ -0,0 +1,80 @@
+#![cfg(feature = "vt")]
+
+// End to end checks of what the renderer draws, played through an
+// in-memory terminal as a user's terminal would show it
+
+use std::path::Path;
+use std::time::Duration;
+
+use falling_glyphs::vt::{self, Screen};
+
+const SIZE: (u16, u16) = (80, 24);
+
+// Marks the end of each frame, drawn in a synchronized update
+const FRAME_END: &str = "\x1b[?2026l";
+
+// Runs the rain seeded, a tick per frame, for a number of frames, returning
+// what it wrote for each (and last, what it wrote on exit)
+fn run(options: &[&str], frames: u64) -> Vec<String> {
+    let frames = frames.to_string();
+    let mut args = vec!["--seed", "1", "--lockstep", "--fps", "60"];
+    args.extend(["--frames", &frames]);
+    args.extend(options);
+    let program = Path::new(env!("CARGO_BIN_EXE_falling_glyphs"));
+    let output = vt::capture(program, args, SIZE, Duration::from_secs(30)).unwrap();
+    output
+        .split_inclusive(FRAME_END)
+        .map(str::to_string)
+        .collect()
+}
+
+// The cells with glyphs in them
+fn lit(screen: &Screen) -> Vec<(u16, u16)> {
+    let (columns, rows) = screen.size();
+    (0..rows)
+        .flat_map(|y| (0..columns).map(move |x| (x, y)))
+        .filter(|(x, y)| !matches!(screen.cell(*x, *y).ch, ' ' | '\0'))
+        .collect()
+}
+
+#[test]
+fn exit_restores_the_terminal() {
+    let mut screen = Screen::new(SIZE.0, SIZE.1);
+    for frame in run(&[], 20) {
+        screen.feed(&frame).unwrap();
+    }
+    assert_eq!(screen.mode(1049), Some(false));
+    assert_eq!(screen.mode(25), Some(true));
+    assert_eq!(screen.mode(2026), Some(false));
+    assert!(lit(&screen).is_empty());
+}
+
+#[test]
+fn sixteen_colors_draw_only_basic_color_codes() {
+    let mut screen = Screen::new(SIZE.0, SIZE.1);
+    let frames = run(&["--colors", "16"], 40);
+    let mut seen = 0;
+    for frame in &frames[..frames.len() - 1] {
+        screen.feed(frame).unwrap();
+        for (x, y) in lit(&screen) {
+            let code: u8 = screen.cell(x, y).foreground.parse().unwrap();
+            assert!(matches!(code, 30..=37 | 90..=97), "{} at {},{}", code, x, y);
+            seen += 1;
+        }
+    }
+    assert!(seen > 0);
+}
+
+#[test]
+fn reserved_rows_stay_blank() {
+    let mut screen = Screen::new(SIZE.0, SIZE.1);
+    let frames = run(&["--reserve", "top=2"], 40);
+    let mut seen = 0;
+    for frame in &frames[..frames.len() - 1] {
+        screen.feed(frame).unwrap();
+        let lit = lit(&screen);
+        assert!(lit.iter().all(|(_, y)| *y >= 2), "{:?}", lit);
+        seen += lit.len();
+    }
+    assert!(seen > 0);
+}